@@ -0,0 +1,176 @@
+// allostatic.rs - Allostatic load: chronic stress accumulated over weeks from stressors, sleep and CAR
+use crate::live::{self, DailySummary};
+use crate::sleep;
+use crate::{sleep_quality_score, Event, EventStore};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Days of history the index is accumulated over
+const HISTORY_DAYS: i64 = crate::HISTORY_WINDOW_DAYS;
+/// Time constants of the index: load builds over ~10 days and clears over ~3 weeks
+const BUILD_DAYS: f64 = 10.0;
+const CLEAR_DAYS: f64 = 21.0;
+/// Days of daily index values kept for the trend
+const TREND_DAYS: i64 = 14;
+/// Summed daily stressor severity at which the stress component saturates
+const DAILY_STRESS_SATURATION: f64 = 3.0;
+/// Sleep below this many hours counts as a deficit
+const SLEEP_TARGET_HOURS: f64 = 7.5;
+/// A healthy CAR rises at least this much from the waking sample (50%)
+const HEALTHY_CAR_RISE: f64 = 0.5;
+// Weights of the daily components; renormalized when CAR is not measured
+const STRESS_WEIGHT: f64 = 0.5;
+const SLEEP_WEIGHT: f64 = 0.3;
+const CAR_WEIGHT: f64 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AllostaticStatus {
+    #[default]
+    Low,
+    Elevated,
+    // Sustained load consistent with developing burnout
+    High,
+}
+
+/// One day's inputs and the index at the end of it
+#[derive(Debug, Clone, Serialize)]
+pub struct AllostaticDay {
+    pub date: NaiveDate,
+    // Each 0-1
+    pub stress: f64,
+    pub sleep: f64,
+    // None without salivary cortisol samples that morning
+    pub blunted_car: Option<f64>,
+    pub index: f64,
+}
+
+/// Chronic stress index (0-1) built from uncontrollable stressors, poor sleep and
+/// blunted cortisol awakening responses over the last 6 weeks
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AllostaticLoad {
+    pub index: f64,
+    pub status: AllostaticStatus,
+    // Index change over the last 7 days; positive = accumulating
+    pub weekly_change: Option<f64>,
+    // Last 14 days, oldest first
+    pub trend: Vec<AllostaticDay>,
+}
+
+impl AllostaticLoad {
+    /// Days older than the store's events are read from `summaries` (live feeds)
+    pub fn compute(store: &EventStore, time: DateTime<Utc>, summaries: &BTreeMap<NaiveDate, DailySummary>) -> Self {
+        let first_event = store.until(time).first().map(|e| e.timestamp.date_naive());
+        let Some(first) = summaries.keys().next().copied().into_iter().chain(first_event).min() else {
+            return Self::default();
+        };
+        let start = first.max((time - Duration::days(HISTORY_DAYS)).date_naive());
+        let end = time.date_naive();
+
+        // Whole summarized days only; the store covers the part of the first day in the window
+        let mut stress: BTreeMap<NaiveDate, f64> = summaries
+            .range(start..=end)
+            .filter(|(date, _)| **date > (time - Duration::days(HISTORY_DAYS)).date_naive())
+            .map(|(date, summary)| (*date, summary.stress_severity))
+            .collect();
+        for event in store.of_type("stress_event", time - Duration::days(HISTORY_DAYS), time) {
+            *stress.entry(event.timestamp.date_naive()).or_insert(0.0) += stressor_severity(event);
+        }
+        let sleep_load: BTreeMap<NaiveDate, f64> =
+            live::sleep_days_with_summaries(store, summaries, time - Duration::days(HISTORY_DAYS + 1), time)
+                .iter()
+                .map(|day| (day.date, sleep_deficit(store, summaries, day)))
+                .collect();
+
+        let build = 1.0 - (-1.0 / BUILD_DAYS).exp();
+        let clear = 1.0 - (-1.0 / CLEAR_DAYS).exp();
+        let mut index = 0.0;
+        let mut days = Vec::new();
+        for date in start.iter_days().take_while(|date| *date <= end) {
+            let stress_component = (stress.get(&date).copied().unwrap_or(0.0) / DAILY_STRESS_SATURATION).min(1.0);
+            let sleep_component = sleep_load.get(&date).copied().unwrap_or(0.0);
+            let blunted_car = blunted_car(store, date, time)
+                .or_else(|| summaries.get(&date).and_then(|summary| summary.blunted_car));
+
+            let (weighted, weight) = match blunted_car {
+                Some(car) => (
+                    STRESS_WEIGHT * stress_component + SLEEP_WEIGHT * sleep_component + CAR_WEIGHT * car,
+                    STRESS_WEIGHT + SLEEP_WEIGHT + CAR_WEIGHT,
+                ),
+                None => (STRESS_WEIGHT * stress_component + SLEEP_WEIGHT * sleep_component, STRESS_WEIGHT + SLEEP_WEIGHT),
+            };
+            let daily_load = weighted / weight;
+            index += (daily_load - index) * if daily_load > index { build } else { clear };
+
+            days.push(AllostaticDay {
+                date,
+                stress: stress_component,
+                sleep: sleep_component,
+                blunted_car,
+                index,
+            });
+        }
+
+        let weekly_change = (days.len() > 7).then(|| index - days[days.len() - 8].index);
+        let status = match index {
+            i if i >= 0.45 => AllostaticStatus::High,
+            i if i >= 0.25 => AllostaticStatus::Elevated,
+            _ => AllostaticStatus::Low,
+        };
+        let trend = days.split_off(days.len().saturating_sub(TREND_DAYS as usize));
+
+        AllostaticLoad { index, status, weekly_change, trend }
+    }
+}
+
+/// Severity of one stressor. Uncontrollable and social-evaluative stress drive
+/// allostatic load most; stress the person can act on habituates.
+pub(crate) fn stressor_severity(event: &Event) -> f64 {
+    let intensity = match event.properties.get("intensity").and_then(|v| v.as_str()) {
+        Some("mild") => 0.3,
+        Some("high") => 1.0,
+        Some("severe") => 1.3,
+        _ => 0.6,
+    };
+    let flag = |key: &str| event.properties.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+    let controllability = if flag("controllable") { 0.5 } else { 1.0 };
+    let social = if flag("social_evaluative") { 1.25 } else { 1.0 };
+    intensity * controllability * social
+}
+
+/// 0-1 for a night: the larger of the shortfall against 7.5h (3h short = 1)
+/// and poor quality (fair = 0.5, poor = 1)
+fn sleep_deficit(store: &EventStore, summaries: &BTreeMap<NaiveDate, DailySummary>, day: &sleep::SleepDay) -> f64 {
+    let shortfall = ((SLEEP_TARGET_HOURS - day.total_asleep_hours()) / 3.0).clamp(0.0, 1.0);
+    let period = day.main_period();
+    let quality = store
+        .range(period.start, period.end)
+        .iter()
+        .find(|e| e.event_id == period.primary_event_id)
+        .map(sleep_quality_score)
+        .or_else(|| summaries.get(&day.date).and_then(|summary| summary.sleep_quality))
+        .map(|score| ((0.8 - score) / 0.4).clamp(0.0, 1.0))
+        .unwrap_or(0.0);
+    shortfall.max(quality)
+}
+
+/// How blunted the cortisol awakening response was on `date`, from salivary
+/// samples (`health_cortisol`): the first within 15 min of waking against the
+/// highest 20-60 min after. 0 for a rise of 50% or more, 1 for no rise.
+pub(crate) fn blunted_car(store: &EventStore, date: NaiveDate, until: DateTime<Utc>) -> Option<f64> {
+    let day_start = date.and_hms_opt(0, 0, 0)?.and_utc();
+    let wake = store.of_type("wake", day_start, (day_start + Duration::hours(14)).min(until)).next()?.timestamp;
+    let value = |e: &Event| e.properties.get("value").and_then(|v| v.as_f64());
+
+    let waking = store.of_type("health_cortisol", wake, (wake + Duration::minutes(15)).min(until)).find_map(value)?;
+    let peak = store
+        .of_type("health_cortisol", wake + Duration::minutes(20), (wake + Duration::minutes(60)).min(until))
+        .filter_map(value)
+        .fold(None, |max: Option<f64>, v| Some(max.map_or(v, |m| m.max(v))))?;
+    if waking <= 0.0 {
+        return None;
+    }
+    let rise = peak / waking - 1.0;
+    Some(((HEALTHY_CAR_RISE - rise) / HEALTHY_CAR_RISE).clamp(0.0, 1.0))
+}
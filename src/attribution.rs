@@ -0,0 +1,209 @@
+// attribution.rs - Counterfactual attribution of final scores to individual events
+use crate::live::LiveEstimator;
+use crate::sampling::SplitMix64;
+use crate::{Event, EventStore, EstimationResult, PrimitiveEstimator};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+/// Permutations sampled for the Shapley estimate when none are requested
+pub const DEFAULT_SHAPLEY_PERMUTATIONS: usize = 16;
+
+/// Fixed seed so repeated attributions of the same scenario agree
+const SHAPLEY_SEED: u64 = 0x5EED_A771_B0B5;
+
+/// Final score per primitive, keyed by primitive name
+pub type Scores = BTreeMap<String, f64>;
+
+/// One event's marginal effect on each primitive's final score
+#[derive(Debug, Clone, Serialize)]
+pub struct EventAttribution {
+    pub event_id: String,
+    pub event_type: String,
+    pub timestamp: DateTime<Utc>,
+    // Final score with all events minus final score without this one
+    pub leave_one_out: Scores,
+    // Average marginal effect over random orderings of the events
+    pub shapley: Scores,
+}
+
+/// Effect of a group of events (one event type, or one day)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AttributionGroup {
+    pub event_count: usize,
+    // Final score with all events minus final score without the whole group
+    pub leave_one_out: Scores,
+    // Sum of the members' Shapley values
+    pub shapley: Scores,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AttributionReport {
+    pub timestamp: DateTime<Utc>,
+    pub final_scores: Scores,
+    // Final scores with none of the attributed events (older history kept)
+    pub baseline_scores: Scores,
+    pub permutations: usize,
+    pub events: Vec<EventAttribution>,
+    pub by_event_type: BTreeMap<String, AttributionGroup>,
+    pub by_day: BTreeMap<NaiveDate, AttributionGroup>,
+}
+
+impl AttributionReport {
+    /// Events ordered by the size of their Shapley value for one primitive, largest first
+    pub fn ranked(&self, primitive: &str) -> Vec<&EventAttribution> {
+        let mut ranked: Vec<&EventAttribution> = self.events.iter()
+            .filter(|a| a.shapley.contains_key(primitive))
+            .collect();
+        ranked.sort_by(|a, b| {
+            let a = a.shapley[primitive].abs();
+            let b = b.shapley[primitive].abs();
+            b.partial_cmp(&a).unwrap_or(std::cmp::Ordering::Equal)
+        });
+        ranked
+    }
+}
+
+/// Attribute each primitive's final score (`effective_score` for dopamine and
+/// serotonin, `modified_score` otherwise) to the events that produced it.
+///
+/// Unlike `EventContribution::decayed_impact`, this measures effects after
+/// every pass: sequences, cross-primitive modifiers and physiological
+/// constraints. Events inside the longest primitive context window (plus a
+/// day for the oldest night) are attributed; older history, including what
+/// the multi-week summaries read, is held fixed in every counterfactual.
+///
+/// Shapley values are estimated by sampling `permutations` orderings (paired
+/// with their reverses to reduce variance). They sum to `final - baseline`.
+pub fn attribute_events(
+    estimator: &PrimitiveEstimator,
+    store: &EventStore,
+    estimation_time: DateTime<Utc>,
+    permutations: usize,
+) -> AttributionReport {
+    let window_start = estimation_time - LiveEstimator::retention();
+    // Self-reports never affect estimates, so they are left out of the attribution
+    let players: Vec<Event> = store.range(window_start, estimation_time)
+        .iter()
+        .filter(|e| e.event_type != "self_report")
+        .cloned()
+        .collect();
+    let background: Vec<Event> = store.events()
+        .iter()
+        .filter(|e| e.event_type != "self_report")
+        .filter(|e| e.timestamp < window_start || e.timestamp > estimation_time)
+        .cloned()
+        .collect();
+
+    let evaluate = |store: &EventStore| final_scores(&estimator.run_passes(store, estimation_time));
+    let without = |excluded: &HashSet<&str>| {
+        let kept: Vec<Event> = store.events()
+            .iter()
+            .filter(|e| !excluded.contains(e.event_id.as_str()))
+            .cloned()
+            .collect();
+        evaluate(&EventStore::new(&kept))
+    };
+
+    let final_scores = evaluate(store);
+    let background_store = EventStore::new(&background);
+    let baseline_scores = evaluate(&background_store);
+
+    // Leave-one-out
+    let leave_one_out: Vec<Scores> = players.iter()
+        .map(|event| difference(&final_scores, &without(&HashSet::from([event.event_id.as_str()]))))
+        .collect();
+
+    // Monte Carlo Shapley: add events one at a time in sampled orders
+    let mut shapley: Vec<Scores> = vec![Scores::new(); players.len()];
+    let mut rng = SplitMix64(SHAPLEY_SEED);
+    let mut order: Vec<usize> = (0..players.len()).collect();
+    for k in 0..permutations {
+        if k % 2 == 0 {
+            rng.shuffle(&mut order);
+        } else {
+            order.reverse();
+        }
+
+        let mut coalition = background_store.clone();
+        let mut previous = baseline_scores.clone();
+        for &index in &order {
+            coalition.insert(players[index].clone());
+            let current = evaluate(&coalition);
+            accumulate(&mut shapley[index], &difference(&current, &previous), 1.0 / permutations as f64);
+            previous = current;
+        }
+    }
+
+    let events: Vec<EventAttribution> = players.iter()
+        .zip(leave_one_out)
+        .zip(shapley)
+        .map(|((event, leave_one_out), shapley)| EventAttribution {
+            event_id: event.event_id.clone(),
+            event_type: event.event_type.clone(),
+            timestamp: event.timestamp,
+            leave_one_out,
+            shapley,
+        })
+        .collect();
+
+    let by_event_type = group_by(&events, |a| a.event_type.clone(), &final_scores, &without);
+    let by_day = group_by(&events, |a| a.timestamp.date_naive(), &final_scores, &without);
+
+    AttributionReport {
+        timestamp: estimation_time,
+        final_scores,
+        baseline_scores,
+        permutations,
+        events,
+        by_event_type,
+        by_day,
+    }
+}
+
+fn group_by<K: Ord>(
+    events: &[EventAttribution],
+    key: impl Fn(&EventAttribution) -> K,
+    final_scores: &Scores,
+    without: &impl Fn(&HashSet<&str>) -> Scores,
+) -> BTreeMap<K, AttributionGroup> {
+    let mut members: BTreeMap<K, Vec<&EventAttribution>> = BTreeMap::new();
+    for attribution in events {
+        members.entry(key(attribution)).or_default().push(attribution);
+    }
+
+    members.into_iter()
+        .map(|(group_key, members)| {
+            let excluded: HashSet<&str> = members.iter().map(|a| a.event_id.as_str()).collect();
+            let mut shapley = Scores::new();
+            for attribution in &members {
+                accumulate(&mut shapley, &attribution.shapley, 1.0);
+            }
+            let group = AttributionGroup {
+                event_count: members.len(),
+                leave_one_out: difference(final_scores, &without(&excluded)),
+                shapley,
+            };
+            (group_key, group)
+        })
+        .collect()
+}
+
+fn final_scores(result: &EstimationResult) -> Scores {
+    result.primitives
+        .iter()
+        .map(|(key, state)| (key.clone(), state.final_score()))
+        .collect()
+}
+
+fn difference(a: &Scores, b: &Scores) -> Scores {
+    a.iter()
+        .map(|(key, value)| (key.clone(), value - b.get(key).copied().unwrap_or(0.0)))
+        .collect()
+}
+
+fn accumulate(total: &mut Scores, delta: &Scores, weight: f64) {
+    for (key, value) in delta {
+        *total.entry(key.clone()).or_insert(0.0) += value * weight;
+    }
+}
@@ -1,21 +1,454 @@
 // cli.rs - Command-line interface for neural primitive estimation
-use neurological_primitives::{EventData, Primitive, PrimitiveEstimator};
+use neurological_primitives::{
+    registry::Computation,
+    training::{LoadMethod, TrainingLoad},
+    attribute_events, attribution::DEFAULT_SHAPLEY_PERMUTATIONS, AttributionReport,
+    episodes::{state_timeline, SegmentationOptions, StateTimeline},
+    evaluation::{evaluate, EvaluationReport},
+    performance::{performance_outlook, PerformanceOutlook, PerformancePrediction},
+    recommendations::{recommend, RecommendationReport, UserConstraints},
+    fitting::{fit_parameters, labels_from_measurements, labels_from_self_reports, FitOptions, FitReport},
+    sensitivity::{analyze_sensitivity, SensitivityOptions, SensitivityReport},
+    AllostaticLoad, Chronotype, EstimationTrace, EventData, EventStore, LightReport, ModelParameters, NapReport, Primitive, PrimitiveDefinition, PrimitiveEstimator,
+    PrimitiveRegistry, StateClassifier,
+};
 use chrono::{DateTime, Utc};
 use std::fs;
 
+// Usage: cli [attribute | explain | timeline | performance | recommend | sensitivity | evaluate | fit | primitives] [--adhd] [--params=FILE] [--states=FILE] [--primitives=FILE] [--meq=SCORE | --msf=HOURS] [--json | --csv]
+//   (no argument)  full estimation report
+//   attribute      which events drove each primitive's final score
+//   explain        value of each primitive after every pass, and why it changed
+//   timeline       functional state episodes and transitions over the last 24 hours
+//   performance    predicted cognitive performance hour by hour for the next 24 hours
+//   recommend      ranked recommendations and suppressed actions (--constraints=FILE, --json)
+//   sensitivity    which model parameters the outputs depend on (--json/--csv to export)
+//   evaluate       predictions vs. self_report ratings (--json to export)
+//   fit            fit parameters to self-reports and wearable readings (--out=FILE saves them)
+//   primitives     registered primitives in computation order
+//   --params=FILE  estimate with a parameter file instead of the research defaults
+//   --states=FILE  classify functional states with rules from a JSON file
+//   --primitives=FILE  estimate additional event-driven primitives defined in a JSON file
+//   --meq=SCORE    chronotype from a Morningness-Eveningness Questionnaire score
+//   --msf=HOURS    chronotype from the MCTQ's corrected free-day mid-sleep (--sleep-need=HOURS to add the need)
+
 fn main() {
     let json_data = fs::read_to_string("mock_data.json").expect("Unable to read file");
     let event_data: EventData = serde_json::from_str(&json_data).expect("Unable to parse JSON");
     
-    let estimator = PrimitiveEstimator::new();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let option = |name: &str| args.iter().find_map(|a| a.strip_prefix(name)?.strip_prefix('='));
+
+    let mut estimator = PrimitiveEstimator::with_adhd_mode(args.iter().any(|a| a == "--adhd"));
+    if let Some(path) = option("--params") {
+        let json = fs::read_to_string(path).expect("Unable to read parameter file");
+        estimator = estimator.with_parameters(ModelParameters::from_json(&json).expect("Invalid parameter file"));
+    }
+    if let Some(path) = option("--states") {
+        let json = fs::read_to_string(path).expect("Unable to read state rules");
+        estimator = estimator.with_state_classifier(StateClassifier::from_json(&json).expect("Invalid state rules"));
+    }
+    if let Some(path) = option("--primitives") {
+        let json = fs::read_to_string(path).expect("Unable to read primitive definitions");
+        for definition in PrimitiveDefinition::list_from_json(&json).expect("Invalid primitive definitions") {
+            estimator = estimator.with_primitive(definition).expect("Invalid primitive definition");
+        }
+    }
+    let hours = |name: &str| option(name).map(|v| v.parse::<f64>().unwrap_or_else(|_| panic!("Invalid {} value", name)));
+    let chronotype = match (hours("--meq"), hours("--msf")) {
+        (Some(score), _) => Some(Chronotype::from_meq(score)),
+        (None, Some(msf_sc)) => Some(Chronotype::from_mctq(msf_sc)),
+        (None, None) => None,
+    };
+    if let Some(mut chronotype) = chronotype {
+        if let Some(need) = hours("--sleep-need") {
+            chronotype = chronotype.with_sleep_need(need);
+        }
+        estimator = estimator.with_chronotype(chronotype);
+    }
     
     // Use a timestamp that aligns with the mock data (Jan 18, 2025, 10:00 AM UTC)
     let estimation_time = DateTime::parse_from_rfc3339("2025-01-18T10:00:00Z")
         .expect("Failed to parse estimation time")
         .with_timezone(&Utc);
-    let result = estimator.estimate_at_time(&event_data.events, estimation_time);
-    
-    print_result(&result);
+
+    match args.iter().map(String::as_str).find(|a| !a.starts_with("--")) {
+        Some("attribute") => {
+            let store = EventStore::new(&event_data.events);
+            let report = attribute_events(&estimator, &store, estimation_time, DEFAULT_SHAPLEY_PERMUTATIONS);
+            print_attribution(&report);
+        }
+        Some("explain") => {
+            let store = EventStore::new(&event_data.events);
+            let result = estimator.estimate_with_trace(&store, estimation_time);
+            if let Some(trace) = &result.trace {
+                print_trace(trace, estimator.registry());
+            }
+        }
+        Some("timeline") => {
+            let store = EventStore::new(&event_data.events);
+            let timeline = state_timeline(
+                &estimator,
+                &store,
+                estimation_time - chrono::Duration::hours(24),
+                estimation_time,
+                chrono::Duration::minutes(30),
+                &SegmentationOptions::default(),
+            );
+            print_timeline(&timeline);
+        }
+        Some("performance") => {
+            let store = EventStore::new(&event_data.events);
+            let outlook = performance_outlook(&estimator, &store, estimation_time, 24);
+            if args.iter().any(|a| a == "--json") {
+                println!("{}", serde_json::to_string_pretty(&outlook).unwrap_or_default());
+            } else {
+                print_performance_outlook(&outlook);
+            }
+        }
+        Some("recommend") => {
+            let constraints = match option("--constraints") {
+                Some(path) => {
+                    let json = fs::read_to_string(path).expect("Unable to read constraints file");
+                    serde_json::from_str(&json).expect("Invalid constraints file")
+                }
+                None => UserConstraints::default(),
+            };
+            let store = EventStore::new(&event_data.events);
+            let report = recommend(&estimator, &store, estimation_time, &constraints);
+            if args.iter().any(|a| a == "--json") {
+                println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+            } else {
+                print_recommendations(&report, true);
+            }
+        }
+        Some("sensitivity") => {
+            let store = EventStore::new(&event_data.events);
+            let report = analyze_sensitivity(&estimator, &store, &[estimation_time], &SensitivityOptions::default())
+                .expect("Sensitivity analysis failed");
+            if args.iter().any(|a| a == "--json") {
+                println!("{}", report.to_json());
+            } else if args.iter().any(|a| a == "--csv") {
+                print!("{}", report.to_csv());
+            } else {
+                print_sensitivity(&report);
+            }
+        }
+        Some("evaluate") => {
+            let report = evaluate(&estimator, std::slice::from_ref(&event_data));
+            if args.iter().any(|a| a == "--json") {
+                println!("{}", report.to_json());
+            } else {
+                print_evaluation(&report);
+            }
+        }
+        Some("fit") => {
+            let datasets = std::slice::from_ref(&event_data);
+            let mut labels = labels_from_self_reports(datasets);
+            labels.extend(labels_from_measurements(&estimator, datasets));
+            let report = fit_parameters(&estimator, datasets, &labels, &FitOptions::default())
+                .expect("Parameter fitting failed");
+            if let Some(path) = option("--out") {
+                fs::write(path, report.parameter_file()).expect("Unable to write parameter file");
+            }
+            if args.iter().any(|a| a == "--json") {
+                println!("{}", report.to_json());
+            } else {
+                print_fit(&report);
+            }
+        }
+        Some("primitives") => print_registry(estimator.registry()),
+        _ => {
+            let result = estimator.estimate_at_time(&event_data.events, estimation_time);
+            print_result(&result);
+            let store = EventStore::new(&event_data.events);
+            let report = recommend(&estimator, &store, estimation_time, &UserConstraints::default());
+            print_recommendations(&report, false);
+        }
+    }
+}
+
+fn print_registry(registry: &PrimitiveRegistry) {
+    println!("\n╔══════════════════════════════════════════════════════════════════╗");
+    println!("║             REGISTERED PRIMITIVES (computation order)            ║");
+    println!("╚══════════════════════════════════════════════════════════════════╝\n");
+
+    println!("  {:<18} {:>8} {:>8} {:>10}  {:<14} depends on", "primitive", "baseline", "window", "half-life", "computation");
+    for definition in registry.definitions() {
+        let computation = match &definition.computation {
+            Computation::Builtin(_) => "built-in".to_string(),
+            Computation::EventImpacts(impacts) => format!("{} event types", impacts.len()),
+            Computation::Derived(_) => "derived".to_string(),
+        };
+        println!("  {:<18} {:>8.2} {:>7}h {:>9.2}h  {:<14} {}",
+            definition.name,
+            definition.baseline,
+            definition.context.window_hours,
+            definition.context.decay_half_life_hours,
+            computation,
+            definition.dependencies.join(", "));
+    }
+}
+
+fn print_evaluation(report: &EvaluationReport) {
+    println!("\n╔══════════════════════════════════════════════════════════════════╗");
+    println!("║              MODEL EVALUATION (vs. self-reports)                 ║");
+    println!("╚══════════════════════════════════════════════════════════════════╝\n");
+
+    if report.sample_count == 0 {
+        println!("No self_report events found.");
+        return;
+    }
+
+    println!("Ratings compared: {}\n", report.sample_count);
+    println!("  {:<12} {:>5} {:>8} {:>7} {:>7}", "dimension", "n", "r", "MAE", "bias");
+    for (dimension, metrics) in &report.overall {
+        let correlation = metrics.correlation.map(|r| format!("{:.2}", r)).unwrap_or_else(|| "-".to_string());
+        println!("  {:<12} {:>5} {:>8} {:>7.3} {:>+7.3}", dimension, metrics.count, correlation, metrics.mae, metrics.bias);
+    }
+
+    println!("\nCalibration (mean predicted → mean reported):");
+    for (dimension, metrics) in &report.overall {
+        let bins: Vec<String> = metrics.calibration
+            .iter()
+            .map(|bin| format!("{:.2}→{:.2} (n={})", bin.mean_predicted, bin.mean_observed, bin.count))
+            .collect();
+        println!("  {:<12} {}", dimension, bins.join("  "));
+    }
+
+    if report.per_user.len() > 1 {
+        println!("\nPer user (MAE):");
+        for (user_id, dimensions) in &report.per_user {
+            let maes: Vec<String> = dimensions.iter().map(|(d, m)| format!("{} {:.3}", d, m.mae)).collect();
+            println!("  {}: {}", user_id, maes.join(", "));
+        }
+    }
+
+    let states = &report.functional_state;
+    if let Some(accuracy) = states.accuracy {
+        println!("\nFunctional state agreement: {}/{} ({:.0}%)", states.matches, states.count, accuracy * 100.0);
+        for (reported, predicted) in &states.confusion {
+            for (state, count) in predicted {
+                println!("  reported {:<24} predicted {:<24} {}", reported, state, count);
+            }
+        }
+    }
+
+    for diagnostic in &report.diagnostics {
+        println!("⚠️  {} ({}): {}", diagnostic.event_id, diagnostic.event_type, diagnostic.message);
+    }
+}
+
+fn print_timeline(timeline: &StateTimeline) {
+    println!("\n╔══════════════════════════════════════════════════════════════════╗");
+    println!("║                FUNCTIONAL STATES (last 24 hours)                 ║");
+    println!("╚══════════════════════════════════════════════════════════════════╝\n");
+
+    for episode in &timeline.episodes {
+        println!("  {} → {}  {:<26} {:>5.1}h  (mean p {:.0}%)",
+            episode.start.format("%a %H:%M"), episode.end.format("%a %H:%M"),
+            episode.state_type, episode.dwell_hours, episode.mean_probability * 100.0);
+    }
+
+    if !timeline.transitions.is_empty() {
+        println!("\nTransitions:");
+        for transition in &timeline.transitions {
+            println!("  {} (from {})", transition.summary, transition.from);
+        }
+    }
+}
+
+fn print_performance(performance: &PerformancePrediction) {
+    println!("Performance Index: {:.2} (limited by {})", performance.performance_index, performance.limiting_factor);
+    println!("  PVT lapses (10 min):  {:.1}", performance.pvt_lapses);
+    println!("  Reaction time:        {:.0} ms", performance.reaction_time_ms);
+    println!("  Working memory:       {:.1} items", performance.working_memory_items);
+    println!("  Sleepiness (KSS 1-9): {:.1}", performance.sleepiness_kss);
+    println!("  Sleep debt (7 days):  {:.1}h", performance.sleep_debt_hours);
+}
+
+fn print_performance_outlook(outlook: &PerformanceOutlook) {
+    println!("\n╔══════════════════════════════════════════════════════════════════╗");
+    println!("║             PREDICTED PERFORMANCE (next 24 hours)                ║");
+    println!("╚══════════════════════════════════════════════════════════════════╝\n");
+
+    println!("  {:<10} {:>6} {:>8} {:>8} {:>6} {:>5}", "time", "index", "lapses", "RT ms", "WM", "KSS");
+    for hour in &outlook.hourly {
+        if hour.asleep {
+            println!("  {:<10} asleep", hour.timestamp.format("%a %H:%M"));
+            continue;
+        }
+        let p = &hour.prediction;
+        println!("  {:<10} {:>6.2} {:>8.1} {:>8.0} {:>6.1} {:>5.1}",
+            hour.timestamp.format("%a %H:%M"), p.performance_index, p.pvt_lapses,
+            p.reaction_time_ms, p.working_memory_items, p.sleepiness_kss);
+    }
+
+    for (label, window) in [("Best", &outlook.best_window), ("Worst", &outlook.worst_window)] {
+        if let Some(window) = window {
+            println!("\n{} window: {}–{} (mean index {:.2})", label,
+                window.start.format("%a %H:%M"), window.end.format("%H:%M"), window.mean_performance_index);
+        }
+    }
+}
+
+fn print_fit(report: &FitReport) {
+    println!("\n╔══════════════════════════════════════════════════════════════════╗");
+    println!("║                  PARAMETER FIT (Nelder-Mead)                     ║");
+    println!("╚══════════════════════════════════════════════════════════════════╝\n");
+
+    println!("Labels: {} | evaluations: {}{} | regularization: {}\n",
+        report.label_count, report.evaluations,
+        if report.converged { "" } else { " (not converged)" }, report.regularization);
+
+    println!("  {:<38} {:>9} {:>9} {:>9}", "parameter", "default", "initial", "fitted");
+    for parameter in &report.parameters {
+        println!("  {:<38} {:>9.3} {:>9.3} {:>9.3}",
+            parameter.name, parameter.default_value, parameter.initial_value, parameter.fitted_value);
+    }
+
+    println!("\nMSE: {:.4} → {:.4} (training)", report.initial_mse, report.fitted_mse);
+    if let Some(cv) = &report.cross_validation {
+        println!("MSE: {:.4} → {:.4} (held-out days)", cv.initial_mse, cv.fitted_mse);
+        for fold in &cv.folds {
+            println!("  {}  {:>3} labels  {:.4} → {:.4}", fold.held_out_day, fold.test_labels, fold.initial_mse, fold.fitted_mse);
+        }
+    }
+}
+
+fn print_sensitivity(report: &SensitivityReport) {
+    println!("\n╔══════════════════════════════════════════════════════════════════╗");
+    println!("║                  PARAMETER SENSITIVITY (Morris)                  ║");
+    println!("╚══════════════════════════════════════════════════════════════════╝\n");
+
+    println!("Range: nominal ±{:.0}% | {} trajectories, {} levels\n",
+        report.relative_range * 100.0, report.trajectories, report.levels);
+
+    println!("Parameters by influence (largest mu* across primitives):");
+    for (i, (name, mu_star)) in report.ranked_parameters().iter().enumerate() {
+        println!("  {:>2}. {:<38} {:.4}", i + 1, name, mu_star);
+    }
+
+    println!("\nTop parameter/primitive pairs:");
+    println!("  {:<38} {:<16} {:>9} {:>9} {:>9} {:>9}", "parameter", "primitive", "mu*", "sigma", "d/dp", "elastic");
+    for row in report.rows.iter().filter(|r| r.morris_mu_star > 0.0).take(20) {
+        println!("  {:<38} {:<16} {:>9.4} {:>9.4} {:>9.4} {:>9.3}",
+            row.parameter, row.primitive, row.morris_mu_star, row.morris_sigma, row.derivative, row.elasticity);
+    }
+}
+
+fn print_trace(trace: &EstimationTrace, registry: &PrimitiveRegistry) {
+    println!("\n╔══════════════════════════════════════════════════════════════════╗");
+    println!("║                  ESTIMATION TRACE (explain mode)                 ║");
+    println!("╚══════════════════════════════════════════════════════════════════╝\n");
+
+    for key in registry.names() {
+        let Some(steps) = trace.steps.get(key) else { continue };
+
+        println!("┌─ {}", key.to_uppercase());
+        for step in steps {
+            println!("│  [{:?}] {}: {:.3} → {:.3}", step.pass, step.rule, step.before, step.after);
+            println!("│      {}", step.reason);
+        }
+        println!("└─────────────────────────────────────────────────────────");
+        println!();
+    }
+
+    if let Some(cortisol) = &trace.cortisol {
+        println!("Cortisol internals:");
+        println!("  Allostatic load:               {:.3}", cortisol.allostatic_index);
+        println!("  Circadian multiplier:          {:.3}", cortisol.circadian_multiplier);
+        println!("  Healthy baseline:              {:.3}", cortisol.healthy_baseline);
+        println!("  Event impact (net):            {:+.3}", cortisol.accumulated_impact);
+        println!("  Awakening boost (CAR):         {:.3}x", cortisol.awakening_boost);
+        println!("  Circadian stress sensitivity:  {:.3}", cortisol.circadian_stress_sensitivity);
+        println!("  Stress response:               {:+.3}", cortisol.stress_response);
+        println!("  Relaxation effect:             {:+.3}", cortisol.relaxation_effect);
+        println!();
+    }
+
+    if let Some(caffeine) = &trace.caffeine_dopamine_boost {
+        println!("Caffeine dopamine boost (plasma-tracked): {:+.3} ({})",
+            caffeine.boost,
+            if caffeine.applied { "applied" } else { "not applied; caffeine's dopamine effect enters via its event impact" });
+        println!();
+    }
+
+    if let Some(adhd) = &trace.adhd {
+        println!("ADHD mode:");
+        for (key, baseline) in &adhd.baselines {
+            let modifier = adhd.decay_modifiers.get(key).copied().unwrap_or(1.0);
+            println!("  {}: baseline {:.2}, decays {:.2}x faster", key, baseline, modifier);
+        }
+        println!("  Adenosine/cortisol suppression of dopamine: {:.1}x", adhd.suppression_multiplier);
+        println!();
+    }
+}
+
+fn print_attribution(report: &AttributionReport) {
+    println!("\n╔══════════════════════════════════════════════════════════════════╗");
+    println!("║                EVENT ATTRIBUTION (after all passes)              ║");
+    println!("╚══════════════════════════════════════════════════════════════════╝\n");
+
+    println!("Timestamp: {}", report.timestamp);
+    println!("Shapley permutations: {}\n", report.permutations);
+
+    for primitive in Primitive::all() {
+        let key = primitive.as_str();
+        let final_score = report.final_scores.get(key).copied().unwrap_or(0.0);
+        let baseline = report.baseline_scores.get(key).copied().unwrap_or(0.0);
+
+        println!("┌─ {}", key.to_uppercase());
+        println!("│  Final: {:.3} (without recent events: {:.3})", final_score, baseline);
+
+        let ranked: Vec<_> = report.ranked(key)
+            .into_iter()
+            .filter(|a| a.shapley[key].abs() >= 0.001)
+            .collect();
+        if !ranked.is_empty() {
+            println!("│");
+            println!("│  Top Events (Shapley | leave-one-out):");
+            for (i, attribution) in ranked.iter().take(3).enumerate() {
+                println!("│    {}. {} {} ({}): {:+.3} | {:+.3}",
+                    i + 1,
+                    attribution.event_type,
+                    attribution.event_id,
+                    attribution.timestamp.format("%a %H:%M"),
+                    attribution.shapley[key],
+                    attribution.leave_one_out.get(key).copied().unwrap_or(0.0),
+                );
+            }
+        }
+
+        let mut by_type: Vec<(&String, f64)> = report.by_event_type
+            .iter()
+            .map(|(event_type, group)| (event_type, group.shapley.get(key).copied().unwrap_or(0.0)))
+            .filter(|(_, value)| value.abs() >= 0.001)
+            .collect();
+        by_type.sort_by(|a, b| b.1.abs().partial_cmp(&a.1.abs()).unwrap_or(std::cmp::Ordering::Equal));
+        if !by_type.is_empty() {
+            println!("│");
+            println!("│  By Event Type:");
+            for (event_type, value) in by_type.iter().take(4) {
+                println!("│    {}: {:+.3}", event_type, value);
+            }
+        }
+        println!("└─────────────────────────────────────────────────────────");
+        println!();
+    }
+
+    println!("By Day (Shapley sum per primitive):");
+    for (day, group) in &report.by_day {
+        let effects: Vec<String> = Primitive::all()
+            .iter()
+            .filter_map(|p| {
+                let value = group.shapley.get(p.as_str()).copied().unwrap_or(0.0);
+                (value.abs() >= 0.01).then(|| format!("{} {:+.3}", p.as_str(), value))
+            })
+            .collect();
+        println!("  {} ({} events): {}", day, group.event_count,
+            if effects.is_empty() { "no notable effect".to_string() } else { effects.join(", ") });
+    }
 }
 
 fn print_result(result: &neurological_primitives::EstimationResult) {
@@ -44,52 +477,57 @@ fn print_result(result: &neurological_primitives::EstimationResult) {
         }
     }
 
+    if !result.measurement_diagnostics.is_empty() {
+        println!("⚠️  Rejected measurements:");
+        for diagnostic in &result.measurement_diagnostics {
+            println!("   {} ({}): {}", diagnostic.event_id, diagnostic.event_type, diagnostic.message);
+        }
+        println!();
+    }
+
     println!("\n╔══════════════════════════════════════════════════════════════════╗");
     println!("║                     PRIMITIVE ESTIMATES                          ║");
     println!("╚══════════════════════════════════════════════════════════════════╝\n");
 
-    for primitive in Primitive::all() {
-        let key = primitive.as_str();
-        if let Some(state) = result.primitives.get(key) {
-            let confidence_indicator = if state.confidence < 0.6 {
-                "⚠️ "
-            } else if state.confidence > 0.9 {
-                "✓ "
-            } else {
-                ""
-            };
-            
-            println!("┌─ {} {}", key.to_uppercase(), confidence_indicator);
-            println!("│  Score: {:.3} (Confidence: {:.1}%)", 
-                state.modified_score, state.confidence * 100.0);
-            
-            if let Some(acute) = state.acute_score {
-                println!("│  Acute: {:.3} | Chronic: {:.3}", 
-                    acute, state.chronic_score.unwrap_or(0.0));
-            }
-            if let Some(effective) = state.effective_score {
-                println!("│  Effective (after inhibition): {:.3}", effective);
-            }
-            
-            println!("│  Description: {}", get_level_description(key, state.modified_score));
-            
-            if !state.contributors.is_empty() {
-                println!("│");
-                println!("│  Top Contributors:");
-                for (i, contrib) in state.contributors.iter().enumerate().take(3) {
-                    let sign = if contrib.decayed_impact >= 0.0 { "+" } else { "" };
-                    println!("│    {}. {} ({:.1}h ago): {}{:.3}",
-                        i + 1,
-                        contrib.event_type,
-                        contrib.hours_ago,
-                        sign,
-                        contrib.decayed_impact
-                    );
-                }
+    for (key, state) in &result.primitives {
+        let confidence_indicator = if state.confidence < 0.6 {
+            "⚠️ "
+        } else if state.confidence > 0.9 {
+            "✓ "
+        } else {
+            ""
+        };
+        
+        println!("┌─ {} {}", key.to_uppercase(), confidence_indicator);
+        println!("│  Score: {:.3} (Confidence: {:.1}%)", 
+            state.modified_score, state.confidence * 100.0);
+        
+        if let Some(acute) = state.acute_score {
+            println!("│  Acute: {:.3} | Chronic: {:.3}", 
+                acute, state.chronic_score.unwrap_or(0.0));
+        }
+        if let Some(effective) = state.effective_score {
+            println!("│  Effective (after inhibition): {:.3}", effective);
+        }
+        
+        println!("│  Description: {}", get_level_description(key, state.modified_score));
+        
+        if !state.contributors.is_empty() {
+            println!("│");
+            println!("│  Top Contributors:");
+            for (i, contrib) in state.contributors.iter().enumerate().take(3) {
+                let sign = if contrib.decayed_impact >= 0.0 { "+" } else { "" };
+                println!("│    {}. {} ({:.1}h ago): {}{:.3}",
+                    i + 1,
+                    contrib.event_type,
+                    contrib.hours_ago,
+                    sign,
+                    contrib.decayed_impact
+                );
             }
-            println!("└─────────────────────────────────────────────────────────");
-            println!();
         }
+        println!("└─────────────────────────────────────────────────────────");
+        println!();
     }
 
     println!("\n╔══════════════════════════════════════════════════════════════════╗");
@@ -107,17 +545,164 @@ fn print_result(result: &neurological_primitives::EstimationResult) {
     };
     
     println!("Overall Sleep Drive: {:.3} ({})", result.sleep_drive, sleep_status);
+    let chronotype = &result.chronotype;
+    let clock = |hour: f64| format!("{:02}:{:02}", hour.floor() as u32 % 24, (hour.fract() * 60.0).round() as u32 % 60);
+    println!("Chronotype: {} ({:?}), sleeps best {}–{} ({:.1}h need)",
+        chronotype.category, chronotype.source,
+        clock(chronotype.preferred_bedtime_hour()), clock(chronotype.preferred_wake_hour()), chronotype.sleep_need_hours);
+    let metrics = &result.sleep_metrics;
+    let metric = |value: Option<f64>, unit: &str| value.map_or("n/a".to_string(), |v| format!("{:+.2}{}", v, unit));
+    println!("Sleep regularity: SRI {} | social jet lag {} | midpoint drift {} | bedtime SD {} | wake SD {}",
+        metrics.sleep_regularity_index.map_or("n/a".to_string(), |sri| format!("{:.0}", sri)),
+        metric(metrics.social_jet_lag_hours, "h"),
+        metric(metrics.midpoint_drift_hours_per_day, "h/day"),
+        metrics.bedtime_std_hours.map_or("n/a".to_string(), |sd| format!("{:.2}h", sd)),
+        metrics.wake_time_std_hours.map_or("n/a".to_string(), |sd| format!("{:.2}h", sd)));
+    println!();
+
+    let training = &result.training;
+    if !training.acute_session_ids.is_empty() || training.chronic_load.is_some_and(|load| load > 0.0) {
+        println!("\n╔══════════════════════════════════════════════════════════════════╗");
+        println!("║                   TRAINING LOAD & RECOVERY                       ║");
+        println!("╚══════════════════════════════════════════════════════════════════╝\n");
+        print_training(training);
+        println!();
+    }
+
+    if !result.naps.naps.is_empty() || result.naps.best_window.is_some() {
+        println!("\n╔══════════════════════════════════════════════════════════════════╗");
+        println!("║                            NAPS                                  ║");
+        println!("╚══════════════════════════════════════════════════════════════════╝\n");
+        print_naps(&result.naps);
+        println!();
+    }
+
+    if result.light.measured_minutes > 0.0 || result.light.dose.advancing + result.light.dose.delaying > 0.0 {
+        println!("\n╔══════════════════════════════════════════════════════════════════╗");
+        println!("║                        LIGHT EXPOSURE                            ║");
+        println!("╚══════════════════════════════════════════════════════════════════╝\n");
+        print_light(&result.light);
+        println!();
+    }
+
+    if result.allostatic_load.index > 0.0 {
+        println!("\n╔══════════════════════════════════════════════════════════════════╗");
+        println!("║                       ALLOSTATIC LOAD                            ║");
+        println!("╚══════════════════════════════════════════════════════════════════╝\n");
+        print_allostatic_load(&result.allostatic_load);
+        println!();
+    }
+
+    println!("\n╔══════════════════════════════════════════════════════════════════╗");
+    println!("║                  PREDICTED COGNITIVE PERFORMANCE                 ║");
+    println!("╚══════════════════════════════════════════════════════════════════╝\n");
+
+    print_performance(&result.performance);
+    let inertia = &result.sleep_inertia;
+    if let (Some(source), Some(stage)) = (&inertia.source, inertia.stage_at_waking) {
+        if inertia.level >= 0.01 {
+            println!("Sleep inertia: {:.2} ({:.2} on waking from {:?} sleep{}, {} {:.0} min ago; time constant {:.0} min)",
+                inertia.level, inertia.initial_level, stage,
+                if inertia.stage_inferred { " (inferred)" } else { "" },
+                if source == "nap" { "nap ended" } else { "woke" },
+                inertia.minutes_since_waking, inertia.time_constant_minutes);
+        }
+    }
     println!();
 
     println!("\n╔══════════════════════════════════════════════════════════════════╗");
     println!("║                      INTERPRETATION                              ║");
     println!("╚══════════════════════════════════════════════════════════════════╝\n");
 
-    println!("Functional State: {}", result.functional_state.state_type);
-    println!("{}", result.functional_state.description);
+    let state = &result.functional_state;
+    println!("Functional State: {} ({:.0}% likely)", state.state_type, state.probability * 100.0);
+    println!("{}", state.description);
+    for driver in &state.drivers {
+        println!("  • {} {:.3} ({})", driver.input, driver.value, driver.requirement);
+    }
+    if let Some(runner_up) = state.alternatives.first() {
+        println!("  Next most likely: {} ({:.0}%)", runner_up.state_type, runner_up.probability * 100.0);
+    }
+}
+
+fn print_training(training: &TrainingLoad) {
+    let units = match training.method {
+        LoadMethod::SessionRpe => "AU/day (session RPE)",
+        LoadMethod::Trimp => "TRIMP/day",
+    };
+    println!("Acute load (7d):   {:.0} {} across {} sessions", training.acute_load, units, training.acute_session_ids.len());
+    match (training.chronic_load, training.acute_chronic_ratio) {
+        (Some(chronic), Some(ratio)) => println!("Chronic load (28d): {:.0} → acute:chronic ratio {:.2}", chronic, ratio),
+        (Some(chronic), None) => println!("Chronic load (28d): {:.0}", chronic),
+        _ => println!("Chronic load (28d): n/a (under 14 days of history)"),
+    }
+    let hrv = training.hrv_trend.map_or("n/a".to_string(), |trend| format!("{:+.0}% vs baseline", (trend - 1.0) * 100.0));
+    let sleep = training.recent_sleep_hours.map_or("n/a".to_string(), |hours| format!("{:.1}h", hours));
+    println!("Recovery: {:.2} ({:?}) | morning HRV {} | sleep (3 nights) {}",
+        training.recovery_score, training.recovery_status, hrv, sleep);
+    if training.overreaching {
+        println!("⚠️  Overreaching: sustained high load with falling HRV and poor sleep");
+    }
+}
+
+fn print_naps(report: &NapReport) {
+    for nap in &report.naps {
+        println!("{} {:.0} min nap: {:?} ({:+.1}h from the circadian dip, {:.1}h before bedtime), adenosine {:+.2}{}{}",
+            nap.start.format("%H:%M"), nap.duration_minutes, nap.timing, nap.hours_from_dip, nap.hours_before_bedtime,
+            nap.adenosine_clearance,
+            if nap.onset_delay_minutes > 0.0 { format!(", delays sleep onset ~{:.0} min", nap.onset_delay_minutes) } else { String::new() },
+            if nap.caffeine_nap { ", caffeine nap" } else { "" });
+    }
+    match &report.best_window {
+        Some(window) => println!("Best nap window today: {}–{} (sleep propensity {:.2})",
+            window.start.format("%H:%M"), window.end.format("%H:%M"), window.sleep_propensity),
+        None => println!("Best nap window today: none (too close to bedtime)"),
+    }
+}
+
+fn print_light(report: &LightReport) {
+    println!("Last 24h: {:.0} min phase-advancing and {:.0} min phase-delaying light (full-drive minutes), {:.0} min of daylight",
+        report.dose.advancing, report.dose.delaying, report.daylight_minutes);
+    if let Some(mean) = report.mean_melanopic_edi {
+        println!("Wearable: {:.1}h measured, mean {:.0} melanopic lux", report.measured_minutes / 60.0, mean);
+    }
+    for episode in &report.daylight_episodes {
+        println!("  {}–{} daylight, {:.0} min at {:.0} lux ({:.0} melanopic lux)",
+            episode.start.format("%H:%M"), episode.end.format("%H:%M"), episode.minutes, episode.mean_lux, episode.mean_melanopic_edi);
+    }
+}
+
+fn print_allostatic_load(load: &AllostaticLoad) {
+    let weekly = load.weekly_change.map_or("n/a".to_string(), |change| format!("{:+.3}", change));
+    println!("Index: {:.3} ({:?}) | change over 7 days {}", load.index, load.status, weekly);
+    println!("\n  Date        Stress  Sleep   CAR     Index");
+    for day in &load.trend {
+        let car = day.blunted_car.map_or("  -   ".to_string(), |car| format!("{:.2}  ", car));
+        println!("  {}  {:.2}    {:.2}    {}  {:.3}", day.date, day.stress, day.sleep, car, day.index);
+    }
+}
+
+fn print_recommendations(report: &RecommendationReport, show_suppressed: bool) {
     println!("\nRecommendations:");
-    for (i, rec) in result.functional_state.recommendations.iter().enumerate() {
-        println!("  {}. {}", i + 1, rec);
+    for (i, rec) in report.recommendations.iter().enumerate() {
+        println!("  {}. {} ({}–{})", i + 1, rec.title, rec.window_start.format("%H:%M"), rec.window_end.format("%H:%M"));
+        println!("     {}", rec.rationale);
+        let effects: Vec<String> = rec.expected_effects.iter()
+            .map(|(primitive, effect)| format!("{} {:+.2}", primitive, effect))
+            .collect();
+        if !effects.is_empty() {
+            println!("     Expected: {}", effects.join(", "));
+        }
+    }
+    if report.recommendations.is_empty() {
+        println!("  Nothing to change right now.");
+    }
+
+    if show_suppressed && !report.suppressed.is_empty() {
+        println!("\nNot recommended:");
+        for suppressed in &report.suppressed {
+            println!("  • {:?}: {}", suppressed.action, suppressed.reason);
+        }
     }
 }
 
@@ -189,6 +774,18 @@ fn get_level_description(primitive_key: &str, score: f64) -> String {
                 "Very low (depleted)".to_string()
             }
         },
+        "melatonin" => {
+            if score >= 0.7 {
+                "High (sleep gate open)".to_string()
+            } else if score >= 0.4 {
+                "Rising (winding down)".to_string()
+            } else if score >= 0.15 {
+                "Low (onset suppressed or not yet due)".to_string()
+            } else {
+                "Daytime (no sleep signal)".to_string()
+            }
+        },
+        // Registered primitives carry no built-in interpretation
         _ => format!("{:.3}", score)
     }
 }
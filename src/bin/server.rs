@@ -5,10 +5,13 @@ use chrono::{DateTime, Duration, Utc};
 use neurological_primitives::{
     generate_profile_events, get_all_profiles, PrimitiveEstimator,
     chat::ChatRequest,
-    Event,
+    episodes::{segment_states, SegmentationOptions, StateTimeline},
+    performance::{performance_outlook, PerformanceOutlook, PerformancePrediction},
+    recommendations::{recommend, RecommendationReport, UserConstraints},
+    Event, EventStore,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use dotenv::dotenv;
 
 #[derive(Debug, Deserialize)]
@@ -22,18 +25,29 @@ struct EstimateRequest {
     timezone_offset_minutes: Option<i64>,
     #[serde(default)]
     adhd_mode: bool,
+    // Bedtime, caffeine limits and actions to avoid when recommending
+    #[serde(default)]
+    constraints: Option<UserConstraints>,
 }
 
 #[derive(Debug, Serialize)]
 struct TimelinePoint {
     timestamp: DateTime<Utc>,
-    primitives: HashMap<String, f64>,
+    primitives: BTreeMap<String, f64>,
+    functional_state: String,
+    performance: PerformancePrediction,
 }
 
 #[derive(Debug, Serialize)]
 struct EstimateResponse {
     timeline: Vec<TimelinePoint>,
+    // Functional state episodes and transitions across the timeline
+    states: StateTimeline,
     final_state: serde_json::Value,
+    // Hourly performance for the next 24 hours, with best and worst windows
+    performance: PerformanceOutlook,
+    // Ranked actions for the end of the timeline
+    recommendations: RecommendationReport,
 }
 
 #[derive(Debug, Deserialize)]
@@ -71,9 +85,9 @@ async fn get_profiles(query: web::Query<ProfilesQuery>) -> impl Responder {
 
 async fn estimate_profile(req: web::Json<EstimateRequest>) -> impl Responder {
     // Generate events for the profile
-    // Add lookback padding so long context windows (e.g., circadian 7d) have history
+    // Add lookback padding so long context windows (e.g., circadian 7d, chronic training load 28d) have history
     let display_days: i64 = 4;
-    let padding_days_before: i64 = 7; // matches max ContextConfig window (168h)
+    let padding_days_before: i64 = 28; // matches the chronic training-load window (28 days)
     let event_data = generate_profile_events(&req.profile_id, display_days + padding_days_before);
     
     // NOTE: We do NOT shift events for estimation. Profile events are generated with
@@ -94,40 +108,46 @@ async fn estimate_profile(req: web::Json<EstimateRequest>) -> impl Responder {
     
     // Generate timeline
     let mut timeline = Vec::new();
+    let mut states = Vec::new();
     let start_time = fake_utc_now - Duration::days(display_days);
     let end_time = fake_utc_now;
     
+    let store = EventStore::new(&events_for_estimation);
     let mut current_time = start_time;
     while current_time <= end_time {
-        let result = estimator.estimate_at_time(&events_for_estimation, current_time);
+        let result = estimator.estimate_with_store(&store, current_time);
         
         // Extract primitive scores
-        let mut primitives = HashMap::new();
+        let mut primitives = BTreeMap::new();
         for (key, state) in &result.primitives {
             // For dopamine and serotonin, use effective_score (after reciprocal inhibition) if available
-            let score = if (key == "dopamine" || key == "serotonin") && state.effective_score.is_some() {
-                state.effective_score.unwrap()
-            } else {
-                state.modified_score
-            };
-            primitives.insert(key.clone(), score);
+            primitives.insert(key.clone(), state.final_score());
         }
         
         timeline.push(TimelinePoint {
             timestamp: current_time,
             primitives,
+            functional_state: result.functional_state.state_type.clone(),
+            performance: result.performance.clone(),
         });
+        states.push((current_time, result.functional_state));
         
-        current_time = current_time + Duration::hours(req.resolution_hours);
+        current_time += Duration::hours(req.resolution_hours);
     }
     
     // Get final state
-    let final_result = estimator.estimate_at_time(&events_for_estimation, end_time);
+    let final_result = estimator.estimate_with_store(&store, end_time);
     let final_state = serde_json::to_value(&final_result).unwrap();
+    let constraints = req.constraints.clone().unwrap_or_default();
+    let recommendations = recommend(&estimator, &store, end_time, &constraints);
+    let performance = performance_outlook(&estimator, &store, end_time, 24);
     
     HttpResponse::Ok().json(EstimateResponse {
         timeline,
+        states: segment_states(&states, &SegmentationOptions::default()),
         final_state,
+        performance,
+        recommendations,
     })
 }
 
@@ -1,7 +1,10 @@
 // chat.rs - Enhanced chat bridge with full neurological context
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use crate::{PrimitiveEstimator, EstimationResult};
+use crate::episodes::{state_timeline, SegmentationOptions, StateTimeline};
+use crate::recommendations::{recommend, RecommendationReport, UserConstraints};
+use crate::{EstimationResult, EventStore, PrimitiveEstimator};
+use chrono::Timelike;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatMessage {
@@ -44,23 +47,145 @@ struct OpenAIChoice {
 }
 
 /// Generate a comprehensive context string from estimation results
-fn format_neurological_context(result: &EstimationResult, recent_events: &[crate::Event], current_time: chrono::DateTime<chrono::Utc>) -> String {
+fn format_neurological_context(
+    result: &EstimationResult,
+    arc: &StateTimeline,
+    recommendations: &RecommendationReport,
+    recent_events: &[crate::Event],
+    current_time: chrono::DateTime<chrono::Utc>,
+) -> String {
     let mut context = String::new();
     
     // 1. Current functional state
     context.push_str(&format!(
-        "CURRENT STATE: {}\n{}\n\n",
+        "CURRENT STATE: {} ({:.0}% likely)\n{}\n",
         result.functional_state.state_type,
+        result.functional_state.probability * 100.0,
         result.functional_state.description
     ));
+    let drivers: Vec<String> = result.functional_state.drivers.iter()
+        .map(|d| format!("{} {:.2} ({})", d.input, d.value, d.requirement))
+        .collect();
+    if !drivers.is_empty() {
+        context.push_str(&format!("Driven by: {}\n", drivers.join(", ")));
+    }
+    context.push('\n');
+    
+    // 2. Arc of the day: state episodes since midnight
+    if !arc.episodes.is_empty() {
+        context.push_str("TODAY'S ARC:\n");
+        for episode in &arc.episodes {
+            context.push_str(&format!(
+                "- {}-{} {} ({:.1}h)\n",
+                episode.start.format("%H:%M"),
+                episode.end.format("%H:%M"),
+                episode.state_type,
+                episode.dwell_hours
+            ));
+        }
+        for transition in &arc.transitions {
+            context.push_str(&format!("- {} (from {})\n", transition.summary, transition.from));
+        }
+        context.push('\n');
+    }
     
-    // 2. Key metrics
+    // 3. Key metrics
     context.push_str("KEY METRICS:\n");
     context.push_str(&format!("- Sleep Drive: {:.1}% (0=alert, 100=exhausted)\n", result.sleep_drive * 100.0));
     context.push_str(&format!("- Dopamine/Serotonin Ratio: {:.2}\n", result.dopamine_serotonin_ratio));
-    context.push_str("\n");
+    context.push_str(&format!(
+        "- Chronotype: {} (mid-sleep {:.1}h after midnight, {:.1}h sleep need, {:?})\n",
+        result.chronotype.category,
+        result.chronotype.mid_sleep_hour,
+        result.chronotype.sleep_need_hours,
+        result.chronotype.source
+    ));
+    let metrics = &result.sleep_metrics;
+    let metric = |name: &str, value: Option<f64>, unit: &str| {
+        value.map(|v| format!("{} {:.1}{}", name, v, unit))
+    };
+    let regularity: Vec<String> = [
+        metric("Sleep Regularity Index", metrics.sleep_regularity_index, "/100"),
+        metric("social jet lag", metrics.social_jet_lag_hours, "h"),
+        metric("mid-sleep drift", metrics.midpoint_drift_hours_per_day, "h/day"),
+        metric("bedtime SD", metrics.bedtime_std_hours, "h"),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if !regularity.is_empty() {
+        context.push_str(&format!("- Sleep Regularity: {}\n", regularity.join(", ")));
+    }
+    let training = &result.training;
+    if let Some(ratio) = training.acute_chronic_ratio {
+        context.push_str(&format!(
+            "- Training: acute:chronic load ratio {:.2}, recovery {:.0}% ({:?}){}\n",
+            ratio,
+            training.recovery_score * 100.0,
+            training.recovery_status,
+            if training.overreaching { ", OVERREACHING (high load, falling HRV, poor sleep)" } else { "" }
+        ));
+    }
+    let allostatic = &result.allostatic_load;
+    if allostatic.index > 0.0 {
+        context.push_str(&format!(
+            "- Allostatic Load: {:.2} ({:?}){}\n",
+            allostatic.index,
+            allostatic.status,
+            allostatic
+                .weekly_change
+                .map_or(String::new(), |change| format!(", {:+.2} over the last week", change))
+        ));
+    }
+    let naps = &result.naps;
+    if let Some(window) = &naps.best_window {
+        context.push_str(&format!(
+            "- Best Nap Window: {}-{} today (20 minutes, sleep propensity {:.2})\n",
+            window.start.format("%H:%M"),
+            window.end.format("%H:%M"),
+            window.sleep_propensity
+        ));
+    }
+    if naps.onset_delay_minutes >= 5.0 {
+        context.push_str(&format!(
+            "- Late Naps: expected to delay tonight's sleep onset by ~{:.0} min\n",
+            naps.onset_delay_minutes
+        ));
+    }
+    let light = &result.light;
+    if light.measured_minutes > 0.0 || light.daylight_minutes > 0.0 {
+        context.push_str(&format!(
+            "- Light (24h): {:.0} min of daylight{}\n",
+            light.daylight_minutes,
+            light
+                .mean_melanopic_edi
+                .map_or(String::new(), |mean| format!(", mean {:.0} melanopic lux over {:.1}h measured", mean, light.measured_minutes / 60.0))
+        ));
+    }
+    let inertia = &result.sleep_inertia;
+    if inertia.level >= 0.05 {
+        context.push_str(&format!(
+            "- Sleep Inertia: {:.2}, {:.0} min after waking{} (grogginess that clears over the next 15-60 min)\n",
+            inertia.level,
+            inertia.minutes_since_waking,
+            if inertia.source.as_deref() == Some("nap") { " from a nap" } else { "" }
+        ));
+    }
+    let performance = &result.performance;
+    context.push_str(&format!(
+        "- Predicted Performance: {:.0}% (limited by {}); {:.1} PVT lapses, {:.0} ms reaction time, \
+{:.1}-item working memory, sleepiness {:.1}/9 KSS, {:.1}h sleep debt this week\n",
+        performance.performance_index * 100.0,
+        performance.limiting_factor,
+        performance.pvt_lapses,
+        performance.reaction_time_ms,
+        performance.working_memory_items,
+        performance.sleepiness_kss,
+        performance.sleep_debt_hours
+    ));
+    context.push('\n');
     
-    // 3. Neurological primitives with interpretations
+    // 4. Neurological primitives with interpretations
     context.push_str("NEUROLOGICAL PRIMITIVES (0.0-1.0 scale):\n");
     
     for (name, state) in &result.primitives {
@@ -92,7 +217,7 @@ fn format_neurological_context(result: &EstimationResult, recent_events: &[crate
                         contrib.decayed_impact
                     ));
                 }
-                context.push_str("\n");
+                context.push('\n');
             }
         }
         
@@ -104,9 +229,9 @@ fn format_neurological_context(result: &EstimationResult, recent_events: &[crate
             ));
         }
     }
-    context.push_str("\n");
+    context.push('\n');
     
-    // 4. Detected patterns
+    // 5. Detected patterns
     if !result.detected_sequences.is_empty() {
         context.push_str("DETECTED PATTERNS:\n");
         for seq in &result.detected_sequences {
@@ -117,10 +242,10 @@ fn format_neurological_context(result: &EstimationResult, recent_events: &[crate
                 seq.adjustment
             ));
         }
-        context.push_str("\n");
+        context.push('\n');
     }
     
-    // 5. Recent significant events (last 12 hours)
+    // 6. Recent significant events (last 12 hours)
     let now = current_time;
     let recent_cutoff = now - chrono::Duration::hours(12);
     let recent: Vec<_> = recent_events
@@ -177,16 +302,28 @@ fn format_neurological_context(result: &EstimationResult, recent_events: &[crate
                 }
                 _ => {}
             }
-            context.push_str("\n");
+            context.push('\n');
         }
-        context.push_str("\n");
+        context.push('\n');
     }
     
-    // 6. Recommendations from functional state
-    if !result.functional_state.recommendations.is_empty() {
+    // 7. Recommendations ranked by simulated effect, and what to avoid
+    if !recommendations.recommendations.is_empty() {
         context.push_str("RECOMMENDATIONS:\n");
-        for rec in &result.functional_state.recommendations {
-            context.push_str(&format!("- {}\n", rec));
+        for rec in &recommendations.recommendations {
+            context.push_str(&format!(
+                "- {} ({}-{}): {}\n",
+                rec.title,
+                rec.window_start.format("%H:%M"),
+                rec.window_end.format("%H:%M"),
+                rec.rationale
+            ));
+        }
+    }
+    if !recommendations.suppressed.is_empty() {
+        context.push_str("NOT RECOMMENDED NOW:\n");
+        for suppressed in &recommendations.suppressed {
+            context.push_str(&format!("- {:?}: {}\n", suppressed.action, suppressed.reason));
         }
     }
     
@@ -238,7 +375,13 @@ fn interpret_primitive_score(primitive: &str, score: f64) -> &'static str {
             else if score > 0.3 { "Some circadian disruption" }
             else { "Significant circadian misalignment" }
         }
-        _ => "Unknown primitive"
+        "melatonin" => {
+            if score > 0.7 { "Night-time melatonin, sleep gate open" }
+            else if score > 0.4 { "Melatonin rising, winding down" }
+            else if score > 0.15 { "Low melatonin, onset suppressed or not yet due" }
+            else { "Daytime levels, no sleep signal" }
+        }
+        _ => "Registered primitive, no built-in interpretation"
     }
 }
 
@@ -284,13 +427,14 @@ pub async fn chat_reply(req: ChatRequest) -> Result<ChatResponse, String> {
     
     // Run the estimator to get current state
     let estimator = PrimitiveEstimator::new();
-    let estimation = estimator.estimate_at_time(
-        &event_data.events,
-        fake_utc_now,
-    );
+    let store = EventStore::new(&event_data.events);
+    let estimation = estimator.estimate_with_store(&store, fake_utc_now);
     
     // Generate rich neurological context
-    let neuro_context = format_neurological_context(&estimation, &event_data.events, fake_utc_now);
+    let midnight = fake_utc_now - chrono::Duration::seconds(fake_utc_now.num_seconds_from_midnight() as i64);
+    let arc = state_timeline(&estimator, &store, midnight, fake_utc_now, chrono::Duration::minutes(30), &SegmentationOptions::default());
+    let report = recommend(&estimator, &store, fake_utc_now, &UserConstraints::default());
+    let neuro_context = format_neurological_context(&estimation, &arc, &report, store.events(), fake_utc_now);
     
     // Create system message with context
     let system_message = format!(
@@ -369,7 +513,7 @@ Current time: {} {}",
             .map_err(|e| format!("decode error: {}", e))?;
         let reply = parsed
             .choices
-            .get(0)
+            .first()
             .map(|c| c.message.content.clone())
             .unwrap_or_else(|| "No reply.".to_string());
         return Ok(ChatResponse { reply });
@@ -424,10 +568,10 @@ Current time: {} {}",
     }
     let reply = v.get("output")
         .and_then(|o| o.as_array())
-        .and_then(|arr| arr.get(0))
+        .and_then(|arr| arr.first())
         .and_then(|first| first.get("content"))
         .and_then(|c| c.as_array())
-        .and_then(|carr| carr.get(0))
+        .and_then(|carr| carr.first())
         .and_then(|c0| c0.get("text"))
         .and_then(|t| t.as_str())
         .unwrap_or("No reply.");
@@ -0,0 +1,146 @@
+// chronotype.rs - Chronotype and sleep need, set from questionnaires or inferred from free days
+use crate::live::{self, DailySummary};
+use crate::sleep;
+use crate::EventStore;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Mid-sleep assumed without a questionnaire or enough free days (03:00)
+const DEFAULT_MID_SLEEP_HOUR: f64 = 3.0;
+const DEFAULT_SLEEP_NEED_HOURS: f64 = 8.0;
+/// How far back free days are looked for when inferring
+const INFERENCE_LOOKBACK_DAYS: i64 = 28;
+const _: () = assert!(INFERENCE_LOOKBACK_DAYS <= crate::HISTORY_WINDOW_DAYS);
+/// Free days needed before an inferred chronotype replaces the default
+const MIN_FREE_DAYS: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChronotypeSource {
+    // Morningness-Eveningness Questionnaire score
+    Meq,
+    // Munich ChronoType Questionnaire MSFsc
+    Mctq,
+    // Sleep midpoints on free days in the event history
+    Inferred,
+    Default,
+}
+
+/// When a person sleeps best if left to themselves, and how long
+#[derive(Debug, Clone, Serialize)]
+pub struct Chronotype {
+    // Preferred mid-sleep in hours around midnight (-12..12), e.g. 4.5 = 04:30
+    pub mid_sleep_hour: f64,
+    pub sleep_need_hours: f64,
+    // "definite morning" .. "definite evening"
+    pub category: String,
+    pub source: ChronotypeSource,
+    // Free days the inference was based on (0 unless inferred)
+    pub free_days: usize,
+}
+
+impl Default for Chronotype {
+    fn default() -> Self {
+        Self::new(DEFAULT_MID_SLEEP_HOUR, ChronotypeSource::Default)
+    }
+}
+
+impl Chronotype {
+    fn new(mid_sleep_hour: f64, source: ChronotypeSource) -> Self {
+        Chronotype {
+            mid_sleep_hour,
+            sleep_need_hours: DEFAULT_SLEEP_NEED_HOURS,
+            category: Self::category_for_mid_sleep(mid_sleep_hour).to_string(),
+            source,
+            free_days: 0,
+        }
+    }
+
+    /// From a Morningness-Eveningness Questionnaire score (16-86). Each point
+    /// below 50 moves mid-sleep 6 minutes later; the category uses the MEQ bands.
+    pub fn from_meq(score: f64) -> Self {
+        let score = score.clamp(16.0, 86.0);
+        let mut chronotype = Self::new(DEFAULT_MID_SLEEP_HOUR + (50.0 - score) * 0.1, ChronotypeSource::Meq);
+        chronotype.category = match score {
+            s if s >= 70.0 => "definite morning",
+            s if s >= 59.0 => "moderate morning",
+            s if s >= 42.0 => "intermediate",
+            s if s >= 31.0 => "moderate evening",
+            _ => "definite evening",
+        }
+        .to_string();
+        chronotype
+    }
+
+    /// From the MCTQ's sleep-corrected mid-sleep on free days (MSFsc, hours after midnight)
+    pub fn from_mctq(msf_sc: f64) -> Self {
+        Self::new(sleep::wrap_hours(msf_sc), ChronotypeSource::Mctq)
+    }
+
+    pub fn with_sleep_need(mut self, hours: f64) -> Self {
+        self.sleep_need_hours = hours.clamp(4.0, 12.0);
+        self
+    }
+
+    /// Infer from sleep on free days (Saturday and Sunday mornings) over the last
+    /// 4 weeks, as the MCTQ does: the free-day mid-sleep, corrected for catch-up
+    /// sleep when free-day sleep is longer than the weekly average. Free-day sleep,
+    /// uncurtailed by alarms, is also the sleep need (kept within 7-9h).
+    /// Falls back to the default with fewer than 2 free days.
+    /// Nights older than the store's events are read from `summaries` (live feeds).
+    pub fn infer(store: &EventStore, time: DateTime<Utc>, summaries: &BTreeMap<NaiveDate, DailySummary>) -> Self {
+        let days = live::sleep_days_with_summaries(store, summaries, time - Duration::days(INFERENCE_LOOKBACK_DAYS), time);
+        let (free, work): (Vec<_>, Vec<_>) = days.iter().partition(|day| is_free_day(day.date));
+        if free.len() < MIN_FREE_DAYS {
+            return Self::default();
+        }
+
+        let mean = |values: &mut dyn Iterator<Item = f64>| {
+            let (sum, count) = values.fold((0.0, 0), |(s, n), v| (s + v, n + 1));
+            sum / count.max(1) as f64
+        };
+        let mid_sleep_free = mean(&mut free.iter().map(|d| sleep::hours_around_midnight(d.midpoint())));
+        let duration_free = mean(&mut free.iter().map(|d| d.total_asleep_hours()));
+        let duration_work = if work.is_empty() {
+            duration_free
+        } else {
+            mean(&mut work.iter().map(|d| d.total_asleep_hours()))
+        };
+        let duration_week = (5.0 * duration_work + 2.0 * duration_free) / 7.0;
+        let msf_sc = if duration_free > duration_work {
+            mid_sleep_free - (duration_free - duration_week) / 2.0
+        } else {
+            mid_sleep_free
+        };
+
+        let mut chronotype = Self::new(msf_sc, ChronotypeSource::Inferred).with_sleep_need(duration_free.clamp(7.0, 9.0));
+        chronotype.free_days = free.len();
+        chronotype
+    }
+
+    /// Clock hour (0-24) this person naturally wakes
+    pub fn preferred_wake_hour(&self) -> f64 {
+        (self.mid_sleep_hour + self.sleep_need_hours / 2.0).rem_euclid(24.0)
+    }
+
+    /// Clock hour (0-24) this person naturally falls asleep
+    pub fn preferred_bedtime_hour(&self) -> f64 {
+        (self.mid_sleep_hour - self.sleep_need_hours / 2.0).rem_euclid(24.0)
+    }
+
+    fn category_for_mid_sleep(mid_sleep_hour: f64) -> &'static str {
+        match mid_sleep_hour {
+            h if h <= 1.5 => "definite morning",
+            h if h <= 2.5 => "moderate morning",
+            h if h < 4.0 => "intermediate",
+            h if h < 5.0 => "moderate evening",
+            _ => "definite evening",
+        }
+    }
+}
+
+/// Sleep days that end on a weekend morning, when no alarm is assumed
+pub(crate) fn is_free_day(date: NaiveDate) -> bool {
+    matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+}
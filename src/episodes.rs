@@ -0,0 +1,163 @@
+// episodes.rs - Segment a timeline of functional states into episodes, with hysteresis
+use crate::{EventStore, FunctionalState, PrimitiveEstimator, StateDriver};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+#[derive(Debug, Clone)]
+pub struct SegmentationOptions {
+    // A new state must outscore the current one by this much to challenge it
+    pub hysteresis_margin: f64,
+    // ...and keep doing so for this long before the switch is made
+    pub min_dwell: Duration,
+}
+
+impl Default for SegmentationOptions {
+    fn default() -> Self {
+        SegmentationOptions {
+            hysteresis_margin: 0.1,
+            min_dwell: Duration::hours(1),
+        }
+    }
+}
+
+/// A contiguous stretch of one functional state
+#[derive(Debug, Clone, Serialize)]
+pub struct StateEpisode {
+    pub state_type: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub dwell_hours: f64,
+    // Mean probability of the state over the episode's samples
+    pub mean_probability: f64,
+    pub samples: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StateTransition {
+    pub timestamp: DateTime<Utc>,
+    pub from: String,
+    pub to: String,
+    // Inputs supporting the new state when it first took the lead
+    pub drivers: Vec<StateDriver>,
+    // e.g. "entered Depleted at 14:20 driven by adenosine"
+    pub summary: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StateTimeline {
+    pub episodes: Vec<StateEpisode>,
+    pub transitions: Vec<StateTransition>,
+}
+
+/// Score of a named state in a classification (0 if it was not considered)
+fn score_of(state: &FunctionalState, name: &str) -> f64 {
+    if state.state_type == name {
+        return state.score;
+    }
+    state.alternatives.iter().find(|a| a.state_type == name).map_or(0.0, |a| a.score)
+}
+
+fn probability_of(state: &FunctionalState, name: &str) -> f64 {
+    if state.state_type == name {
+        return state.probability;
+    }
+    state.alternatives.iter().find(|a| a.state_type == name).map_or(0.0, |a| a.probability)
+}
+
+fn close_episode(
+    timeline: &mut StateTimeline,
+    state: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    members: &[&FunctionalState],
+) {
+    let mean_probability = members.iter().map(|s| probability_of(s, state)).sum::<f64>() / members.len().max(1) as f64;
+    timeline.episodes.push(StateEpisode {
+        state_type: state.to_string(),
+        start,
+        end,
+        dwell_hours: (end - start).num_minutes() as f64 / 60.0,
+        mean_probability,
+        samples: members.len(),
+    });
+}
+
+/// Segment time-ordered classifications into episodes.
+///
+/// The current state holds until another state leads it by
+/// `hysteresis_margin` for at least `min_dwell`; the switch is then dated
+/// to when the challenger first took the lead. Brief excursions and
+/// boundary jitter therefore stay inside the surrounding episode.
+pub fn segment_states(samples: &[(DateTime<Utc>, FunctionalState)], options: &SegmentationOptions) -> StateTimeline {
+    let Some((first_time, first_state)) = samples.first() else {
+        return StateTimeline::default();
+    };
+
+    let mut timeline = StateTimeline::default();
+    let mut current = first_state.state_type.clone();
+    let mut episode_start = *first_time;
+    let mut episode_samples: Vec<&FunctionalState> = vec![first_state];
+    // Challenger and the sample index where it took the lead
+    let mut pending: Option<(String, usize)> = None;
+
+    for (index, (time, state)) in samples.iter().enumerate().skip(1) {
+        let challenger = &state.state_type;
+        let leads = challenger != &current && state.score > score_of(state, &current) + options.hysteresis_margin;
+
+        pending = match pending.take() {
+            Some((name, since)) if leads && &name == challenger => Some((name, since)),
+            _ if leads => Some((challenger.clone(), index)),
+            _ => None,
+        };
+
+        if let Some((name, since)) = &pending {
+            let (lead_time, lead_state) = &samples[*since];
+            if *time - *lead_time >= options.min_dwell {
+                // Samples since the challenger took the lead belong to its episode
+                let kept = episode_samples.len() - (index - since);
+                close_episode(&mut timeline, &current, episode_start, *lead_time, &episode_samples[..kept]);
+
+                let drivers = lead_state.drivers.clone();
+                let summary = match drivers.first() {
+                    Some(driver) => format!("entered {} at {} driven by {}", name, lead_time.format("%H:%M"), driver.input),
+                    None => format!("entered {} at {}", name, lead_time.format("%H:%M")),
+                };
+                timeline.transitions.push(StateTransition {
+                    timestamp: *lead_time,
+                    from: current.clone(),
+                    to: name.clone(),
+                    drivers,
+                    summary,
+                });
+
+                current = name.clone();
+                episode_start = *lead_time;
+                episode_samples = samples[*since..index].iter().map(|(_, s)| s).collect();
+                pending = None;
+            }
+        }
+        episode_samples.push(state);
+    }
+
+    let end = samples.last().map(|(t, _)| *t).unwrap_or(episode_start);
+    close_episode(&mut timeline, &current, episode_start, end, &episode_samples);
+    timeline
+}
+
+/// Classify the state every `step` from `start` to `end` and segment the result
+pub fn state_timeline(
+    estimator: &PrimitiveEstimator,
+    store: &EventStore,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    step: Duration,
+    options: &SegmentationOptions,
+) -> StateTimeline {
+    let mut samples = Vec::new();
+    let mut time = start;
+    while time <= end {
+        samples.push((time, estimator.run_passes(store, time).functional_state));
+        time += step;
+    }
+    segment_states(&samples, options)
+}
@@ -0,0 +1,327 @@
+// evaluation.rs - Compare predictions with what users report feeling (self_report events)
+use crate::{Event, EventData, EventStore, EstimationResult, MeasurementDiagnostic, PrimitiveEstimator};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Rated dimensions of a self_report event
+pub const DIMENSIONS: [&str; 5] = ["energy", "mood", "focus", "stress", "sleepiness"];
+
+/// States a focus/mood self-report can be labelled with
+const QUADRANTS: [&str; 4] = ["Optimal", "Driven but Anxious", "Content but Unmotivated", "Depleted"];
+
+/// Predictions are grouped into this many equal-width bins for calibration curves
+const CALIBRATION_BINS: usize = 5;
+
+/// A self_report event with each rating normalized to 0-1 (higher = more of the dimension)
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfReport {
+    pub event_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub ratings: BTreeMap<String, f64>,
+}
+
+/// Range of a rating scale
+fn scale_range(scale: &str) -> Option<(f64, f64)> {
+    match scale.trim().to_lowercase().as_str() {
+        // Karolinska Sleepiness Scale: 1 = extremely alert, 9 = very sleepy
+        "kss" | "karolinska" => Some((1.0, 9.0)),
+        "likert5" | "likert" | "1-5" => Some((1.0, 5.0)),
+        "likert7" | "1-7" => Some((1.0, 7.0)),
+        "0-10" | "nrs" => Some((0.0, 10.0)),
+        "vas" | "0-100" | "percent" => Some((0.0, 100.0)),
+        "0-1" | "fraction" => Some((0.0, 1.0)),
+        _ => None,
+    }
+}
+
+/// Parse the ratings of one self_report event.
+///
+/// Each dimension uses `<dimension>_scale` if given, then `scale`; sleepiness
+/// defaults to the Karolinska scale and everything else to a 5-point Likert.
+pub fn parse_self_report(event: &Event) -> (Option<SelfReport>, Vec<MeasurementDiagnostic>) {
+    let mut ratings = BTreeMap::new();
+    let mut diagnostics = Vec::new();
+
+    for dimension in DIMENSIONS {
+        let Some(value) = event.properties.get(dimension).and_then(|v| v.as_f64()) else {
+            continue;
+        };
+
+        let scale = event.properties.get(&format!("{}_scale", dimension))
+            .or_else(|| event.properties.get("scale"))
+            .and_then(|v| v.as_str())
+            .unwrap_or(if dimension == "sleepiness" { "kss" } else { "likert5" });
+
+        let diagnostic = |message: String| MeasurementDiagnostic {
+            event_id: event.event_id.clone(),
+            event_type: event.event_type.clone(),
+            unit: scale.to_string(),
+            message,
+        };
+
+        match scale_range(scale) {
+            Some((low, high)) if (low..=high).contains(&value) => {
+                ratings.insert(dimension.to_string(), (value - low) / (high - low));
+            }
+            Some((low, high)) => diagnostics.push(diagnostic(format!(
+                "{} rating {} is outside the {}-{} scale", dimension, value, low, high
+            ))),
+            None => diagnostics.push(diagnostic(format!("unknown rating scale '{}'", scale))),
+        }
+    }
+
+    let report = (!ratings.is_empty()).then(|| SelfReport {
+        event_id: event.event_id.clone(),
+        timestamp: event.timestamp,
+        ratings,
+    });
+    (report, diagnostics)
+}
+
+/// The model's prediction for a self-reported dimension, on the same 0-1 scale
+pub fn predicted_rating(dimension: &str, result: &EstimationResult) -> Option<f64> {
+    let score = |key: &str| result.primitives.get(key).map(|state| state.final_score());
+    match dimension {
+        "energy" => Some(1.0 - result.sleep_drive),
+        "sleepiness" => Some(result.sleep_drive),
+        "mood" => score("serotonin"),
+        "focus" => score("dopamine"),
+        "stress" => score("cortisol"),
+        _ => None,
+    }
+}
+
+/// One self-reported rating next to the prediction at the same timestamp
+#[derive(Debug, Clone, Serialize)]
+pub struct AlignedSample {
+    pub user_id: String,
+    pub event_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub dimension: String,
+    pub observed: f64,
+    pub predicted: f64,
+}
+
+/// Reported functional state vs. the classifier's. The label is the report's
+/// `state` when given, otherwise the motivation/mood quadrant its focus and mood imply.
+#[derive(Debug, Clone, Serialize)]
+pub struct StateSample {
+    pub user_id: String,
+    pub event_id: String,
+    pub reported: String,
+    pub predicted: String,
+}
+
+/// Estimate at every self-report in a user's history and pair ratings with predictions
+pub fn align_self_reports(
+    estimator: &PrimitiveEstimator,
+    user_id: &str,
+    store: &EventStore,
+) -> (Vec<AlignedSample>, Vec<StateSample>, Vec<MeasurementDiagnostic>) {
+    let mut samples = Vec::new();
+    let mut states = Vec::new();
+    let mut diagnostics = Vec::new();
+
+    for event in store.events().iter().filter(|e| e.event_type == "self_report") {
+        let (report, report_diagnostics) = parse_self_report(event);
+        diagnostics.extend(report_diagnostics);
+        let Some(report) = report else { continue };
+
+        let result = estimator.run_passes(store, report.timestamp);
+        for (dimension, &observed) in &report.ratings {
+            if let Some(predicted) = predicted_rating(dimension, &result) {
+                samples.push(AlignedSample {
+                    user_id: user_id.to_string(),
+                    event_id: report.event_id.clone(),
+                    timestamp: report.timestamp,
+                    dimension: dimension.clone(),
+                    observed,
+                    predicted,
+                });
+            }
+        }
+
+        let labelled_state = event.properties.get("state").and_then(|v| v.as_str());
+        let state = match (labelled_state, report.ratings.get("focus"), report.ratings.get("mood")) {
+            (Some(state), _, _) => Some((state.to_string(), result.functional_state.state_type.clone())),
+            (None, Some(&focus), Some(&mood)) => Some((
+                PrimitiveEstimator::mood_quadrant(focus, mood).to_string(),
+                most_likely_quadrant(&result),
+            )),
+            _ => None,
+        };
+        if let Some((reported, predicted)) = state {
+            states.push(StateSample {
+                user_id: user_id.to_string(),
+                event_id: report.event_id.clone(),
+                reported,
+                predicted,
+            });
+        }
+    }
+
+    (samples, states, diagnostics)
+}
+
+/// The classifier's most probable state among the four motivation/mood
+/// quadrants, for labels that only name a quadrant. Falls back to the winning
+/// state when custom rules define none of them.
+fn most_likely_quadrant(result: &EstimationResult) -> String {
+    let state = &result.functional_state;
+    std::iter::once((&state.state_type, state.probability))
+        .chain(state.alternatives.iter().map(|candidate| (&candidate.state_type, candidate.probability)))
+        .filter(|(name, _)| QUADRANTS.contains(&name.as_str()))
+        .fold(None, |best: Option<(&String, f64)>, candidate| match best {
+            Some(best) if best.1 >= candidate.1 => Some(best),
+            _ => Some(candidate),
+        })
+        .map_or_else(|| state.state_type.clone(), |(name, _)| name.clone())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CalibrationBin {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: usize,
+    pub mean_predicted: f64,
+    pub mean_observed: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DimensionMetrics {
+    pub count: usize,
+    // Pearson correlation; None with fewer than 3 samples or no variation
+    pub correlation: Option<f64>,
+    pub mae: f64,
+    // Mean of predicted - observed (positive = model over-predicts)
+    pub bias: f64,
+    pub calibration: Vec<CalibrationBin>,
+}
+
+impl DimensionMetrics {
+    pub fn from_samples(samples: &[&AlignedSample]) -> Self {
+        let n = samples.len() as f64;
+        let mean = |f: &dyn Fn(&AlignedSample) -> f64| samples.iter().map(|s| f(s)).sum::<f64>() / n.max(1.0);
+
+        let mean_predicted = mean(&|s| s.predicted);
+        let mean_observed = mean(&|s| s.observed);
+        let covariance = mean(&|s| (s.predicted - mean_predicted) * (s.observed - mean_observed));
+        let sd_predicted = mean(&|s| (s.predicted - mean_predicted).powi(2)).sqrt();
+        let sd_observed = mean(&|s| (s.observed - mean_observed).powi(2)).sqrt();
+        let correlation = (samples.len() >= 3 && sd_predicted > 1e-9 && sd_observed > 1e-9)
+            .then(|| covariance / (sd_predicted * sd_observed));
+
+        let calibration = (0..CALIBRATION_BINS)
+            .filter_map(|bin| {
+                let lower = bin as f64 / CALIBRATION_BINS as f64;
+                let upper = (bin + 1) as f64 / CALIBRATION_BINS as f64;
+                let members: Vec<&&AlignedSample> = samples.iter()
+                    .filter(|s| s.predicted >= lower && (s.predicted < upper || (bin == CALIBRATION_BINS - 1 && s.predicted <= upper)))
+                    .collect();
+                if members.is_empty() {
+                    return None;
+                }
+                let count = members.len() as f64;
+                Some(CalibrationBin {
+                    lower,
+                    upper,
+                    count: members.len(),
+                    mean_predicted: members.iter().map(|s| s.predicted).sum::<f64>() / count,
+                    mean_observed: members.iter().map(|s| s.observed).sum::<f64>() / count,
+                })
+            })
+            .collect();
+
+        DimensionMetrics {
+            count: samples.len(),
+            correlation,
+            mae: mean(&|s| (s.predicted - s.observed).abs()),
+            bias: mean_predicted - mean_observed,
+            calibration,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StateAgreement {
+    pub count: usize,
+    pub matches: usize,
+    pub accuracy: Option<f64>,
+    // reported state -> predicted state -> count
+    pub confusion: BTreeMap<String, BTreeMap<String, usize>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EvaluationReport {
+    pub sample_count: usize,
+    pub overall: BTreeMap<String, DimensionMetrics>,
+    pub per_user: BTreeMap<String, BTreeMap<String, DimensionMetrics>>,
+    pub functional_state: StateAgreement,
+    pub samples: Vec<AlignedSample>,
+    pub diagnostics: Vec<MeasurementDiagnostic>,
+}
+
+impl EvaluationReport {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Evaluate predictions against every user's self-reports
+pub fn evaluate(estimator: &PrimitiveEstimator, datasets: &[EventData]) -> EvaluationReport {
+    let mut samples = Vec::new();
+    let mut states = Vec::new();
+    let mut diagnostics = Vec::new();
+
+    for data in datasets {
+        let store = EventStore::new(&data.events);
+        let (user_samples, user_states, user_diagnostics) = align_self_reports(estimator, &data.user_id, &store);
+        samples.extend(user_samples);
+        states.extend(user_states);
+        diagnostics.extend(user_diagnostics);
+    }
+
+    let by_dimension = |samples: &[&AlignedSample]| -> BTreeMap<String, DimensionMetrics> {
+        DIMENSIONS.iter()
+            .filter_map(|&dimension| {
+                let members: Vec<&AlignedSample> = samples.iter().copied().filter(|s| s.dimension == dimension).collect();
+                (!members.is_empty()).then(|| (dimension.to_string(), DimensionMetrics::from_samples(&members)))
+            })
+            .collect()
+    };
+
+    let all: Vec<&AlignedSample> = samples.iter().collect();
+    let overall = by_dimension(&all);
+
+    let mut users: BTreeMap<&str, Vec<&AlignedSample>> = BTreeMap::new();
+    for sample in &samples {
+        users.entry(sample.user_id.as_str()).or_default().push(sample);
+    }
+    let per_user = users.into_iter()
+        .map(|(user_id, user_samples)| (user_id.to_string(), by_dimension(&user_samples)))
+        .collect();
+
+    let mut functional_state = StateAgreement { count: states.len(), ..Default::default() };
+    for state in &states {
+        if state.reported == state.predicted {
+            functional_state.matches += 1;
+        }
+        *functional_state.confusion
+            .entry(state.reported.clone())
+            .or_default()
+            .entry(state.predicted.clone())
+            .or_insert(0) += 1;
+    }
+    functional_state.accuracy = (!states.is_empty())
+        .then(|| functional_state.matches as f64 / states.len() as f64);
+
+    EvaluationReport {
+        sample_count: samples.len(),
+        overall,
+        per_user,
+        functional_state,
+        samples,
+        diagnostics,
+    }
+}
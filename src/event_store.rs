@@ -0,0 +1,229 @@
+// event_store.rs - Canonical, order-independent event storage for the estimator
+use crate::sleep::{self, SleepPeriod};
+use crate::Event;
+use chrono::{DateTime, Duration, Utc};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Records of the same type from different sources this close together with
+/// matching properties are treated as the same event reported twice
+const NEAR_DUPLICATE_WINDOW_MINUTES: i64 = 5;
+
+/// Relative tolerance when comparing numeric properties of near-duplicates
+const NEAR_DUPLICATE_VALUE_TOLERANCE: f64 = 0.02;
+
+/// Events sorted by time and deduplicated, with range queries by timestamp.
+///
+/// Every estimator pass reads from an `EventStore`, so results do not depend on
+/// the order events arrive in or on how many sources reported the same event.
+#[derive(Debug, Clone, Default)]
+pub struct EventStore {
+    events: Vec<Event>,
+}
+
+impl EventStore {
+    pub fn new(events: &[Event]) -> Self {
+        // Deduplicate by event_id, keeping a canonical copy regardless of input order
+        let mut by_id: BTreeMap<&str, &Event> = BTreeMap::new();
+        for event in events {
+            by_id
+                .entry(event.event_id.as_str())
+                .and_modify(|kept| {
+                    if canonical_rank(event) < canonical_rank(kept) {
+                        *kept = event;
+                    }
+                })
+                .or_insert(event);
+        }
+
+        let mut sorted: Vec<Event> = by_id.into_values().cloned().collect();
+        sorted.sort_by(canonical_order);
+
+        // Drop near-identical records from other sources, keeping the first in canonical order
+        let mut deduplicated: Vec<Event> = Vec::with_capacity(sorted.len());
+        for event in sorted {
+            let window_start = event.timestamp - Duration::minutes(NEAR_DUPLICATE_WINDOW_MINUTES);
+            let is_duplicate = deduplicated
+                .iter()
+                .rev()
+                .take_while(|kept| kept.timestamp >= window_start)
+                .any(|kept| is_near_duplicate(kept, &event));
+            if !is_duplicate {
+                deduplicated.push(event);
+            }
+        }
+
+        let mut store = EventStore { events: deduplicated };
+        store.merge_overlapping_sleep();
+        store
+    }
+
+    /// Add one event, keeping the store sorted and deduplicated.
+    /// A duplicate replaces the stored copy if it comes first canonically, so the
+    /// result matches `EventStore::new` for any arrival order.
+    /// Returns false if the event duplicated one already stored and was dropped.
+    pub fn insert(&mut self, event: Event) -> bool {
+        if let Some(existing) = self.events.iter().position(|e| e.event_id == event.event_id) {
+            if canonical_rank(&event) >= canonical_rank(&self.events[existing]) {
+                return false;
+            }
+            self.events.remove(existing);
+        }
+
+        let window = Duration::minutes(NEAR_DUPLICATE_WINDOW_MINUTES);
+        let near_duplicate = self.range(event.timestamp - window, event.timestamp + window)
+            .iter()
+            .find(|kept| is_near_duplicate(kept, &event))
+            .map(|kept| (canonical_order(&event, kept), kept.event_id.clone()));
+        if let Some((order, kept_id)) = near_duplicate {
+            if order.is_ge() {
+                return false;
+            }
+            self.events.retain(|e| e.event_id != kept_id);
+        }
+
+        let is_sleep = event.event_type == "sleep";
+        let position = self.events.partition_point(|e| canonical_order(e, &event).is_lt());
+        self.events.insert(position, event);
+
+        if is_sleep {
+            self.merge_overlapping_sleep();
+        }
+        true
+    }
+
+    /// Remove and return all events with `timestamp < cutoff`, oldest first
+    pub fn evict_before(&mut self, cutoff: DateTime<Utc>) -> Vec<Event> {
+        let split = self.events.partition_point(|e| e.timestamp < cutoff);
+        self.events.drain(..split).collect()
+    }
+
+    /// Overlapping sleep records are merged into one period; keep only its primary
+    /// record, widened to the merged interval so a later rebuild sees the same period
+    fn merge_overlapping_sleep(&mut self) {
+        let far_future = DateTime::<Utc>::MAX_UTC;
+        let merged: HashMap<String, SleepPeriod> = sleep::sleep_periods(&self.events, far_future)
+            .into_iter()
+            .filter(|p| p.event_ids.len() > 1)
+            .map(|p| (p.primary_event_id.clone(), p))
+            .collect();
+        if merged.is_empty() {
+            return;
+        }
+
+        let redundant_sleep: HashSet<&str> = merged
+            .values()
+            .flat_map(|p| p.event_ids.iter().map(String::as_str).filter(|id| *id != p.primary_event_id))
+            .collect();
+        self.events.retain(|e| !redundant_sleep.contains(e.event_id.as_str()));
+
+        for event in &mut self.events {
+            if let Some(period) = merged.get(&event.event_id) {
+                event.timestamp = period.start;
+                event.end_timestamp = Some(period.end);
+                event.properties.insert("duration_hours".to_string(), Value::from(period.asleep_hours));
+            }
+        }
+        self.events.sort_by(canonical_order);
+    }
+
+    /// All events in canonical order (by timestamp, then type, then id)
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Events with `start <= timestamp <= end`
+    pub fn range(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> &[Event] {
+        let lower = self.events.partition_point(|e| e.timestamp < start);
+        let upper = self.events.partition_point(|e| e.timestamp <= end);
+        if lower >= upper {
+            return &[];
+        }
+        &self.events[lower..upper]
+    }
+
+    /// Events at or before `end`
+    pub fn until(&self, end: DateTime<Utc>) -> &[Event] {
+        let upper = self.events.partition_point(|e| e.timestamp <= end);
+        &self.events[..upper]
+    }
+
+    /// Events of one type with `start <= timestamp <= end`, oldest first
+    pub fn of_type<'a>(
+        &'a self,
+        event_type: &'a str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> impl DoubleEndedIterator<Item = &'a Event> + 'a {
+        self.range(start, end).iter().filter(move |e| e.event_type == event_type)
+    }
+}
+
+/// Canonical event order: by timestamp, then type, then id
+fn canonical_order(a: &Event, b: &Event) -> std::cmp::Ordering {
+    a.timestamp
+        .cmp(&b.timestamp)
+        .then_with(|| a.event_type.cmp(&b.event_type))
+        .then_with(|| a.event_id.cmp(&b.event_id))
+}
+
+/// Ordering used to pick one copy among records sharing an event_id:
+/// the most complete record wins, then the lexicographically smallest serialization
+fn canonical_rank(event: &Event) -> (std::cmp::Reverse<usize>, bool, String) {
+    // Properties go through a BTreeMap so the serialization doesn't depend on HashMap order
+    let properties: BTreeMap<&String, &Value> = event.properties.iter().collect();
+    let serialized = serde_json::to_string(&(&event.event_type, event.timestamp, event.end_timestamp, properties))
+        .unwrap_or_default();
+    (
+        std::cmp::Reverse(event.properties.len()),
+        event.end_timestamp.is_none(),
+        serialized,
+    )
+}
+
+/// Same event reported by two sources: different ids and different `source`
+/// properties, otherwise matching. Records without a source are never merged
+/// this way, since two espressos minutes apart are real repeated doses.
+fn is_near_duplicate(a: &Event, b: &Event) -> bool {
+    let window = Duration::minutes(NEAR_DUPLICATE_WINDOW_MINUTES);
+
+    if a.event_type != b.event_type || a.event_id == b.event_id || (a.timestamp - b.timestamp).abs() > window {
+        return false;
+    }
+    let source = |event: &Event| event.properties.get("source").and_then(|v| v.as_str()).map(str::to_string);
+    match (source(a), source(b)) {
+        (Some(x), Some(y)) if x != y => {}
+        _ => return false,
+    }
+
+    let ends_match = match (a.end_timestamp, b.end_timestamp) {
+        (None, None) => true,
+        (Some(x), Some(y)) => (x - y).abs() <= window,
+        _ => false,
+    };
+    if !ends_match || a.properties.len() != b.properties.len() {
+        return false;
+    }
+
+    a.properties.iter().filter(|(key, _)| key.as_str() != "source").all(|(key, value)| {
+        b.properties.get(key).is_some_and(|other| values_match(value, other))
+    })
+}
+
+fn values_match(a: &Value, b: &Value) -> bool {
+    match (a.as_f64(), b.as_f64()) {
+        (Some(x), Some(y)) => {
+            let scale = x.abs().max(y.abs()).max(f64::EPSILON);
+            (x - y).abs() / scale <= NEAR_DUPLICATE_VALUE_TOLERANCE
+        }
+        _ => a == b,
+    }
+}
@@ -0,0 +1,486 @@
+// fitting.rs - Calibrate model parameters to a user's labelled history (Nelder-Mead)
+use crate::evaluation::{parse_self_report, predicted_rating, DIMENSIONS};
+use crate::{ConstraintType, EstimationResult, Event, EventData, EventStore, ModelParameters, PrimitiveEstimator};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::cell::Cell;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+/// Parameters fitted when none are requested: the acute/chronic blend,
+/// the chronic half-lives and the resting baselines of the rated primitives
+pub const DEFAULT_FIT_PARAMETERS: [&str; 6] = [
+    "monoamine_chronic_weight",
+    "dopamine_chronic_half_life_hours",
+    "serotonin_chronic_half_life_hours",
+    "dopamine_baseline_shift",
+    "serotonin_baseline_shift",
+    "cortisol_baseline_shift",
+];
+
+/// Initial simplex size in fitting coordinates (about ±20% of a default)
+const SIMPLEX_STEP: f64 = 0.2;
+
+/// A ground-truth value at a point in a user's history
+#[derive(Debug, Clone, Serialize)]
+pub struct FitLabel {
+    pub user_id: String,
+    // Event the label came from; left out of the history when predicting
+    pub event_id: String,
+    pub timestamp: DateTime<Utc>,
+    // Self-report dimension (energy, mood, ...) or primitive name (glucose, ...)
+    pub target: String,
+    // Observed value on the 0-1 scale of the prediction
+    pub observed: f64,
+}
+
+/// Every rating of every self_report event
+pub fn labels_from_self_reports(datasets: &[EventData]) -> Vec<FitLabel> {
+    let mut labels = Vec::new();
+    for data in datasets {
+        for event in data.events.iter().filter(|e| e.event_type == "self_report") {
+            let Some(report) = parse_self_report(event).0 else { continue };
+            for (dimension, &observed) in &report.ratings {
+                labels.push(FitLabel {
+                    user_id: data.user_id.clone(),
+                    event_id: report.event_id.clone(),
+                    timestamp: report.timestamp,
+                    target: dimension.clone(),
+                    observed,
+                });
+            }
+        }
+    }
+    labels
+}
+
+/// Wearable readings that measure a primitive directly (e.g. blood glucose),
+/// on the scale the physiological override would apply
+pub fn labels_from_measurements(estimator: &PrimitiveEstimator, datasets: &[EventData]) -> Vec<FitLabel> {
+    let mut labels = Vec::new();
+    for data in datasets {
+        for event in data.events.iter().filter(|e| e.event_type.starts_with("health_")) {
+            let single = EventStore::new(std::slice::from_ref(event));
+            let (measurements, _) = estimator.extract_physiological_measurements(&single, event.timestamp);
+            for measurement in measurements {
+                for constraint in measurement.constraints_with_parameters(event.timestamp, estimator.parameters()) {
+                    if let ConstraintType::Override(observed) = constraint.constraint_type {
+                        labels.push(FitLabel {
+                            user_id: data.user_id.clone(),
+                            event_id: event.event_id.clone(),
+                            timestamp: event.timestamp,
+                            target: constraint.affects_primitive.as_str().to_string(),
+                            observed,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    labels
+}
+
+/// The model's prediction for a label target
+fn predicted_value(target: &str, result: &EstimationResult) -> Option<f64> {
+    predicted_rating(target, result)
+        .or_else(|| result.primitives.get(target).map(|state| state.final_score()))
+}
+
+fn is_known_target(estimator: &PrimitiveEstimator, target: &str) -> bool {
+    DIMENSIONS.contains(&target) || estimator.registry().names().any(|name| name == target)
+}
+
+#[derive(Debug, Clone)]
+pub struct FitOptions {
+    // Parameters to fit; DEFAULT_FIT_PARAMETERS when empty
+    pub parameters: Vec<String>,
+    // Fit a single user's labels; the whole population when None
+    pub user_id: Option<String>,
+    // Weight of the squared distance from the research defaults (in fitting coordinates)
+    pub regularization: f64,
+    // Loss evaluations allowed per fit
+    pub max_evaluations: usize,
+    // Stop once the simplex's losses differ by less than this
+    pub tolerance: f64,
+    // Hold out each labelled day in turn
+    pub cross_validate: bool,
+}
+
+impl Default for FitOptions {
+    fn default() -> Self {
+        FitOptions {
+            parameters: Vec::new(),
+            user_id: None,
+            regularization: 0.01,
+            max_evaluations: 200,
+            tolerance: 1e-6,
+            cross_validate: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FittedParameter {
+    pub name: String,
+    pub default_value: f64,
+    pub initial_value: f64,
+    pub fitted_value: f64,
+}
+
+/// Fit on every labelled day but one, scored on the held-out day
+#[derive(Debug, Clone, Serialize)]
+pub struct FoldResult {
+    pub held_out_day: NaiveDate,
+    pub train_labels: usize,
+    pub test_labels: usize,
+    pub initial_mse: f64,
+    pub fitted_mse: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrossValidation {
+    pub folds: Vec<FoldResult>,
+    // Held-out errors averaged over all test labels
+    pub initial_mse: f64,
+    pub fitted_mse: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FitReport {
+    pub label_count: usize,
+    pub regularization: f64,
+    pub evaluations: usize,
+    pub converged: bool,
+    // Mean squared error with the estimator's parameters and with the fitted ones
+    pub initial_mse: f64,
+    pub fitted_mse: f64,
+    pub parameters: Vec<FittedParameter>,
+    pub cross_validation: Option<CrossValidation>,
+    // Complete parameter set: the estimator's parameters with the fitted values
+    #[serde(skip)]
+    pub fitted: ModelParameters,
+}
+
+impl FitReport {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// Parameter file loadable with `ModelParameters::from_json`
+    pub fn parameter_file(&self) -> String {
+        self.fitted.to_json()
+    }
+}
+
+/// How a parameter maps to an unbounded fitting coordinate. Zero is the
+/// research default, and one unit is roughly "e times larger" (or a logit
+/// step for weights, or one typical scale for shifts), so the regularization
+/// penalty treats every parameter alike.
+#[derive(Debug, Clone, Copy)]
+enum Coordinate {
+    Logit(f64),
+    Log(f64),
+    Linear(f64),
+}
+
+impl Coordinate {
+    fn for_parameter(name: &str) -> Self {
+        let default = ModelParameters::default().get(name).unwrap_or(0.0);
+        if name.ends_with("_weight") {
+            Coordinate::Logit(logit(default))
+        } else if default.abs() > f64::EPSILON {
+            Coordinate::Log(default)
+        } else {
+            Coordinate::Linear(ModelParameters::typical_scale(name))
+        }
+    }
+
+    fn value(self, z: f64) -> f64 {
+        match self {
+            Coordinate::Logit(center) => 1.0 / (1.0 + (-(center + z)).exp()),
+            Coordinate::Log(default) => default * z.exp(),
+            Coordinate::Linear(scale) => z * scale,
+        }
+    }
+
+    fn coordinate(self, value: f64) -> f64 {
+        match self {
+            Coordinate::Logit(center) => logit(value) - center,
+            Coordinate::Log(default) if value / default > 0.0 => (value / default).ln(),
+            Coordinate::Log(_) => 0.0,
+            Coordinate::Linear(scale) => value / scale,
+        }
+    }
+}
+
+fn logit(p: f64) -> f64 {
+    let p = p.clamp(1e-6, 1.0 - 1e-6);
+    (p / (1.0 - p)).ln()
+}
+
+/// A user's history with the labelling events removed
+struct UserHistory<'a> {
+    store: EventStore,
+    labels: Vec<&'a FitLabel>,
+}
+
+/// Mean squared error and label count over the labels accepted by `include`
+fn squared_error(
+    estimator: &PrimitiveEstimator,
+    params: &ModelParameters,
+    histories: &[UserHistory],
+    include: &dyn Fn(&FitLabel) -> bool,
+) -> (f64, usize) {
+    // Same chronotype, state rules and registered primitives as the configured estimator
+    let estimator = estimator.clone().with_parameters(params.clone());
+    let mut total = 0.0;
+    let mut count = 0;
+    for history in histories {
+        // One estimate per labelled moment
+        let mut moments: BTreeMap<DateTime<Utc>, Vec<&FitLabel>> = BTreeMap::new();
+        for &label in history.labels.iter().filter(|l| include(l)) {
+            moments.entry(label.timestamp).or_default().push(label);
+        }
+        for (timestamp, labels) in moments {
+            let result = estimator.run_passes(&history.store, timestamp);
+            for label in labels {
+                if let Some(predicted) = predicted_value(&label.target, &result) {
+                    total += (predicted - label.observed).powi(2);
+                    count += 1;
+                }
+            }
+        }
+    }
+    (if count > 0 { total / count as f64 } else { 0.0 }, count)
+}
+
+/// Fit a subset of model parameters to labelled history.
+///
+/// Minimizes mean squared error between labels and predictions plus
+/// `regularization` times the squared distance from the research defaults,
+/// starting from the estimator's current parameters. Labels are predicted
+/// from history without their own events, so a glucose reading cannot
+/// simply be read back through the physiological override.
+pub fn fit_parameters(
+    estimator: &PrimitiveEstimator,
+    datasets: &[EventData],
+    labels: &[FitLabel],
+    options: &FitOptions,
+) -> Result<FitReport, String> {
+    let names: Vec<String> = if options.parameters.is_empty() {
+        DEFAULT_FIT_PARAMETERS.iter().map(|name| name.to_string()).collect()
+    } else {
+        options.parameters.clone()
+    };
+    let defaults = ModelParameters::default();
+    for name in &names {
+        if defaults.get(name).is_none() {
+            return Err(format!("Unknown parameter '{}'", name));
+        }
+    }
+    if let Some(label) = labels.iter().find(|l| !is_known_target(estimator, &l.target)) {
+        return Err(format!("Unknown label target '{}'", label.target));
+    }
+
+    let selected: Vec<&FitLabel> = labels.iter()
+        .filter(|l| options.user_id.as_ref().is_none_or(|user| &l.user_id == user))
+        .collect();
+    if selected.is_empty() {
+        return Err("No labels to fit".to_string());
+    }
+
+    let label_events: HashSet<&str> = selected.iter().map(|l| l.event_id.as_str()).collect();
+    let histories: Vec<UserHistory> = datasets.iter()
+        .filter_map(|data| {
+            let user_labels: Vec<&FitLabel> = selected.iter().copied().filter(|l| l.user_id == data.user_id).collect();
+            if user_labels.is_empty() {
+                return None;
+            }
+            let events: Vec<Event> = data.events.iter()
+                .filter(|e| !label_events.contains(e.event_id.as_str()))
+                .cloned()
+                .collect();
+            Some(UserHistory { store: EventStore::new(&events), labels: user_labels })
+        })
+        .collect();
+
+    let initial = estimator.parameters().clone();
+    let coordinates: Vec<Coordinate> = names.iter().map(|name| Coordinate::for_parameter(name)).collect();
+    let start: Vec<f64> = names.iter()
+        .zip(&coordinates)
+        .map(|(name, coordinate)| coordinate.coordinate(initial.get(name).unwrap_or(0.0)))
+        .collect();
+
+    let to_params = |z: &[f64]| -> Result<ModelParameters, String> {
+        let changes: BTreeMap<String, f64> = names.iter()
+            .zip(coordinates.iter().zip(z))
+            .map(|(name, (coordinate, &z))| (name.clone(), coordinate.value(z)))
+            .collect();
+        initial.with_values(&changes)
+    };
+
+    let fit = |include: &dyn Fn(&FitLabel) -> bool| -> Result<(Vec<f64>, usize, bool), String> {
+        let mut error = None;
+        let objective = |z: &[f64]| -> f64 {
+            match to_params(z) {
+                Ok(params) => {
+                    let penalty = options.regularization * z.iter().map(|v| v * v).sum::<f64>();
+                    squared_error(estimator, &params, &histories, include).0 + penalty
+                }
+                Err(e) => {
+                    error.get_or_insert(e);
+                    f64::INFINITY
+                }
+            }
+        };
+        let result = nelder_mead(objective, &start, options.max_evaluations, options.tolerance);
+        match error {
+            Some(e) => Err(e),
+            None => Ok(result),
+        }
+    };
+
+    let all = |_: &FitLabel| true;
+    let (best, evaluations, converged) = fit(&all)?;
+    let fitted = to_params(&best)?;
+
+    let cross_validation = if options.cross_validate {
+        let days: BTreeSet<NaiveDate> = selected.iter().map(|l| l.timestamp.date_naive()).collect();
+        if days.len() < 2 {
+            None
+        } else {
+            let mut folds = Vec::new();
+            for day in days {
+                let train = move |l: &FitLabel| l.timestamp.date_naive() != day;
+                let test = move |l: &FitLabel| l.timestamp.date_naive() == day;
+                let (fold_best, _, _) = fit(&train)?;
+                let (initial_mse, test_labels) = squared_error(estimator, &initial, &histories, &test);
+                let (fitted_mse, _) = squared_error(estimator, &to_params(&fold_best)?, &histories, &test);
+                folds.push(FoldResult {
+                    held_out_day: day,
+                    train_labels: selected.iter().filter(|l| train(l)).count(),
+                    test_labels,
+                    initial_mse,
+                    fitted_mse,
+                });
+            }
+            let total = folds.iter().map(|f| f.test_labels).sum::<usize>().max(1) as f64;
+            Some(CrossValidation {
+                initial_mse: folds.iter().map(|f| f.initial_mse * f.test_labels as f64).sum::<f64>() / total,
+                fitted_mse: folds.iter().map(|f| f.fitted_mse * f.test_labels as f64).sum::<f64>() / total,
+                folds,
+            })
+        }
+    } else {
+        None
+    };
+
+    let (initial_mse, label_count) = squared_error(estimator, &initial, &histories, &all);
+    let (fitted_mse, _) = squared_error(estimator, &fitted, &histories, &all);
+    let parameters = names.iter()
+        .map(|name| FittedParameter {
+            name: name.clone(),
+            default_value: defaults.get(name).unwrap_or(0.0),
+            initial_value: initial.get(name).unwrap_or(0.0),
+            fitted_value: fitted.get(name).unwrap_or(0.0),
+        })
+        .collect();
+
+    Ok(FitReport {
+        label_count,
+        regularization: options.regularization,
+        evaluations,
+        converged,
+        initial_mse,
+        fitted_mse,
+        parameters,
+        cross_validation,
+        fitted,
+    })
+}
+
+/// Minimize `f` with the Nelder-Mead simplex method.
+/// Returns the best point, the number of evaluations and whether the
+/// simplex converged within `max_evaluations`.
+fn nelder_mead(
+    mut f: impl FnMut(&[f64]) -> f64,
+    start: &[f64],
+    max_evaluations: usize,
+    tolerance: f64,
+) -> (Vec<f64>, usize, bool) {
+    let n = start.len();
+    let evaluations = Cell::new(0);
+    let mut evaluate = |point: &[f64]| {
+        evaluations.set(evaluations.get() + 1);
+        f(point)
+    };
+
+    let mut simplex: Vec<(Vec<f64>, f64)> = Vec::with_capacity(n + 1);
+    simplex.push((start.to_vec(), evaluate(start)));
+    for i in 0..n {
+        let mut vertex = start.to_vec();
+        vertex[i] += SIMPLEX_STEP;
+        let value = evaluate(&vertex);
+        simplex.push((vertex, value));
+    }
+
+    let combine = |a: &[f64], b: &[f64], t: f64| -> Vec<f64> {
+        a.iter().zip(b).map(|(x, y)| x + t * (y - x)).collect()
+    };
+
+    let mut converged = false;
+    loop {
+        simplex.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+        if (simplex[n].1 - simplex[0].1).abs() < tolerance {
+            converged = true;
+            break;
+        }
+        if evaluations.get() >= max_evaluations {
+            break;
+        }
+
+        let centroid: Vec<f64> = (0..n)
+            .map(|j| simplex[..n].iter().map(|(v, _)| v[j]).sum::<f64>() / n as f64)
+            .collect();
+        let worst = simplex[n].clone();
+
+        // Reflect the worst vertex through the centroid of the others
+        let reflected = combine(&centroid, &worst.0, -1.0);
+        let reflected_value = evaluate(&reflected);
+
+        if reflected_value < simplex[0].1 {
+            let expanded = combine(&centroid, &worst.0, -2.0);
+            let expanded_value = evaluate(&expanded);
+            simplex[n] = if expanded_value < reflected_value {
+                (expanded, expanded_value)
+            } else {
+                (reflected, reflected_value)
+            };
+        } else if reflected_value < simplex[n - 1].1 {
+            simplex[n] = (reflected, reflected_value);
+        } else {
+            // Contract towards the better of the worst and reflected points
+            let (toward, toward_value) = if reflected_value < worst.1 {
+                (reflected, reflected_value)
+            } else {
+                worst
+            };
+            let contracted = combine(&centroid, &toward, 0.5);
+            let contracted_value = evaluate(&contracted);
+            if contracted_value < toward_value {
+                simplex[n] = (contracted, contracted_value);
+            } else {
+                // Shrink everything towards the best vertex
+                let best = simplex[0].0.clone();
+                for vertex in simplex.iter_mut().skip(1) {
+                    let shrunk = combine(&best, &vertex.0, 0.5);
+                    let value = evaluate(&shrunk);
+                    *vertex = (shrunk, value);
+                }
+            }
+        }
+    }
+
+    simplex.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+    let best = simplex.swap_remove(0).0;
+    (best, evaluations.get(), converged)
+}
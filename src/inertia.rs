@@ -0,0 +1,131 @@
+// inertia.rs - Sleep inertia after waking and after naps, by sleep stage, sleep debt and circadian time
+use crate::performance::{circadian_alertness, sleep_debt_hours};
+use crate::sleep;
+use crate::{Event, EventStore};
+use chrono::{DateTime, Duration, Timelike, Utc};
+use serde::Serialize;
+
+/// Awakenings further back than this no longer leave measurable inertia
+const LOOKBACK_MINUTES: i64 = 90;
+/// Naps up to this long stay in light sleep and leave no inertia
+const SHORT_NAP_MINUTES: f64 = 30.0;
+/// A nap this long usually completes a cycle and ends in light sleep
+const FULL_CYCLE_NAP_MINUTES: f64 = 80.0;
+/// Sleep debt at which its effect on inertia saturates
+const MAX_DEBT_HOURS: f64 = 14.0;
+
+/// Sleep stage the sleeper woke from
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WakeStage {
+    // N1/N2
+    Light,
+    Rem,
+    // N3 (slow-wave sleep): the worst grogginess
+    Deep,
+}
+
+impl WakeStage {
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "light" | "n1" | "n2" => Some(WakeStage::Light),
+            "rem" => Some(WakeStage::Rem),
+            "deep" | "n3" | "sws" => Some(WakeStage::Deep),
+            _ => None,
+        }
+    }
+
+    /// Relative severity of inertia on waking from this stage
+    fn severity(self) -> f64 {
+        match self {
+            WakeStage::Light => 0.45,
+            WakeStage::Rem => 0.6,
+            WakeStage::Deep => 1.0,
+        }
+    }
+}
+
+/// Grogginess after the latest awakening (a `wake` event or the end of a nap over
+/// 30 min). Starts at a level set by the stage woken from, sleep debt and how close
+/// waking is to the circadian trough, then decays over 15-60 minutes.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SleepInertia {
+    // 0 (none) to 1 (severe) at the estimation time
+    pub level: f64,
+    // Level at the moment of waking
+    pub initial_level: f64,
+    // Time constant of the exponential decay
+    pub time_constant_minutes: f64,
+    pub minutes_since_waking: f64,
+    // "wake" or "nap"; None without a recent awakening
+    pub source: Option<String>,
+    pub event_id: Option<String>,
+    pub stage_at_waking: Option<WakeStage>,
+    // Stage estimated from sleep length because none was recorded
+    pub stage_inferred: bool,
+}
+
+impl SleepInertia {
+    /// `sleep_need_hours` is the sleeper's nightly need, for sleep debt
+    pub fn compute(store: &EventStore, time: DateTime<Utc>, sleep_need_hours: f64) -> Self {
+        let since = time - Duration::minutes(LOOKBACK_MINUTES);
+        let wake = store.of_type("wake", since, time).next_back().map(|event| {
+            let stage = recorded_stage(event).map_or_else(|| wake_stage(store, event), |stage| (stage, false));
+            (event, event.timestamp, stage)
+        });
+        let nap = store
+            .of_type("nap", since - Duration::hours(4), time)
+            .filter_map(|event| {
+                let minutes = event.properties.get("duration_minutes").and_then(|v| v.as_f64()).unwrap_or(20.0);
+                let end = event.end_timestamp.unwrap_or(event.timestamp + Duration::minutes(minutes as i64));
+                let stage = match recorded_stage(event) {
+                    Some(stage) => (stage, false),
+                    None if minutes > FULL_CYCLE_NAP_MINUTES => (WakeStage::Light, true),
+                    None => (WakeStage::Deep, true),
+                };
+                (minutes > SHORT_NAP_MINUTES && end >= since && end <= time).then_some((event, end, stage))
+            })
+            .next_back();
+        let latest = [wake, nap].into_iter().flatten().max_by_key(|(_, woke_at, _)| *woke_at);
+        let Some((event, woke_at, (stage, stage_inferred))) = latest else {
+            return Self::default();
+        };
+
+        let debt = (sleep_debt_hours(store, woke_at, sleep_need_hours) / MAX_DEBT_HOURS).clamp(0.0, 1.0);
+        let hour = woke_at.hour() as f64 + woke_at.minute() as f64 / 60.0;
+        // 1 at the early-morning trough (~05:00), 0 at the evening peak
+        let circadian_dip = (1.0 - circadian_alertness(hour, 0.5)) / 2.0;
+
+        let initial_level = (0.5 * stage.severity() * (1.0 + 0.5 * debt) * (1.0 + 0.5 * circadian_dip)).min(1.0);
+        let time_constant_minutes = 5.0 + 10.0 * stage.severity() + 5.0 * debt;
+        let minutes_since_waking = (time - woke_at).num_seconds() as f64 / 60.0;
+
+        SleepInertia {
+            level: initial_level * (-minutes_since_waking / time_constant_minutes).exp(),
+            initial_level,
+            time_constant_minutes,
+            minutes_since_waking,
+            source: Some(event.event_type.clone()),
+            event_id: Some(event.event_id.clone()),
+            stage_at_waking: Some(stage),
+            stage_inferred,
+        }
+    }
+}
+
+fn recorded_stage(event: &Event) -> Option<WakeStage> {
+    event.properties.get("sleep_stage").and_then(|v| v.as_str()).and_then(WakeStage::parse)
+}
+
+/// Stage at a wake event without a recorded one: slow-wave sleep dominates the
+/// first cycles, so waking after under 4.5h asleep is likely from deep sleep
+fn wake_stage(store: &EventStore, wake: &Event) -> (WakeStage, bool) {
+    let asleep_hours = sleep::sleep_periods(store.until(wake.timestamp), wake.timestamp)
+        .last()
+        .filter(|period| (wake.timestamp - period.end).num_minutes().abs() <= 60)
+        .map(|period| period.asleep_hours);
+    match asleep_hours {
+        Some(hours) if hours < 4.5 => (WakeStage::Deep, true),
+        _ => (WakeStage::Light, true),
+    }
+}
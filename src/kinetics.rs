@@ -0,0 +1,102 @@
+// kinetics.rs - Onset and offset kinetics of interval events (exercise, light, screens, social, stress)
+use crate::light;
+use crate::Event;
+use chrono::{DateTime, Duration, Utc};
+
+/// Longest interval event looked back for when it started before a context window
+pub const MAX_INTERVAL_HOURS: i64 = 24;
+
+/// How an interval event's effect builds while it lasts and fades once it ends
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EventKinetics {
+    // Time constant of the rise after each minute of the event is delivered (0: no ramp)
+    pub onset_minutes: f64,
+    // Multiplier on the primitive's half-life once the event is over
+    pub offset_half_life_scale: f64,
+    // The impact already covers only the part of the event before the estimation time
+    pub impact_is_elapsed: bool,
+}
+
+impl EventKinetics {
+    /// Kinetics of event types whose impacts are spread over their duration.
+    /// Other types (meals, caffeine, sleep, naps, ...) act from their timestamp.
+    pub fn for_event_type(event_type: &str) -> Option<Self> {
+        let (onset_minutes, offset_half_life_scale, impact_is_elapsed) = match event_type {
+            // Catecholamines rise within minutes of starting
+            "exercise" => (10.0, 1.0, false),
+            // The light dose is integrated from the samples before the estimation
+            // time, so it already accumulates with exposure and needs no ramp
+            "light_exposure" | "light_series" => (0.0, 1.0, true),
+            // Screen arousal fades quickly once the screen is off
+            "screen_time" => (10.0, 0.5, false),
+            "social_interaction" => (15.0, 1.0, false),
+            // Cortisol peaks 20-30 min after stressor onset
+            "stress_event" => (20.0, 1.0, false),
+            _ => return None,
+        };
+        Some(EventKinetics { onset_minutes, offset_half_life_scale, impact_is_elapsed })
+    }
+
+    /// Fraction of the full impact active `minutes` after the start of an event
+    /// lasting `duration_minutes`. Each minute of the event delivers an equal
+    /// share of the impact, which rises as `1 - e^(-t / onset)` from that minute on.
+    pub fn elapsed_share(&self, minutes: f64, duration_minutes: f64) -> f64 {
+        if self.impact_is_elapsed {
+            return 1.0;
+        }
+        let minutes = minutes.max(0.0);
+        let elapsed = minutes.min(duration_minutes);
+        let delivered = if self.onset_minutes <= 0.0 {
+            elapsed
+        } else {
+            // Integral of the rise over the minutes delivered so far
+            elapsed - self.onset_minutes
+                * ((-(minutes - elapsed) / self.onset_minutes).exp() - (-minutes / self.onset_minutes).exp())
+        };
+        (delivered / duration_minutes).clamp(0.0, 1.0)
+    }
+}
+
+/// Duration of an event in hours, from `end_timestamp`, `duration_minutes` or
+/// the samples of a light series. None when none is recorded.
+pub fn duration_hours(event: &Event) -> Option<f64> {
+    let hours = match event.end_timestamp {
+        Some(end) => (end - event.timestamp).num_minutes() as f64 / 60.0,
+        None => event.properties.get("duration_minutes").and_then(|v| v.as_f64()).or_else(|| light::series_minutes(event))? / 60.0,
+    };
+    (hours > 0.0).then_some(hours)
+}
+
+/// Whether an event counts in a context window starting at `cutoff`: it started
+/// in the window, or it is an interval event that was still running at `cutoff`.
+/// Query from `cutoff - MAX_INTERVAL_HOURS` and filter with this.
+pub fn in_window(event: &Event, cutoff: DateTime<Utc>) -> bool {
+    event.timestamp >= cutoff
+        || (EventKinetics::for_event_type(&event.event_type).is_some()
+            && duration_hours(event).is_some_and(|hours| {
+                event.timestamp + Duration::minutes((hours * 60.0) as i64) >= cutoff
+            }))
+}
+
+/// Share of an event's impact active at `time`. Interval events with kinetics
+/// and a known duration build up as their minutes are delivered and only decay,
+/// via `decay(hours_since_end, half_life)`, once they end. Everything else
+/// decays from its timestamp as before.
+pub fn impact_weight(
+    event: &Event,
+    time: DateTime<Utc>,
+    half_life_hours: f64,
+    decay: impl Fn(f64, f64) -> f64,
+) -> f64 {
+    let hours_ago = (time - event.timestamp).num_minutes() as f64 / 60.0;
+    let (Some(kinetics), Some(duration)) = (EventKinetics::for_event_type(&event.event_type), duration_hours(event)) else {
+        return decay(hours_ago, half_life_hours);
+    };
+
+    let share = kinetics.elapsed_share(hours_ago * 60.0, duration * 60.0);
+    if hours_ago <= duration {
+        share
+    } else {
+        share * decay(hours_ago - duration, half_life_hours * kinetics.offset_half_life_scale)
+    }
+}
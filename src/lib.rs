@@ -1,14 +1,61 @@
 // lib.rs - Research-Based Neurobiological Primitive Estimator Library
 pub mod profiles;
 pub mod chat;
+pub mod event_store;
+pub mod sleep;
+pub mod units;
+pub mod live;
+pub mod attribution;
+pub mod trace;
+pub mod params;
+pub mod sensitivity;
+pub mod evaluation;
+pub mod fitting;
+pub mod states;
+pub mod episodes;
+pub mod recommendations;
+pub mod performance;
+pub mod chronotype;
+pub mod sleep_metrics;
+pub mod registry;
+pub mod training;
+pub mod allostatic;
+pub mod kinetics;
+pub mod inertia;
+pub mod naps;
+pub mod light;
+mod sampling;
 
 // Re-export profile functions for convenience
 pub use profiles::{generate_profile_events, get_all_profiles, Profile};
-
-use chrono::{DateTime, Duration, Timelike, Utc};
+pub use units::MeasurementDiagnostic;
+pub use event_store::EventStore;
+pub use live::LiveEstimator;
+pub use attribution::{attribute_events, AttributionReport};
+pub use trace::EstimationTrace;
+pub use params::ModelParameters;
+pub use states::{StateCandidate, StateClassifier, StateDriver};
+pub use performance::{PerformanceOutlook, PerformancePrediction};
+pub use chronotype::{Chronotype, ChronotypeSource};
+pub use sleep_metrics::SleepMetrics;
+pub use training::TrainingLoad;
+pub use allostatic::{AllostaticLoad, AllostaticStatus};
+pub use inertia::SleepInertia;
+pub use naps::{NapAssessment, NapReport};
+pub use light::LightReport;
+pub use registry::{PrimitiveDefinition, PrimitiveKey, PrimitiveMap, PrimitiveRegistry};
+
+use chrono::{DateTime, Duration, NaiveDate, Timelike, Utc};
 use serde::{Deserialize, Serialize};
-use std::cell::Cell;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use live::DailySummary;
+use registry::Computation;
+use trace::{AdhdTrace, CaffeineBoostTrace, CortisolTrace, TracePass};
+
+/// Longest stretch of history any estimate reads (allostatic load, 6 weeks).
+/// Multi-week windows elsewhere must fit inside it; live feeds serve the part
+/// older than their retained events from daily summaries.
+pub const HISTORY_WINDOW_DAYS: i64 = 42;
 
 // ============================================================================
 // DATA STRUCTURES
@@ -39,6 +86,7 @@ pub enum Primitive {
     CircadianPhase,
     Cortisol,
     Glucose,
+    Melatonin,
 }
 
 impl Primitive {
@@ -51,6 +99,7 @@ impl Primitive {
             Primitive::CircadianPhase => "circadian_phase",
             Primitive::Cortisol => "cortisol",
             Primitive::Glucose => "glucose",
+            Primitive::Melatonin => "melatonin",
         }
     }
 
@@ -63,6 +112,7 @@ impl Primitive {
             Primitive::CircadianPhase,
             Primitive::Cortisol,
             Primitive::Glucose,
+            Primitive::Melatonin,
         ]
     }
 }
@@ -79,6 +129,14 @@ pub struct PrimitiveState {
     pub effective_score: Option<f64>,  // After reciprocal inhibition
 }
 
+impl PrimitiveState {
+    /// Score after every pass: the effective score for dopamine and serotonin
+    /// (after reciprocal inhibition), the modified score otherwise
+    pub fn final_score(&self) -> f64 {
+        self.effective_score.unwrap_or(self.modified_score)
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct EventContribution {
     pub event_id: String,
@@ -91,19 +149,34 @@ pub struct EventContribution {
 #[derive(Debug, Serialize)]
 pub struct EstimationResult {
     pub timestamp: DateTime<Utc>,
-    pub primitives: HashMap<String, PrimitiveState>,
+    pub primitives: PrimitiveMap,  // In registry order
     pub detected_sequences: Vec<DetectedSequence>,
     pub sleep_drive: f64,
     pub dopamine_serotonin_ratio: f64,
     pub functional_state: FunctionalState,
+    pub performance: PerformancePrediction,  // Predicted vigilance, reaction time, working memory, sleepiness
+    pub chronotype: Chronotype,  // Configured or inferred; circadian alignment is scored against it
+    pub sleep_metrics: SleepMetrics,  // Regularity (SRI, social jet lag, drift, timing variability)
+    pub training: TrainingLoad,  // Acute/chronic load, recovery and overreaching
+    pub allostatic_load: AllostaticLoad,  // Chronic stress index over weeks, with its daily trend
+    pub sleep_inertia: SleepInertia,  // Grogginess after the latest awakening or long nap
+    pub naps: NapReport,  // Today's naps by circadian timing, and the best window for the next one
+    pub light: LightReport,  // Melanopic light dose and daylight episodes over the last 24h
     pub physiological_constraints: Vec<PhysiologicalConstraintApplied>,  // NEW: Track applied constraints
+    pub measurement_diagnostics: Vec<MeasurementDiagnostic>,  // Measurements rejected during unit normalization
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace: Option<EstimationTrace>,  // Only set by estimate_with_trace
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct FunctionalState {
     pub state_type: String,
     pub description: String,
     pub recommendations: Vec<String>,
+    pub score: f64,
+    pub probability: f64,
+    pub drivers: Vec<StateDriver>,  // Inputs that matched the state's rule, strongest first
+    pub alternatives: Vec<StateCandidate>,  // Other states, most likely first
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -124,6 +197,8 @@ pub struct PhysiologicalMeasurement {
     pub value: f64,
     pub timestamp: DateTime<Utc>,
     pub unit: String,
+    // Secondary numeric readings (e.g. diastolic pressure, sleep stage minutes)
+    pub components: HashMap<String, f64>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -135,6 +210,62 @@ pub enum MeasurementType {
     BodyTemperature,
     RespiratoryRate,
     Steps,
+    ElectrodermalActivity,
+    BloodPressure,
+    SkinTemperature,
+    SleepStages,
+    RestingHeartRate,
+    Vo2Max,
+}
+
+impl MeasurementType {
+    /// Map a `health_*` event type to the measurement it carries
+    pub fn from_event_type(event_type: &str) -> Option<Self> {
+        match event_type {
+            "health_heart_rate" => Some(MeasurementType::HeartRate),
+            "health_hrv" => Some(MeasurementType::HeartRateVariability),
+            "health_blood_oxygen" => Some(MeasurementType::BloodOxygen),
+            "health_blood_glucose" => Some(MeasurementType::BloodGlucose),
+            "health_body_temperature" => Some(MeasurementType::BodyTemperature),
+            "health_respiratory_rate" => Some(MeasurementType::RespiratoryRate),
+            "health_steps" => Some(MeasurementType::Steps),
+            "health_eda" => Some(MeasurementType::ElectrodermalActivity),
+            "health_blood_pressure" => Some(MeasurementType::BloodPressure),
+            "health_skin_temperature" => Some(MeasurementType::SkinTemperature),
+            "health_sleep_stages" => Some(MeasurementType::SleepStages),
+            "health_resting_heart_rate" => Some(MeasurementType::RestingHeartRate),
+            "health_vo2max" => Some(MeasurementType::Vo2Max),
+            _ => None,
+        }
+    }
+
+    /// Unit assumed when the event does not state one
+    pub fn default_unit(&self) -> &'static str {
+        match self {
+            MeasurementType::HeartRate | MeasurementType::RestingHeartRate => "bpm",
+            MeasurementType::HeartRateVariability => "ms",
+            MeasurementType::BloodOxygen => "%",
+            MeasurementType::BloodGlucose => "mg/dL",
+            MeasurementType::BodyTemperature => "°C",
+            MeasurementType::RespiratoryRate => "breaths/min",
+            MeasurementType::Steps => "steps",
+            MeasurementType::ElectrodermalActivity => "µS",
+            MeasurementType::BloodPressure => "mmHg",
+            MeasurementType::SkinTemperature => "°C",
+            MeasurementType::SleepStages => "min",
+            MeasurementType::Vo2Max => "ml/kg/min",
+        }
+    }
+
+    /// How long a reading stays relevant for estimation.
+    /// Fitness markers change over weeks, acute signals over hours.
+    fn lookback_hours(&self) -> i64 {
+        match self {
+            MeasurementType::Vo2Max => 24 * 30,
+            MeasurementType::RestingHeartRate => 48,
+            _ => 24,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -168,6 +299,7 @@ pub struct PhysiologicalConstraintApplied {
 // CONTEXT WINDOWS
 // ============================================================================
 
+#[derive(Debug, Clone, Copy)]
 pub struct ContextConfig {
     pub window_hours: i64,
     pub decay_half_life_hours: f64,
@@ -204,6 +336,10 @@ impl ContextConfig {
                 window_hours: 168,
                 decay_half_life_hours: 72.0,
             },
+            Primitive::Melatonin => ContextConfig {
+                window_hours: 12,
+                decay_half_life_hours: 0.75,
+            },
         }
     }
     
@@ -243,6 +379,15 @@ impl ContextConfig {
 impl PhysiologicalMeasurement {
     /// Generate constraints based on this measurement's value
     pub fn generate_constraints(&self, estimation_time: DateTime<Utc>) -> Vec<PhysiologicalConstraint> {
+        self.constraints_with_parameters(estimation_time, &ModelParameters::default())
+    }
+
+    /// Generate constraints using the thresholds of a parameter set
+    pub fn constraints_with_parameters(
+        &self,
+        estimation_time: DateTime<Utc>,
+        params: &ModelParameters,
+    ) -> Vec<PhysiologicalConstraint> {
         let mut constraints = Vec::new();
         
         // Only apply constraints for recent measurements (within 4 hours for acute signals)
@@ -250,16 +395,16 @@ impl PhysiologicalMeasurement {
         
         match self.measurement_type {
             MeasurementType::HeartRateVariability => {
-                self.generate_hrv_constraints(&mut constraints, hours_ago);
+                self.generate_hrv_constraints(&mut constraints, hours_ago, params);
             },
             MeasurementType::HeartRate => {
-                self.generate_hr_constraints(&mut constraints, hours_ago);
+                self.generate_hr_constraints(&mut constraints, hours_ago, params);
             },
             MeasurementType::BloodOxygen => {
                 self.generate_spo2_constraints(&mut constraints, hours_ago);
             },
             MeasurementType::BloodGlucose => {
-                self.generate_glucose_constraints(&mut constraints, hours_ago);
+                self.generate_glucose_constraints(&mut constraints, hours_ago, params);
             },
             MeasurementType::BodyTemperature => {
                 self.generate_temperature_constraints(&mut constraints, hours_ago);
@@ -270,12 +415,31 @@ impl PhysiologicalMeasurement {
             MeasurementType::Steps => {
                 self.generate_steps_constraints(&mut constraints, hours_ago);
             },
+            MeasurementType::ElectrodermalActivity => {
+                self.generate_eda_constraints(&mut constraints, hours_ago);
+            },
+            MeasurementType::BloodPressure => {
+                self.generate_blood_pressure_constraints(&mut constraints, hours_ago);
+            },
+            MeasurementType::SkinTemperature => {
+                self.generate_skin_temperature_constraints(&mut constraints, hours_ago);
+            },
+            MeasurementType::SleepStages => {
+                self.generate_sleep_stage_constraints(&mut constraints, hours_ago);
+            },
+            MeasurementType::RestingHeartRate => {
+                self.generate_resting_hr_constraints(&mut constraints, hours_ago);
+            },
+            MeasurementType::Vo2Max => {
+                // VO2max is a fitness marker, not an acute signal. It is used as
+                // context in apply_physiological_validation rather than as a constraint.
+            },
         }
         
         constraints
     }
     
-    fn generate_hrv_constraints(&self, constraints: &mut Vec<PhysiologicalConstraint>, hours_ago: f64) {
+    fn generate_hrv_constraints(&self, constraints: &mut Vec<PhysiologicalConstraint>, hours_ago: f64, params: &ModelParameters) {
         // HRV (RMSSD in ms) - only apply for recent measurements (< 2 hours)
         if hours_ago > 2.0 {
             return;
@@ -284,7 +448,7 @@ impl PhysiologicalMeasurement {
         let rmssd = self.value;
         
         // Very low HRV (<30ms) indicates high sympathetic activation
-        if rmssd < 30.0 {
+        if rmssd < params.hrv_low_ms {
             // High cortisol floor
             constraints.push(PhysiologicalConstraint {
                 source_measurement: MeasurementType::HeartRateVariability,
@@ -305,7 +469,7 @@ impl PhysiologicalMeasurement {
         }
         
         // High HRV (>70ms) indicates good parasympathetic tone
-        if rmssd > 70.0 {
+        if rmssd > params.hrv_high_ms {
             constraints.push(PhysiologicalConstraint {
                 source_measurement: MeasurementType::HeartRateVariability,
                 source_value: rmssd,
@@ -316,7 +480,7 @@ impl PhysiologicalMeasurement {
         }
     }
     
-    fn generate_hr_constraints(&self, constraints: &mut Vec<PhysiologicalConstraint>, hours_ago: f64) {
+    fn generate_hr_constraints(&self, constraints: &mut Vec<PhysiologicalConstraint>, hours_ago: f64, params: &ModelParameters) {
         // Heart rate (BPM) - only apply for recent measurements (< 1 hour)
         if hours_ago > 1.0 {
             return;
@@ -325,7 +489,7 @@ impl PhysiologicalMeasurement {
         let hr = self.value;
         
         // Elevated resting HR (>80 bpm) suggests sympathetic activation
-        if hr > 80.0 {
+        if hr > params.hr_elevated_bpm {
             constraints.push(PhysiologicalConstraint {
                 source_measurement: MeasurementType::HeartRate,
                 source_value: hr,
@@ -345,7 +509,7 @@ impl PhysiologicalMeasurement {
         }
         
         // Very low resting HR (<55 bpm) in non-athletes suggests low arousal
-        if hr < 55.0 {
+        if hr < params.hr_low_bpm {
             constraints.push(PhysiologicalConstraint {
                 source_measurement: MeasurementType::HeartRate,
                 source_value: hr,
@@ -372,7 +536,7 @@ impl PhysiologicalMeasurement {
         }
     }
     
-    fn generate_glucose_constraints(&self, constraints: &mut Vec<PhysiologicalConstraint>, hours_ago: f64) {
+    fn generate_glucose_constraints(&self, constraints: &mut Vec<PhysiologicalConstraint>, hours_ago: f64, params: &ModelParameters) {
         // Blood glucose (mg/dL) - only apply for recent measurements (< 2 hours)
         if hours_ago > 2.0 {
             return;
@@ -381,7 +545,7 @@ impl PhysiologicalMeasurement {
         let glucose_mg_dl = self.value;
         
         // Hypoglycemia (<70 mg/dL) triggers cortisol release
-        if glucose_mg_dl < 70.0 {
+        if glucose_mg_dl < params.glucose_low_mg_dl {
             constraints.push(PhysiologicalConstraint {
                 source_measurement: MeasurementType::BloodGlucose,
                 source_value: glucose_mg_dl,
@@ -400,7 +564,7 @@ impl PhysiologicalMeasurement {
         }
         
         // Normal glucose (70-100 mg/dL)
-        if glucose_mg_dl >= 70.0 && glucose_mg_dl <= 100.0 {
+        if (params.glucose_low_mg_dl..=100.0).contains(&glucose_mg_dl) {
             let normalized_score = 0.5 + ((glucose_mg_dl - 85.0) / 30.0).clamp(-0.3, 0.3);
             constraints.push(PhysiologicalConstraint {
                 source_measurement: MeasurementType::BloodGlucose,
@@ -412,7 +576,7 @@ impl PhysiologicalMeasurement {
         }
         
         // Hyperglycemia (>140 mg/dL)
-        if glucose_mg_dl > 140.0 {
+        if glucose_mg_dl > params.glucose_high_mg_dl {
             constraints.push(PhysiologicalConstraint {
                 source_measurement: MeasurementType::BloodGlucose,
                 source_value: glucose_mg_dl,
@@ -470,7 +634,7 @@ impl PhysiologicalMeasurement {
     
     fn generate_steps_constraints(&self, constraints: &mut Vec<PhysiologicalConstraint>, hours_ago: f64) {
         // Daily step count - only check if looking at a full day window
-        if hours_ago < 12.0 || hours_ago > 30.0 {
+        if !(12.0..=30.0).contains(&hours_ago) {
             return;
         }
         
@@ -487,37 +651,325 @@ impl PhysiologicalMeasurement {
             });
         }
     }
+    
+    fn generate_eda_constraints(&self, constraints: &mut Vec<PhysiologicalConstraint>, hours_ago: f64) {
+        // Electrodermal activity (tonic skin conductance, µS) - sympathetic arousal marker
+        // Sweat glands are purely sympathetically innervated, so EDA tracks norepinephrine
+        if hours_ago > 1.0 {
+            return;
+        }
+        
+        let scl = self.value;
+        let peaks_per_minute = self.components.get("peaks_per_minute").copied().unwrap_or(0.0);
+        
+        // High tonic level or frequent phasic responses (>5 SCRs/min) indicate arousal
+        if scl > 8.0 || peaks_per_minute > 5.0 {
+            constraints.push(PhysiologicalConstraint {
+                source_measurement: MeasurementType::ElectrodermalActivity,
+                source_value: scl,
+                affects_primitive: Primitive::Norepinephrine,
+                constraint_type: ConstraintType::Floor(0.55),
+                reason: format!("High electrodermal activity ({:.1}µS, {:.1} peaks/min) indicates sympathetic arousal", scl, peaks_per_minute),
+            });
+            
+            constraints.push(PhysiologicalConstraint {
+                source_measurement: MeasurementType::ElectrodermalActivity,
+                source_value: scl,
+                affects_primitive: Primitive::Cortisol,
+                constraint_type: ConstraintType::Floor(0.5),
+                reason: format!("Sustained skin conductance ({:.1}µS) accompanies stress-axis activation", scl),
+            });
+        }
+        
+        // Very low tonic level with no phasic activity indicates low arousal
+        if scl < 0.5 && peaks_per_minute < 1.0 {
+            constraints.push(PhysiologicalConstraint {
+                source_measurement: MeasurementType::ElectrodermalActivity,
+                source_value: scl,
+                affects_primitive: Primitive::Norepinephrine,
+                constraint_type: ConstraintType::Ceiling(0.45),
+                reason: format!("Low electrodermal activity ({:.2}µS) indicates low sympathetic arousal", scl),
+            });
+        }
+    }
+    
+    fn generate_blood_pressure_constraints(&self, constraints: &mut Vec<PhysiologicalConstraint>, hours_ago: f64) {
+        // Blood pressure (mmHg) - only apply for recent measurements (< 2 hours)
+        if hours_ago > 2.0 {
+            return;
+        }
+        
+        let systolic = self.value;
+        let diastolic = self.components.get("diastolic").copied().unwrap_or(0.0);
+        
+        // Hypertensive reading (>=140/90) reflects sympathetic and HPA activation
+        if systolic >= 140.0 || diastolic >= 90.0 {
+            constraints.push(PhysiologicalConstraint {
+                source_measurement: MeasurementType::BloodPressure,
+                source_value: systolic,
+                affects_primitive: Primitive::Norepinephrine,
+                constraint_type: ConstraintType::Floor(0.5),
+                reason: format!("Elevated blood pressure ({:.0}/{:.0} mmHg) indicates sympathetic activation", systolic, diastolic),
+            });
+            
+            constraints.push(PhysiologicalConstraint {
+                source_measurement: MeasurementType::BloodPressure,
+                source_value: systolic,
+                affects_primitive: Primitive::Cortisol,
+                constraint_type: ConstraintType::Floor(0.5),
+                reason: format!("Elevated blood pressure ({:.0}/{:.0} mmHg) suggests stress response", systolic, diastolic),
+            });
+        }
+        
+        // Low systolic pressure (<95 mmHg) indicates low sympathetic tone
+        if systolic < 95.0 {
+            constraints.push(PhysiologicalConstraint {
+                source_measurement: MeasurementType::BloodPressure,
+                source_value: systolic,
+                affects_primitive: Primitive::Norepinephrine,
+                constraint_type: ConstraintType::Ceiling(0.4),
+                reason: format!("Low blood pressure ({:.0}/{:.0} mmHg) indicates low sympathetic tone", systolic, diastolic),
+            });
+        }
+    }
+    
+    fn generate_skin_temperature_constraints(&self, constraints: &mut Vec<PhysiologicalConstraint>, hours_ago: f64) {
+        // Wrist skin temperature deviation from the wearer's baseline (°C)
+        // Nightly deviations flag illness or a displaced circadian rhythm
+        if hours_ago > 24.0 {
+            return;
+        }
+        
+        let deviation = self.value;
+        
+        if deviation.abs() >= 1.0 {
+            constraints.push(PhysiologicalConstraint {
+                source_measurement: MeasurementType::SkinTemperature,
+                source_value: deviation,
+                affects_primitive: Primitive::CircadianPhase,
+                constraint_type: ConstraintType::ConfidencePenalty(0.7),
+                reason: format!("Skin temperature deviation ({:+.1}°C) suggests disrupted circadian rhythm", deviation),
+            });
+        }
+        
+        // Sustained elevation is an early illness marker; inflammation raises sleep pressure
+        if deviation >= 1.0 {
+            constraints.push(PhysiologicalConstraint {
+                source_measurement: MeasurementType::SkinTemperature,
+                source_value: deviation,
+                affects_primitive: Primitive::Adenosine,
+                constraint_type: ConstraintType::Floor(0.5),
+                reason: format!("Elevated skin temperature ({:+.1}°C) suggests illness increasing sleep pressure", deviation),
+            });
+        }
+    }
+    
+    fn generate_sleep_stage_constraints(&self, constraints: &mut Vec<PhysiologicalConstraint>, hours_ago: f64) {
+        // Wearable sleep-stage summary for the last sleep period (minutes per stage)
+        if hours_ago > 12.0 {
+            return;
+        }
+        
+        let deep_minutes = self.components.get("deep_minutes").copied();
+        let rem_minutes = self.components.get("rem_minutes").copied();
+        
+        if let Some(deep) = deep_minutes {
+            // Slow-wave sleep drives adenosine clearance; <45 min leaves residual pressure
+            if deep < 45.0 {
+                constraints.push(PhysiologicalConstraint {
+                    source_measurement: MeasurementType::SleepStages,
+                    source_value: deep,
+                    affects_primitive: Primitive::Adenosine,
+                    constraint_type: ConstraintType::Floor(0.4),
+                    reason: format!("Little deep sleep ({:.0} min) leaves residual sleep pressure", deep),
+                });
+            } else if deep >= 90.0 && hours_ago < 4.0 {
+                constraints.push(PhysiologicalConstraint {
+                    source_measurement: MeasurementType::SleepStages,
+                    source_value: deep,
+                    affects_primitive: Primitive::Adenosine,
+                    constraint_type: ConstraintType::Ceiling(0.5),
+                    reason: format!("Ample deep sleep ({:.0} min) indicates thorough adenosine clearance", deep),
+                });
+            }
+        }
+        
+        // REM supports emotional processing; short REM makes mood estimates less reliable
+        if let Some(rem) = rem_minutes {
+            if rem < 60.0 {
+                constraints.push(PhysiologicalConstraint {
+                    source_measurement: MeasurementType::SleepStages,
+                    source_value: rem,
+                    affects_primitive: Primitive::Serotonin,
+                    constraint_type: ConstraintType::ConfidencePenalty(0.7),
+                    reason: format!("Short REM sleep ({:.0} min) impairs emotional recovery", rem),
+                });
+            }
+        }
+    }
+    
+    fn generate_resting_hr_constraints(&self, constraints: &mut Vec<PhysiologicalConstraint>, hours_ago: f64) {
+        // Daily resting heart rate compared with the wearer's rolling baseline
+        if hours_ago > 36.0 {
+            return;
+        }
+        
+        let resting_hr = self.value;
+        let Some(baseline) = self.components.get("baseline_bpm").copied() else {
+            return;
+        };
+        let elevation = resting_hr - baseline;
+        
+        // Resting HR >=5 bpm above baseline signals incomplete recovery or illness
+        if elevation >= 5.0 {
+            constraints.push(PhysiologicalConstraint {
+                source_measurement: MeasurementType::RestingHeartRate,
+                source_value: resting_hr,
+                affects_primitive: Primitive::Cortisol,
+                constraint_type: ConstraintType::Floor(0.5),
+                reason: format!("Resting HR {:.0} bpm is {:+.0} above baseline, indicating physiological strain", resting_hr, elevation),
+            });
+            
+            constraints.push(PhysiologicalConstraint {
+                source_measurement: MeasurementType::RestingHeartRate,
+                source_value: resting_hr,
+                affects_primitive: Primitive::Dopamine,
+                constraint_type: ConstraintType::ConfidencePenalty(0.7),
+                reason: format!("Elevated resting HR trend ({:+.0} bpm) suggests fatigue not captured by events", elevation),
+            });
+        }
+    }
 }
 
 // ============================================================================
 // RESEARCH-BASED IMPACT COMPUTATION FUNCTIONS (continued from original)
 // ============================================================================
 
+/// Sleep architecture reported by a wearable or polysomnography, when available
+struct SleepArchitecture {
+    deep_minutes: Option<f64>,
+    rem_minutes: Option<f64>,
+    waso_minutes: Option<f64>,
+    onset_latency_minutes: Option<f64>,
+}
+
+impl SleepArchitecture {
+    fn from_event(event: &Event) -> Self {
+        let minutes = |key: &str| event.properties.get(key).and_then(|v| v.as_f64());
+        SleepArchitecture {
+            deep_minutes: minutes("deep_sleep_minutes"),
+            rem_minutes: minutes("rem_sleep_minutes"),
+            waso_minutes: minutes("wake_after_sleep_onset_minutes"),
+            onset_latency_minutes: minutes("sleep_onset_latency_minutes"),
+        }
+    }
+    
+    fn is_empty(&self) -> bool {
+        self.deep_minutes.is_none() && self.rem_minutes.is_none() && self.waso_minutes.is_none()
+    }
+    
+    /// Slow-wave sleep relative to the ~20% of total sleep a healthy adult gets.
+    /// SWS is when adenosine is cleared and insulin sensitivity is restored.
+    fn slow_wave_factor(&self, duration_hours: f64) -> f64 {
+        match self.deep_minutes {
+            Some(deep) if duration_hours > 0.0 => {
+                let expected_deep = duration_hours * 60.0 * 0.20;
+                (deep / expected_deep).clamp(0.5, 1.2)
+            }
+            _ => 1.0,
+        }
+    }
+    
+    /// REM relative to the ~22% of total sleep that supports emotional processing
+    fn rem_factor(&self, duration_hours: f64) -> f64 {
+        match self.rem_minutes {
+            Some(rem) if duration_hours > 0.0 => {
+                let expected_rem = duration_hours * 60.0 * 0.22;
+                (rem / expected_rem).clamp(0.5, 1.2)
+            }
+            _ => 1.0,
+        }
+    }
+    
+    /// Fragmented sleep (WASO beyond ~20 min) clears less adenosine per hour in bed
+    fn fragmentation_factor(&self) -> f64 {
+        match self.waso_minutes {
+            Some(waso) => (1.0 - ((waso - 20.0).max(0.0) / 100.0) * 0.3).clamp(0.7, 1.0),
+            None => 1.0,
+        }
+    }
+    
+    /// Quality score (0.4-1.0, same scale as the subjective quality strings)
+    /// derived from stage proportions and sleep continuity
+    fn derived_quality(&self, duration_hours: f64, sleep_efficiency: f64) -> Option<f64> {
+        if self.is_empty() || duration_hours <= 0.0 {
+            return None;
+        }
+        
+        let sws_component = self.slow_wave_factor(duration_hours).min(1.0);
+        let rem_component = self.rem_factor(duration_hours).min(1.0);
+        let efficiency_component = ((sleep_efficiency - 0.65) / 0.25).clamp(0.0, 1.0);
+        let waso_component = self.waso_minutes.map(|w| (1.0 - w / 60.0).clamp(0.0, 1.0)).unwrap_or(efficiency_component);
+        let latency_component = self.onset_latency_minutes.map(|l| (1.0 - (l - 20.0).max(0.0) / 40.0).clamp(0.0, 1.0)).unwrap_or(1.0);
+        let continuity = efficiency_component * 0.4 + waso_component * 0.4 + latency_component * 0.2;
+        
+        let score = sws_component * 0.4 + rem_component * 0.3 + continuity * 0.3;
+        Some(0.4 + 0.6 * score.clamp(0.0, 1.0))
+    }
+}
+
+/// Sleep efficiency from `sleep_efficiency` (or the `efficiency` alias used in older exports)
+fn sleep_efficiency(event: &Event) -> f64 {
+    event.properties.get("sleep_efficiency")
+        .or_else(|| event.properties.get("efficiency"))
+        .and_then(|v| v.as_f64())
+        .unwrap_or(0.85)
+}
+
+/// Sleep quality score (0.4-1.0). The subjective `quality` string takes precedence;
+/// without it, quality is derived from sleep architecture when the event carries it.
+pub(crate) fn sleep_quality_score(event: &Event) -> f64 {
+    let quality_str = event.properties.get("quality").and_then(|v| v.as_str());
+    
+    match quality_str {
+        Some("excellent") => 1.0,
+        Some("good") => 0.8,
+        Some("fair") => 0.6,
+        Some("poor") => 0.4,
+        Some(_) => 0.8,
+        None => {
+            let duration = sleep::asleep_hours(event);
+            SleepArchitecture::from_event(event)
+                .derived_quality(duration, sleep_efficiency(event))
+                .unwrap_or(0.8)
+        }
+    }
+}
+
 /// Compute impacts of a sleep event on all primitives
 fn compute_sleep_impacts(event: &Event) -> HashMap<String, f64> {
     let mut impacts = HashMap::new();
     
-    let duration = event.properties.get("duration_hours")
-        .and_then(|v| v.as_f64())
-        .unwrap_or(7.0);
+    // Time asleep, reconciled with the record's interval
+    let duration = sleep::asleep_hours(event);
     
-    let quality_str = event.properties.get("quality")
-        .and_then(|v| v.as_str())
-        .unwrap_or("good");
+    let quality_score = sleep_quality_score(event);
+    let sleep_efficiency = sleep_efficiency(event);
     
-    let quality_score = match quality_str {
-        "excellent" => 1.0,
-        "good" => 0.8,
-        "fair" => 0.6,
-        "poor" => 0.4,
-        _ => 0.8,
+    // Architecture factors are 1.0 when the event has no stage data
+    let architecture = SleepArchitecture::from_event(event);
+    let slow_wave_factor = architecture.slow_wave_factor(duration);
+    // A quality derived from architecture already weighs SWS, REM and continuity,
+    // so the stage factors only scale a subjective quality
+    let quality_derived = event.properties.get("quality").and_then(|v| v.as_str()).is_none() && !architecture.is_empty();
+    let (stage_slow_wave_factor, rem_factor, fragmentation_factor) = if quality_derived {
+        (1.0, 1.0, 1.0)
+    } else {
+        (slow_wave_factor, architecture.rem_factor(duration), architecture.fragmentation_factor())
     };
     
-    let sleep_efficiency = event.properties.get("sleep_efficiency")
-        .and_then(|v| v.as_f64())
-        .unwrap_or(0.85);
-    
-    let adenosine_clearance = -(duration / 7.5) * quality_score * 0.85;
+    // Clearance happens mainly during slow-wave sleep; awakenings interrupt it
+    let adenosine_clearance = -(duration / 7.5) * quality_score * 0.85 * stage_slow_wave_factor * fragmentation_factor;
     impacts.insert("adenosine".to_string(), adenosine_clearance);
     
     let dopamine_impact = if duration >= 7.0 {
@@ -529,7 +981,8 @@ fn compute_sleep_impacts(event: &Event) -> HashMap<String, f64> {
     };
     impacts.insert("dopamine".to_string(), dopamine_impact);
     
-    let serotonin_impact = 0.25 * quality_score * (duration / 7.5).min(1.0);
+    // REM sleep supports overnight emotional recovery
+    let serotonin_impact = 0.25 * quality_score * (duration / 7.5).min(1.0) * rem_factor;
     impacts.insert("serotonin".to_string(), serotonin_impact);
     
     // Adjusted to work with circadian baseline system
@@ -542,17 +995,18 @@ fn compute_sleep_impacts(event: &Event) -> HashMap<String, f64> {
     };
     impacts.insert("cortisol".to_string(), cortisol_impact);
     
+    // Suppressing slow-wave sleep impairs insulin sensitivity even at normal duration
     let glucose_impact = if duration >= 6.0 && sleep_efficiency >= 0.67 {
-        0.2
+        0.2 * (2.0 * slow_wave_factor.min(1.0) - 1.0)
     } else {
         -0.3 * (1.0 - (duration / 6.0).min(1.0))
     };
     impacts.insert("glucose".to_string(), glucose_impact);
     
     let hour_of_sleep = event.timestamp.hour() as f64;
-    let phase_impact = if hour_of_sleep >= 22.0 && hour_of_sleep <= 24.0 {
+    let phase_impact = if (22.0..=24.0).contains(&hour_of_sleep) {
         0.0
-    } else if hour_of_sleep >= 0.0 && hour_of_sleep <= 2.0 {
+    } else if (0.0..=2.0).contains(&hour_of_sleep) {
         0.2 * (hour_of_sleep / 2.0)
     } else {
         0.0
@@ -562,60 +1016,31 @@ fn compute_sleep_impacts(event: &Event) -> HashMap<String, f64> {
     impacts
 }
 
-fn compute_light_impacts(event: &Event, _estimation_time: DateTime<Utc>) -> HashMap<String, f64> {
+/// Light acts through melanopic EDI: each minute's photic drive is weighted by
+/// the phase response at its clock time, so a long day under office light and a
+/// short bright walk outdoors give different phase shifts, serotonin and CAR boosts
+fn compute_light_impacts(event: &Event, estimation_time: DateTime<Utc>) -> HashMap<String, f64> {
     let mut impacts = HashMap::new();
-    
-    let intensity = event.properties.get("intensity_lux")
-        .and_then(|v| v.as_f64())
-        .unwrap_or(1000.0);
-    
-    let duration_min = event.properties.get("duration_minutes")
-        .and_then(|v| v.as_f64())
-        .unwrap_or(30.0);
-    
-    let hour_of_day = event.timestamp.hour() as f64;
-    let is_morning = hour_of_day >= 6.0 && hour_of_day <= 11.0;
-    let is_evening = hour_of_day >= 18.0 && hour_of_day <= 22.0;
-    
-    let circadian_impact = if intensity >= 2000.0 {
-        if is_morning {
-            -0.8 * (duration_min / 120.0).min(1.0)
-        } else if is_evening {
-            0.6 * (duration_min / 120.0).min(1.0)
-        } else {
-            -0.2
-        }
-    } else if intensity >= 100.0 {
-        if is_morning {
-            -0.3
-        } else if is_evening {
-            0.3
-        } else {
-            0.0
-        }
-    } else {
-        0.0
-    };
-    impacts.insert("circadian_phase".to_string(), circadian_impact);
-    
-    if is_morning && intensity >= 2000.0 {
-        let serotonin_impact = 0.25 * (intensity / 10000.0).min(1.0) * (duration_min / 30.0).min(1.0);
+    // Only light already seen: an ongoing exposure's dose grows as its samples arrive
+    let samples: Vec<light::LightSample> = light::samples(event)
+        .into_iter()
+        .filter(|sample| sample.time < estimation_time)
+        .collect();
+    let dose = light::PhoticDose::of(&samples);
+
+    impacts.insert("circadian_phase".to_string(), dose.phase_shift());
+
+    let serotonin_impact = dose.serotonin();
+    if serotonin_impact > 0.0 {
         impacts.insert("serotonin".to_string(), serotonin_impact);
     }
-    
-    if is_morning {
+
+    if dose.morning_peak > 0.0 {
         // Morning light enhances CAR, but the awakening_boost function already models this
         // Direct cortisol impact should be modest to avoid double-counting
-        let cortisol_impact = if intensity >= 5000.0 {
-            0.08  // Reduced from 0.2
-        } else if intensity >= 800.0 {
-            0.05  // Reduced from 0.15
-        } else {
-            0.02  // Reduced from 0.05
-        };
-        impacts.insert("cortisol".to_string(), cortisol_impact);
+        impacts.insert("cortisol".to_string(), 0.08 * dose.morning_peak);
     }
-    
+
     impacts
 }
 
@@ -675,8 +1100,8 @@ fn compute_meal_impacts(event: &Event) -> HashMap<String, f64> {
     
     let hour_of_meal = event.timestamp.hour() as f64;
     let circadian_impact = match meal_type {
-        "breakfast" if hour_of_meal >= 6.0 && hour_of_meal <= 9.0 => 0.1,
-        "dinner" if hour_of_meal >= 18.0 && hour_of_meal <= 20.0 => 0.0,
+        "breakfast" if (6.0..=9.0).contains(&hour_of_meal) => 0.1,
+        "dinner" if (18.0..=20.0).contains(&hour_of_meal) => 0.0,
         "dinner" if hour_of_meal >= 21.0 => 0.2,
         _ => 0.0,
     };
@@ -685,16 +1110,16 @@ fn compute_meal_impacts(event: &Event) -> HashMap<String, f64> {
     impacts
 }
 
-fn compute_caffeine_impacts(event: &Event) -> HashMap<String, f64> {
+fn compute_caffeine_impacts(event: &Event, params: &ModelParameters) -> HashMap<String, f64> {
     let mut impacts = HashMap::new();
     
     let dose_mg = event.properties.get("dose_mg")
         .and_then(|v| v.as_f64())
         .unwrap_or(100.0);
     
-    let a2a_occupancy = (dose_mg / (dose_mg + 65.0)).min(1.0);
+    let a2a_occupancy = (dose_mg / (dose_mg + params.caffeine_ed50_mg)).min(1.0);
     
-    impacts.insert("adenosine".to_string(), -0.5 * a2a_occupancy);
+    impacts.insert("adenosine".to_string(), -params.caffeine_adenosine_blockade * a2a_occupancy);
     
     let dopamine_impact = 0.15 * (dose_mg / 200.0).min(1.0);
     impacts.insert("dopamine".to_string(), dopamine_impact);
@@ -788,13 +1213,6 @@ fn compute_nap_impacts(event: &Event) -> HashMap<String, f64> {
         .and_then(|v| v.as_f64())
         .unwrap_or(20.0);
     
-    let adenosine_clearance = if duration_min <= 30.0 {
-        -0.25 * (duration_min / 30.0)
-    } else {
-        -0.4 * (duration_min / 90.0).min(1.0)
-    };
-    impacts.insert("adenosine".to_string(), adenosine_clearance);
-    
     if duration_min >= 60.0 {
         impacts.insert("circadian_phase".to_string(), 0.15);
     }
@@ -922,6 +1340,68 @@ fn compute_screen_impacts(event: &Event) -> HashMap<String, f64> {
     impacts
 }
 
+/// Fraction of melatonin secretion suppressed by light at the eye (melanopic EDI).
+/// Half-maximal near 100 lux, saturating at 90% (Zeitzer et al., 2000).
+fn melatonin_light_suppression(melanopic_edi: f64) -> f64 {
+    0.9 * melanopic_edi / (melanopic_edi + 100.0)
+}
+
+/// Suppression by one light or screen event at `estimation_time`: full while
+/// the exposure lasts, recovering with a ~30 min half-life after it ends. A
+/// light series is followed sample by sample, so the latest light counts most.
+fn melatonin_suppression_at(event: &Event, estimation_time: DateTime<Utc>) -> f64 {
+    if event.timestamp > estimation_time {
+        return 0.0;
+    }
+    let recovered = |end: DateTime<Utc>| 0.5f64.powf(((estimation_time - end).num_minutes() as f64 / 60.0).max(0.0) / 0.5);
+    match event.event_type.as_str() {
+        "light_exposure" | "light_series" => light::samples(event)
+            .iter()
+            .filter(|sample| sample.time <= estimation_time)
+            .map(|sample| melatonin_light_suppression(sample.melanopic_edi) * recovered(sample.end()))
+            .fold(0.0, f64::max),
+        "screen_time" => {
+            // Equivalent illuminance of a screen at reading distance
+            let lux = match event.properties.get("blue_light_intensity").and_then(|v| v.as_str()).unwrap_or("medium") {
+                "low" => 10.0,
+                "high" => 60.0,
+                _ => 30.0,
+            };
+            let duration_min = event.properties.get("duration_minutes")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(30.0);
+            let end = event.end_timestamp.unwrap_or(event.timestamp + Duration::minutes(duration_min as i64));
+            melatonin_light_suppression(lux) * recovered(end)
+        }
+        _ => 0.0,
+    }
+}
+
+/// Plasma melatonin from a supplement, relative to the night-time peak.
+/// Immediate release peaks ~45 min after the dose and clears with a ~45 min
+/// half-life; extended release is absorbed over hours. 0.3 mg already gives
+/// physiological night-time levels, so the dose response saturates.
+fn exogenous_melatonin(event: &Event, estimation_time: DateTime<Utc>) -> f64 {
+    let hours_ago = (estimation_time - event.timestamp).num_minutes() as f64 / 60.0;
+    if hours_ago < 0.0 {
+        return 0.0;
+    }
+    let dose_mg = event.properties.get("dose_mg")
+        .and_then(|v| v.as_f64())
+        .unwrap_or(1.0);
+    let extended = event.properties.get("formulation")
+        .and_then(|v| v.as_str())
+        .is_some_and(|f| f == "extended_release");
+
+    // One-compartment absorption and elimination, normalized to a peak of 1
+    let (absorption, elimination) = if extended { (0.5, 0.25) } else { (3.0, 0.92) };
+    let curve = |t: f64| (-elimination * t).exp() - (-absorption * t).exp();
+    let t_max = (absorption / elimination).ln() / (absorption - elimination);
+    let concentration = curve(hours_ago) / curve(t_max);
+
+    dose_mg / (dose_mg + 0.3) * concentration
+}
+
 fn compute_interruption_impacts(event: &Event) -> HashMap<String, f64> {
     let mut impacts = HashMap::new();
     
@@ -945,10 +1425,28 @@ fn compute_interruption_impacts(event: &Event) -> HashMap<String, f64> {
 // PRIMITIVE ESTIMATOR (continued)
 // ============================================================================
 
+#[derive(Clone)]
 pub struct PrimitiveEstimator {
-    baseline_values: HashMap<String, f64>,
-    current_adenosine_level: Cell<f64>,
+    registry: PrimitiveRegistry,  // Primitives to compute, in order; ADHD mode lowers catecholamine baselines
     adhd_mode: bool,  // NEW: Toggle ADHD-specific neural dynamics
+    params: ModelParameters,
+    states: StateClassifier,
+    chronotype: Option<Chronotype>,  // Set explicitly; inferred from free days when None
+}
+
+/// Summaries of weeks of history that several passes read. Computed once per
+/// estimate rather than by each primitive or sequence that needs them.
+struct HistoryContext {
+    chronotype: Chronotype,
+    sleep_metrics: SleepMetrics,
+    training: TrainingLoad,
+    allostatic_load: AllostaticLoad,
+}
+
+impl Default for PrimitiveEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PrimitiveEstimator {
@@ -957,76 +1455,66 @@ impl PrimitiveEstimator {
     }
     
     pub fn with_adhd_mode(adhd_mode: bool) -> Self {
-        let mut baseline_values = HashMap::new();
-        
-        // ADHD mode: Lower baseline dopamine and norepinephrine
-        // Research shows reduced baseline catecholamine signaling in ADHD
-        if adhd_mode {
-            baseline_values.insert("dopamine".to_string(), 0.35);  // Lower baseline (normal: 0.5)
-            baseline_values.insert("norepinephrine".to_string(), 0.38);  // Lower baseline (normal: 0.5)
-            baseline_values.insert("serotonin".to_string(), 0.5);
-            baseline_values.insert("adenosine".to_string(), 0.3);
-            baseline_values.insert("circadian_phase".to_string(), 0.5);
-            baseline_values.insert("cortisol".to_string(), 0.4);
-            baseline_values.insert("glucose".to_string(), 0.5);
-        } else {
-            baseline_values.insert("dopamine".to_string(), 0.5);
-            baseline_values.insert("norepinephrine".to_string(), 0.5);
-            baseline_values.insert("serotonin".to_string(), 0.5);
-            baseline_values.insert("adenosine".to_string(), 0.3);
-            baseline_values.insert("circadian_phase".to_string(), 0.5);
-            baseline_values.insert("cortisol".to_string(), 0.4);
-            baseline_values.insert("glucose".to_string(), 0.5);
-        }
-
         PrimitiveEstimator {
-            baseline_values,
-            current_adenosine_level: Cell::new(0.3),
+            registry: PrimitiveRegistry::builtin(adhd_mode),
             adhd_mode,
+            params: ModelParameters::default(),
+            states: StateClassifier::default(),
+            chronotype: None,
         }
     }
 
+    /// Use a calibrated parameter set instead of the defaults
+    pub fn with_parameters(mut self, params: ModelParameters) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Classify functional states with custom rules instead of the built-in set
+    pub fn with_state_classifier(mut self, states: StateClassifier) -> Self {
+        self.states = states;
+        self
+    }
+
+    /// Score circadian alignment against a known chronotype instead of inferring it
+    pub fn with_chronotype(mut self, chronotype: Chronotype) -> Self {
+        self.chronotype = Some(chronotype);
+        self
+    }
+
+    /// The configured chronotype, or one inferred from free-day sleep before `time`
+    pub fn chronotype(&self, store: &EventStore, time: DateTime<Utc>) -> Chronotype {
+        self.chronotype.clone().unwrap_or_else(|| Chronotype::infer(store, time, &BTreeMap::new()))
+    }
+
+    /// Estimate an additional primitive after the registered ones
+    pub fn with_primitive(mut self, definition: PrimitiveDefinition) -> Result<Self, String> {
+        self.registry.register(definition)?;
+        Ok(self)
+    }
+
+    pub fn registry(&self) -> &PrimitiveRegistry {
+        &self.registry
+    }
+
+    pub fn parameters(&self) -> &ModelParameters {
+        &self.params
+    }
+
+    pub fn is_adhd_mode(&self) -> bool {
+        self.adhd_mode
+    }
+
+    /// Resting baseline of a primitive, including any calibrated shift
+    fn baseline(&self, primitive: Primitive) -> f64 {
+        self.registry.get(primitive).map(|d| d.baseline).unwrap_or(0.5)
+            + self.params.baseline_shift(primitive)
+    }
+
     // ========================================================================
     // ADHD-SPECIFIC NEURAL DYNAMICS
     // ========================================================================
     
-    /// Calculate temporal discounting coefficient for ADHD
-    /// ADHD exhibits steep temporal discounting - future rewards are heavily devalued
-    /// Returns a multiplier (0.0-1.0) based on how far in the future the reward/impact is
-    fn adhd_temporal_discount(&self, hours_ago: f64) -> f64 {
-        if !self.adhd_mode {
-            return 1.0;  // No discounting in normal mode
-        }
-        
-        // ADHD temporal discounting: steep hyperbolic function
-        // Recent events (last 2 hours) have full impact
-        // Future-oriented or delayed events decay rapidly
-        // Based on research showing ADHD brains discount delayed rewards by ~50-70%
-        
-        if hours_ago < 2.0 {
-            1.0  // Full impact for very recent/immediate events
-        } else {
-            // Hyperbolic discounting: 1 / (1 + k * delay)
-            // k = 0.3 creates steep ADHD-characteristic curve
-            let k = 0.3;
-            1.0 / (1.0 + k * (hours_ago - 2.0))
-        }
-    }
-    
-    /// Modify dopamine impact based on reward immediacy (ADHD-specific)
-    /// Immediate rewards produce STRONGER dopamine response in ADHD
-    /// Delayed rewards produce WEAKER dopamine response
-    fn adhd_reward_modulation(&self, base_impact: f64, reward_immediacy: f64) -> f64 {
-        if !self.adhd_mode {
-            return base_impact;
-        }
-        
-        // reward_immediacy: 1.0 = immediate, 0.0 = very delayed
-        // ADHD: amplify immediate (1.0-1.3x), reduce delayed (0.5-0.7x)
-        let modulation = 0.7 + (0.6 * reward_immediacy);
-        base_impact * modulation
-    }
-    
     /// Calculate ADHD-specific decay rate
     /// Dopamine/NE decay faster in ADHD, especially for non-immediate rewards
     fn adhd_decay_modifier(&self, primitive: Primitive) -> f64 {
@@ -1041,6 +1529,11 @@ impl PrimitiveEstimator {
         }
     }
     
+    /// How much more strongly adenosine and cortisol suppress dopamine
+    fn adhd_suppression_multiplier(&self) -> f64 {
+        if self.adhd_mode { 1.4 } else { 1.0 }
+    }
+
     /// Add ADHD variability/noise to primitive levels
     /// Models fluctuating arousal and attention typical of ADHD
     fn adhd_add_variability(&self, base_score: f64, primitive: Primitive) -> f64 {
@@ -1068,93 +1561,225 @@ impl PrimitiveEstimator {
         events: &[Event],
         estimation_time: DateTime<Utc>,
     ) -> EstimationResult {
-        println!("\n=== Estimating Primitives at {} ===", estimation_time);
+        let store = EventStore::new(events);
+        self.estimate_with_store(&store, estimation_time)
+    }
 
-        // PASS 1: Compute base scores from behavioral events
-        let mut base_scores = HashMap::new();
+    /// Estimate from a prepared event store. Build the store once when
+    /// estimating many time points over the same events (e.g. a timeline).
+    pub fn estimate_with_store(
+        &self,
+        store: &EventStore,
+        estimation_time: DateTime<Utc>,
+    ) -> EstimationResult {
+        println!("\n=== Estimating Primitives at {} ===", estimation_time);
+        self.run_passes(store, estimation_time)
+    }
+
+    /// Estimate and record how every pass changed every primitive (explain mode).
+    /// The trace is returned in `EstimationResult::trace`.
+    pub fn estimate_with_trace(
+        &self,
+        store: &EventStore,
+        estimation_time: DateTime<Utc>,
+    ) -> EstimationResult {
+        let mut trace = EstimationTrace::default();
+        if self.adhd_mode {
+            let pick = |keys: &[Primitive], value: &dyn Fn(Primitive) -> f64| -> BTreeMap<String, f64> {
+                keys.iter().map(|&p| (p.as_str().to_string(), value(p))).collect()
+            };
+            let catecholamines = [Primitive::Dopamine, Primitive::Norepinephrine];
+            let adhd = AdhdTrace {
+                baselines: pick(&catecholamines, &|p| self.registry.get(p).map(|d| d.baseline).unwrap_or(0.5)),
+                decay_modifiers: pick(&catecholamines, &|p| self.adhd_decay_modifier(p)),
+                suppression_multiplier: self.adhd_suppression_multiplier(),
+            };
+            trace.adhd = Some(adhd);
+        }
+
+        println!("\n=== Estimating Primitives at {} ===", estimation_time);
+        let mut result = self.run_traced_passes(store, estimation_time, &BTreeMap::new(), Some(&mut trace));
+        result.trace = Some(trace);
+        result
+    }
+
+    /// The five estimation passes, without logging. Used directly by analyses
+    /// that re-estimate the same time point many times (e.g. attribution).
+    pub(crate) fn run_passes(
+        &self,
+        store: &EventStore,
+        estimation_time: DateTime<Utc>,
+    ) -> EstimationResult {
+        self.run_traced_passes(store, estimation_time, &BTreeMap::new(), None)
+    }
+
+    /// The estimation passes for a live feed, whose multi-week history before
+    /// the events in `store` is read from its daily summaries
+    pub(crate) fn run_passes_with_summaries(
+        &self,
+        store: &EventStore,
+        estimation_time: DateTime<Utc>,
+        summaries: &BTreeMap<NaiveDate, DailySummary>,
+    ) -> EstimationResult {
+        self.run_traced_passes(store, estimation_time, summaries, None)
+    }
+
+    /// The estimation passes, adding each step to `trace` when one is given
+    fn run_traced_passes(
+        &self,
+        store: &EventStore,
+        estimation_time: DateTime<Utc>,
+        summaries: &BTreeMap<NaiveDate, DailySummary>,
+        mut trace: Option<&mut EstimationTrace>,
+    ) -> EstimationResult {
+        let history = HistoryContext {
+            chronotype: self
+                .chronotype
+                .clone()
+                .unwrap_or_else(|| Chronotype::infer(store, estimation_time, summaries)),
+            sleep_metrics: SleepMetrics::compute(store, estimation_time, summaries),
+            training: TrainingLoad::compute(store, estimation_time, summaries),
+            allostatic_load: AllostaticLoad::compute(store, estimation_time, summaries),
+        };
+
+        // PASS 1: Compute base scores from behavioral events
+        let mut base_scores = HashMap::new();
         let mut all_contributors = HashMap::new();
         let mut acute_scores = HashMap::new();
         let mut chronic_scores = HashMap::new();
 
-        // Compute adenosine first (needed for circadian gating)
-        let (adenosine_score, adenosine_contributors) = self.compute_base_score(
-            Primitive::Adenosine,
-            events,
-            estimation_time,
-        );
-        base_scores.insert("adenosine".to_string(), adenosine_score);
-        all_contributors.insert("adenosine".to_string(), adenosine_contributors);
-        self.current_adenosine_level.set(adenosine_score);
-        
-        // Then compute circadian phase
-        let (circadian_score, circadian_contributors) = self.compute_base_score(
-            Primitive::CircadianPhase,
-            events,
-            estimation_time,
-        );
-        base_scores.insert("circadian_phase".to_string(), circadian_score);
-        all_contributors.insert("circadian_phase".to_string(), circadian_contributors);
-        
-        // Compute dopamine and serotonin with acute/chronic breakdown
-        let (da_acute, da_chronic, da_combined, da_contributors) = 
-            self.compute_monoamine_scores(Primitive::Dopamine, events, estimation_time);
-        base_scores.insert("dopamine".to_string(), da_combined);
-        all_contributors.insert("dopamine".to_string(), da_contributors);
-        acute_scores.insert("dopamine".to_string(), da_acute);
-        chronic_scores.insert("dopamine".to_string(), da_chronic);
-        
-        let (ser_acute, ser_chronic, ser_combined, ser_contributors) = 
-            self.compute_monoamine_scores(Primitive::Serotonin, events, estimation_time);
-        base_scores.insert("serotonin".to_string(), ser_combined);
-        all_contributors.insert("serotonin".to_string(), ser_contributors);
-        acute_scores.insert("serotonin".to_string(), ser_acute);
-        chronic_scores.insert("serotonin".to_string(), ser_chronic);
-        
-        // Compute remaining primitives
-        for primitive in Primitive::all() {
-            let key = primitive.as_str();
-            if key == "adenosine" || key == "circadian_phase" || key == "dopamine" || key == "serotonin" {
-                continue;
+        // In registry order, so dependencies (e.g. adenosine for circadian gating) come first
+        for definition in self.registry.definitions() {
+            let key = definition.name.clone();
+            let (score, contributors) = match &definition.computation {
+                Computation::Builtin(primitive @ (Primitive::Dopamine | Primitive::Serotonin)) => {
+                    // Acute/chronic breakdown
+                    let (acute, chronic, combined, contributors) =
+                        self.compute_monoamine_scores(*primitive, store, estimation_time);
+                    acute_scores.insert(key.clone(), acute);
+                    chronic_scores.insert(key.clone(), chronic);
+                    (combined, contributors)
+                }
+                Computation::Builtin(primitive) => {
+                    self.compute_base_score(*primitive, store, estimation_time, &history, trace.as_deref_mut())
+                }
+                Computation::EventImpacts(impacts) => self.compute_event_driven_score(definition, impacts, store, estimation_time),
+                Computation::Derived(score) => {
+                    let inputs: BTreeMap<String, f64> = definition
+                        .dependencies
+                        .iter()
+                        .map(|d| (d.clone(), base_scores.get(d).copied().unwrap_or(0.5)))
+                        .collect();
+                    (score(&inputs).clamp(0.0, 1.0), Vec::new())
+                }
+            };
+            base_scores.insert(key.clone(), score);
+            all_contributors.insert(key, contributors);
+        }
+        let adenosine_score = base_scores.get(Primitive::Adenosine.as_str()).copied().unwrap_or(0.3);
+        let circadian_score = base_scores.get(Primitive::CircadianPhase.as_str()).copied().unwrap_or(0.5);
+
+        if let Some(trace) = trace.as_deref_mut() {
+            for definition in self.registry.definitions() {
+                let key = definition.name.as_str();
+                let score = base_scores.get(key).copied().unwrap_or(0.5);
+                let event_count = all_contributors.get(key).map(|c| c.len()).unwrap_or(0);
+                let primitive = match &definition.computation {
+                    Computation::Builtin(primitive) => *primitive,
+                    Computation::EventImpacts(_) => {
+                        let reason = format!("Baseline {:.2} + {} decayed event impacts", definition.baseline, event_count);
+                        trace.step(key, TracePass::BaseScore, "base_score", definition.baseline, score, reason);
+                        continue;
+                    }
+                    Computation::Derived(_) => {
+                        let reason = format!("Derived from {}", definition.dependencies.join(", "));
+                        trace.step(key, TracePass::BaseScore, "base_score", definition.baseline, score, reason);
+                        continue;
+                    }
+                };
+                let baseline = self.baseline(primitive);
+                let reason = match primitive {
+                    Primitive::Adenosine => "Process S: accumulation since wake, minus sleep clearance and caffeine blockade".to_string(),
+                    Primitive::CircadianPhase => "Weighted wake time, sleep adequacy, midpoint, consistency and bedtime scores".to_string(),
+                    Primitive::Melatonin => format!("Clock-gated secretion, suppressed by light and screens, plus supplements ({} events)", event_count.saturating_sub(1)),
+                    Primitive::Dopamine | Primitive::Serotonin => format!(
+                        "{:.2} x chronic {:.3} + {:.2} x acute {:.3} ({} events)",
+                        self.params.monoamine_chronic_weight,
+                        chronic_scores.get(key).copied().unwrap_or(baseline),
+                        1.0 - self.params.monoamine_chronic_weight,
+                        acute_scores.get(key).copied().unwrap_or(baseline),
+                        event_count,
+                    ),
+                    Primitive::Cortisol => match &trace.cortisol {
+                        Some(c) => format!(
+                            "Healthy baseline {:.3} + stress response {:.3} + relaxation {:.3}",
+                            c.healthy_baseline, c.stress_response, c.relaxation_effect,
+                        ),
+                        None => format!("Baseline {:.2} + {} decayed event impacts", baseline, event_count),
+                    },
+                    _ => format!("Baseline {:.2} + {} decayed event impacts", baseline, event_count),
+                };
+                trace.step(key, TracePass::BaseScore, "base_score", baseline, score, reason);
             }
-            
-            let (score, contributors) = self.compute_base_score(primitive, events, estimation_time);
-            base_scores.insert(key.to_string(), score);
-            all_contributors.insert(key.to_string(), contributors);
+
+            trace.caffeine_dopamine_boost = Some(CaffeineBoostTrace {
+                boost: self.compute_caffeine_dopamine_boost(store, estimation_time),
+                applied: false,
+            });
         }
 
         // Compute sleep drive
         let sleep_drive = self.compute_sleep_drive(
             adenosine_score,
             circadian_score,
+            base_scores.get(Primitive::Melatonin.as_str()).copied().unwrap_or(0.0),
             estimation_time,
         );
 
         // PASS 2: Detect sequences
-        let detected_sequences = self.detect_sequences(events, estimation_time);
+        let sleep_inertia = SleepInertia::compute(store, estimation_time, history.chronotype.sleep_need_hours);
+        let naps = NapReport::compute(store, estimation_time, &history.chronotype, adenosine_score, circadian_score);
+        let detected_sequences = self.detect_sequences(store, estimation_time, &history, &sleep_inertia, &naps);
 
         // PASS 3: Apply sequence adjustments
         let mut adjusted_scores = base_scores.clone();
         for sequence in &detected_sequences {
             if let Some(score) = adjusted_scores.get_mut(&sequence.impact_on_primitive) {
+                let before = *score;
                 *score = (*score + sequence.adjustment).clamp(0.0, 1.0);
+                let after = *score;
+                if let Some(trace) = trace.as_deref_mut() {
+                    trace.step(
+                        &sequence.impact_on_primitive,
+                        TracePass::Sequences,
+                        &sequence.pattern_name,
+                        before,
+                        after,
+                        format!("Sequence of {} events adjusts by {:+.2}", sequence.events.len(), sequence.adjustment),
+                    );
+                }
             }
         }
 
         // PASS 4: Apply cross-primitive modifiers
-        let (cross_modified_scores, effective_monoamines) = self.apply_cross_primitive_modifiers(&adjusted_scores);
+        let (cross_modified_scores, effective_monoamines) = self.apply_cross_primitive_modifiers(&adjusted_scores, trace.as_deref_mut());
         
         // PASS 5: PHYSIOLOGICAL VALIDATION LAYER (NEW)
+        let (measurements, measurement_diagnostics) = self.extract_physiological_measurements(store, estimation_time);
         let (final_scores, confidence_scores, applied_constraints) = self.apply_physiological_validation(
             &cross_modified_scores,
-            events,
+            &measurements,
             estimation_time,
+            trace,
         );
         
         // Get effective dopamine and serotonin after all modifications
-        let dopamine_effective = effective_monoamines.get("dopamine").copied()
-            .unwrap_or_else(|| final_scores.get("dopamine").copied().unwrap_or(0.5));
-        let serotonin_effective = effective_monoamines.get("serotonin").copied()
-            .unwrap_or_else(|| final_scores.get("serotonin").copied().unwrap_or(0.5));
+        let effective = |primitive: Primitive| {
+            let key = primitive.as_str();
+            effective_monoamines.get(key).or_else(|| final_scores.get(key)).copied().unwrap_or(0.5)
+        };
+        let dopamine_effective = effective(Primitive::Dopamine);
+        let serotonin_effective = effective(Primitive::Serotonin);
         
         let da_ser_ratio = if serotonin_effective > 0.01 {
             dopamine_effective / serotonin_effective
@@ -1162,26 +1787,33 @@ impl PrimitiveEstimator {
             dopamine_effective / 0.01
         };
         
-        let functional_state = Self::compute_functional_state(dopamine_effective, serotonin_effective);
+        let functional_state = self.classify_state(
+            &final_scores,
+            &effective_monoamines,
+            sleep_drive,
+            circadian_score,
+            store,
+            estimation_time,
+        );
 
-        // Build result with confidence scores
-        let mut primitives = HashMap::new();
-        for (key, base_score) in base_scores {
+        let performance = performance::predict_performance(
+            &performance::inputs_at(&final_scores, store, estimation_time, history.chronotype.sleep_need_hours, sleep_inertia.level),
+        );
+
+        // Build result with confidence scores, in registry order
+        let mut primitives = PrimitiveMap::default();
+        for key in self.registry.names().map(str::to_string) {
+            let base_score = base_scores.get(&key).copied().unwrap_or(0.5);
             let modified_score = final_scores.get(&key).copied().unwrap_or(base_score);
             let contributors = all_contributors.get(&key).cloned().unwrap_or_default();
             let confidence = confidence_scores.get(&key).copied().unwrap_or(1.0);
             
-            let (acute_score, chronic_score, effective_score) = if key == "dopamine" || key == "serotonin" {
-                (
-                    acute_scores.get(&key).copied(),
-                    chronic_scores.get(&key).copied(),
-                    effective_monoamines.get(&key).copied(),
-                )
-            } else {
-                (None, None, None)
-            };
+            // Only primitives with an acute/chronic breakdown (dopamine, serotonin) have these
+            let acute_score = acute_scores.get(&key).copied();
+            let chronic_score = chronic_scores.get(&key).copied();
+            let effective_score = effective_monoamines.get(&key).copied();
             
-            primitives.insert(
+            primitives.push(
                 key,
                 PrimitiveState {
                     base_score,
@@ -1202,7 +1834,17 @@ impl PrimitiveEstimator {
             sleep_drive,
             dopamine_serotonin_ratio: da_ser_ratio,
             functional_state,
+            performance,
+            chronotype: history.chronotype,
+            sleep_metrics: history.sleep_metrics,
+            training: history.training,
+            allostatic_load: history.allostatic_load,
+            sleep_inertia,
+            naps,
+            light: LightReport::compute(store, estimation_time),
             physiological_constraints: applied_constraints,
+            measurement_diagnostics,
+            trace: None,
         }
     }
 
@@ -1210,12 +1852,10 @@ impl PrimitiveEstimator {
     fn apply_physiological_validation(
         &self,
         scores: &HashMap<String, f64>,
-        events: &[Event],
+        measurements: &[PhysiologicalMeasurement],
         estimation_time: DateTime<Utc>,
+        mut trace: Option<&mut EstimationTrace>,
     ) -> (HashMap<String, f64>, HashMap<String, f64>, Vec<PhysiologicalConstraintApplied>) {
-        // Extract physiological measurements from events
-        let measurements = self.extract_physiological_measurements(events, estimation_time);
-        
         if measurements.is_empty() {
             // No physiological data available, return original scores with full confidence
             let confidence_scores: HashMap<String, f64> = scores.keys()
@@ -1224,11 +1864,20 @@ impl PrimitiveEstimator {
             return (scores.clone(), confidence_scores, Vec::new());
         }
         
+        // Aerobically fit people (VO2max >= 50 ml/kg/min) have low resting HR at normal arousal
+        let is_aerobically_fit = measurements.iter()
+            .any(|m| m.measurement_type == MeasurementType::Vo2Max && m.value >= 50.0);
+        
         // Generate constraints from all measurements
         let mut all_constraints = Vec::new();
-        for measurement in &measurements {
-            let constraints = measurement.generate_constraints(estimation_time);
-            all_constraints.extend(constraints);
+        for measurement in measurements {
+            let constraints = measurement.constraints_with_parameters(estimation_time, &self.params);
+            all_constraints.extend(constraints.into_iter().filter(|c| {
+                // Athletic bradycardia does not indicate low norepinephrine
+                !(is_aerobically_fit
+                    && c.source_measurement == MeasurementType::HeartRate
+                    && matches!(c.constraint_type, ConstraintType::Ceiling(_)))
+            }));
         }
         
         // Apply constraints to scores
@@ -1260,7 +1909,8 @@ impl PrimitiveEstimator {
                     },
                     ConstraintType::Override(value) => {
                         // Strong signal - blend with original (70% measurement, 30% prediction)
-                        new_score = value * 0.7 + current_score * 0.3;
+                        let weight = self.params.override_measurement_weight;
+                        new_score = value * weight + current_score * (1.0 - weight);
                         confidence_impact = 0.3; // Increase confidence with direct measurement
                     },
                     ConstraintType::ConfidencePenalty(penalty) => {
@@ -1278,6 +1928,16 @@ impl PrimitiveEstimator {
                 
                 // Record what was applied
                 if (new_score - original_score).abs() > 0.01 || confidence_impact.abs() > 0.01 {
+                    if let Some(trace) = trace.as_deref_mut() {
+                        trace.step(
+                            primitive_key,
+                            TracePass::PhysiologicalValidation,
+                            &format!("{:?} {:?}", constraint.source_measurement, constraint.constraint_type),
+                            original_score,
+                            new_score,
+                            format!("{} (confidence {:+.2})", constraint.reason, confidence_impact),
+                        );
+                    }
                     applied_constraints.push(PhysiologicalConstraintApplied {
                         primitive: primitive_key.to_string(),
                         constraint_source: format!("{:?}", constraint.source_measurement),
@@ -1293,78 +1953,154 @@ impl PrimitiveEstimator {
         (adjusted_scores, confidence_scores, applied_constraints)
     }
     
-    /// Extract physiological measurements from health events
-    fn extract_physiological_measurements(
+    /// Extract physiological measurements from health events, normalized to canonical units.
+    /// Readings with unknown or incompatible units are returned as diagnostics instead.
+    pub(crate) fn extract_physiological_measurements(
         &self,
-        events: &[Event],
+        store: &EventStore,
         estimation_time: DateTime<Utc>,
-    ) -> Vec<PhysiologicalMeasurement> {
+    ) -> (Vec<PhysiologicalMeasurement>, Vec<MeasurementDiagnostic>) {
         let mut measurements = Vec::new();
-        
-        // Look for health measurement events within a reasonable window
-        let cutoff_time = estimation_time - Duration::hours(24);
-        
-        for event in events {
-            if event.timestamp < cutoff_time || event.timestamp > estimation_time {
+        let mut diagnostics = Vec::new();
+        // Sub-daily step counts, summed into daily totals below
+        let mut partial_steps: Vec<(DateTime<Utc>, f64)> = Vec::new();
+        
+        // Sub-daily step counts are summed from yesterday's midnight, whatever the
+        // lookback, so yesterday's total covers the whole day
+        let steps_day_start = (estimation_time - Duration::days(1))
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .map_or(estimation_time, |midnight| midnight.and_utc());
+        
+        // The longest lookback of any measurement type bounds the scan
+        let earliest = estimation_time - Duration::hours(MeasurementType::Vo2Max.lookback_hours());
+        for event in store.range(earliest, estimation_time) {
+            let Some(mtype) = MeasurementType::from_event_type(&event.event_type) else {
+                continue;
+            };
+            
+            // Look for health measurement events within a window suited to the signal
+            let cutoff_time = estimation_time - Duration::hours(mtype.lookback_hours());
+            let earliest_kept = if mtype == MeasurementType::Steps { cutoff_time.min(steps_day_start) } else { cutoff_time };
+            if event.timestamp < earliest_kept || event.timestamp > estimation_time {
                 continue;
             }
             
-            let measurement_type = match event.event_type.as_str() {
-                "health_heart_rate" => Some(MeasurementType::HeartRate),
-                "health_hrv" => Some(MeasurementType::HeartRateVariability),
-                "health_blood_oxygen" => Some(MeasurementType::BloodOxygen),
-                "health_blood_glucose" => Some(MeasurementType::BloodGlucose),
-                "health_body_temperature" => Some(MeasurementType::BodyTemperature),
-                "health_respiratory_rate" => Some(MeasurementType::RespiratoryRate),
-                "health_steps" => Some(MeasurementType::Steps),
+            let components: HashMap<String, f64> = event.properties.iter()
+                .filter(|(key, _)| key.as_str() != "value")
+                .filter_map(|(key, v)| v.as_f64().map(|x| (key.clone(), x)))
+                .collect();
+            
+            // Multi-valued readings fall back to their headline number
+            let value = event.properties.get("value").and_then(|v| v.as_f64()).or_else(|| match mtype {
+                MeasurementType::BloodPressure => components.get("systolic").copied(),
+                MeasurementType::SleepStages => {
+                    let stages = ["deep_minutes", "rem_minutes", "light_minutes"];
+                    let total: f64 = stages.iter().filter_map(|k| components.get(*k)).sum();
+                    if total > 0.0 { Some(total) } else { None }
+                },
                 _ => None,
+            });
+            
+            let Some(value) = value else {
+                continue;
             };
             
-            if let Some(mtype) = measurement_type {
-                if let Some(value) = event.properties.get("value").and_then(|v| v.as_f64()) {
-                    let unit = event.properties.get("unit")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string();
+            let unit = event.properties.get("unit")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            
+            match units::normalize_reading(&mtype, value, unit, &components, &event.properties) {
+                Ok(reading) => {
+                    if reading.period_hours.is_some_and(|hours| hours < 24.0) {
+                        partial_steps.push((event.timestamp, reading.value));
+                        continue;
+                    }
+                    if event.timestamp < cutoff_time {
+                        continue;
+                    }
                     
                     measurements.push(PhysiologicalMeasurement {
                         measurement_type: mtype,
-                        value,
+                        value: reading.value,
                         timestamp: event.timestamp,
-                        unit,
+                        unit: reading.unit.to_string(),
+                        components: reading.components,
                     });
-                }
+                },
+                Err(err) => {
+                    diagnostics.push(MeasurementDiagnostic {
+                        event_id: event.event_id.clone(),
+                        event_type: event.event_type.clone(),
+                        unit: unit.to_string(),
+                        message: err.to_string(),
+                    });
+                },
+            }
+        }
+        
+        // Hourly or per-minute step counts are summed per completed calendar day
+        // (only yesterday is scanned). The total is stamped at the day's midday,
+        // so the daily rule sees it until the evening after.
+        let mut daily_steps: BTreeMap<chrono::NaiveDate, f64> = BTreeMap::new();
+        for (timestamp, steps) in partial_steps {
+            if timestamp.date_naive() >= estimation_time.date_naive() {
+                continue;
             }
+            *daily_steps.entry(timestamp.date_naive()).or_insert(0.0) += steps;
+        }
+        for (date, total) in daily_steps {
+            let Some(midday) = date.and_hms_opt(12, 0, 0) else {
+                continue;
+            };
+            measurements.push(PhysiologicalMeasurement {
+                measurement_type: MeasurementType::Steps,
+                value: total,
+                timestamp: midday.and_utc(),
+                unit: MeasurementType::Steps.default_unit().to_string(),
+                components: HashMap::new(),
+            });
         }
         
-        measurements
+        (measurements, diagnostics)
     }
 
     fn compute_base_score(
         &self,
         primitive: Primitive,
-        events: &[Event],
+        store: &EventStore,
         estimation_time: DateTime<Utc>,
+        history: &HistoryContext,
+        trace: Option<&mut EstimationTrace>,
     ) -> (f64, Vec<EventContribution>) {
-        let config = ContextConfig::for_primitive(primitive);
-        let baseline = self.baseline_values
-            .get(primitive.as_str())
-            .copied()
-            .unwrap_or(0.5);
+        let config = self.params.context_config(primitive);
+        let baseline = self.baseline(primitive);
 
         if primitive == Primitive::Adenosine {
-            return self.compute_adenosine_special(events, estimation_time);
+            return self.compute_adenosine_special(store, estimation_time, history);
         }
 
         if primitive == Primitive::CircadianPhase {
-            return self.compute_circadian_phase_special(events, estimation_time);
+            return self.compute_circadian_phase_special(store, estimation_time, history);
+        }
+
+        if primitive == Primitive::Melatonin {
+            return self.compute_melatonin_special(store, estimation_time, history);
         }
 
         let cutoff_time = estimation_time - Duration::hours(config.window_hours);
-        let relevant_events: Vec<_> = events
+        let relevant_events = store
+            .range(cutoff_time - Duration::hours(kinetics::MAX_INTERVAL_HOURS), estimation_time)
             .iter()
-            .filter(|e| e.timestamp >= cutoff_time && e.timestamp <= estimation_time)
-            .collect();
+            .filter(|event| kinetics::in_window(event, cutoff_time));
+
+        // Chronic stress keeps the HPA axis from switching off: new stressors clear
+        // more slowly and the daily cortisol rhythm flattens
+        let allostatic_index = if primitive == Primitive::Cortisol {
+            history.allostatic_load.index
+        } else {
+            0.0
+        };
 
         let mut accumulated_impact = 0.0;
         let mut contributors = Vec::new();
@@ -1375,7 +2111,14 @@ impl PrimitiveEstimator {
             let impacts = self.compute_event_impacts(event, estimation_time);
             
             if let Some(&raw_impact) = impacts.get(primitive.as_str()) {
-                let decay_factor = self.exponential_decay(hours_ago, config.decay_half_life_hours, primitive);
+                let half_life = if event.event_type == "stress_event" {
+                    config.decay_half_life_hours * (1.0 + self.params.allostatic_stress_recovery_slowing * allostatic_index)
+                } else {
+                    config.decay_half_life_hours
+                };
+                let decay_factor = kinetics::impact_weight(event, estimation_time, half_life, |hours, half_life| {
+                    self.exponential_decay(hours, half_life, primitive)
+                });
                 let decayed_impact = raw_impact * decay_factor;
                 
                 accumulated_impact += decayed_impact;
@@ -1391,7 +2134,7 @@ impl PrimitiveEstimator {
         }
 
         if primitive == Primitive::Dopamine {
-            let caffeine_boost = self.compute_caffeine_dopamine_boost(events, estimation_time);
+            let caffeine_boost = self.compute_caffeine_dopamine_boost(store, estimation_time);
             accumulated_impact += caffeine_boost;
         }
 
@@ -1408,17 +2151,22 @@ impl PrimitiveEstimator {
     
         // Apply cortisol-specific circadian rhythm modulation
         if primitive == Primitive::Cortisol {
-            let circadian_multiplier = Self::cortisol_circadian_multiplier(estimation_time);
-            let awakening_boost = Self::cortisol_awakening_boost(events, estimation_time);
+            // Allostatic load pulls the rhythm towards its 24h mean (0.53) and blunts the CAR
+            let rhythm_mean = 0.53;
+            let flattening = (self.params.allostatic_rhythm_flattening * allostatic_index).clamp(0.0, 1.0);
+            let circadian_multiplier =
+                rhythm_mean + (Self::cortisol_circadian_multiplier(estimation_time) - rhythm_mean) * (1.0 - flattening);
+            let awakening_boost = 1.0 + (Self::cortisol_awakening_boost(store, estimation_time) - 1.0) * (1.0 - flattening);
             
             // Circadian rhythm sets the healthy baseline for this time of day
             // Even a completely stress-free person has cortisol following this rhythm
-            let healthy_baseline = 0.15 + (0.50 * circadian_multiplier);  // Range: 0.15 (night nadir) to 0.65 (morning peak)
+            let healthy_baseline = 0.15 + (0.50 * circadian_multiplier)  // Range: 0.15 (night nadir) to 0.65 (morning peak)
+                + self.params.cortisol_baseline_shift;
             
             // Events push cortisol up (stress, caffeine, poor sleep) or slightly down (relaxation, good sleep)
             // Separate positive (stress) and negative (relaxation) contributions
             let stress_load = accumulated_impact.max(0.0);
-            let relaxation_effect = accumulated_impact.min(0.0).max(-0.15);  // Cap relaxation reduction at -0.15
+            let relaxation_effect = accumulated_impact.clamp(-0.15, 0.0);  // Cap relaxation reduction at -0.15
             
             // Stress response is amplified by:
             // 1. Awakening boost (CAR effect)
@@ -1429,36 +2177,104 @@ impl PrimitiveEstimator {
             // Final cortisol is the healthy circadian baseline plus event-driven modulation
             // Stress adds on top, relaxation can reduce slightly but not eliminate the natural rhythm
             final_score = (healthy_baseline + stress_response + relaxation_effect).clamp(0.15, 1.0);
+
+            if let Some(trace) = trace {
+                trace.cortisol = Some(CortisolTrace {
+                    allostatic_index,
+                    accumulated_impact,
+                    circadian_multiplier,
+                    healthy_baseline,
+                    awakening_boost,
+                    circadian_stress_sensitivity,
+                    stress_load,
+                    stress_response,
+                    relaxation_effect,
+                });
+            }
         }
         
         (final_score, contributors)
     }
 
+    /// Base score of a registered event-driven primitive: its baseline plus each
+    /// listed event type's impact, decayed over the definition's context window
+    fn compute_event_driven_score(
+        &self,
+        definition: &PrimitiveDefinition,
+        impacts: &BTreeMap<String, f64>,
+        store: &EventStore,
+        estimation_time: DateTime<Utc>,
+    ) -> (f64, Vec<EventContribution>) {
+        let config = definition.context;
+        let cutoff_time = estimation_time - Duration::hours(config.window_hours);
+
+        let mut accumulated_impact = 0.0;
+        let mut contributors = Vec::new();
+        let events = store
+            .range(cutoff_time - Duration::hours(kinetics::MAX_INTERVAL_HOURS), estimation_time)
+            .iter()
+            .filter(|event| kinetics::in_window(event, cutoff_time));
+        for event in events {
+            let Some(&impact) = impacts.get(&event.event_type) else {
+                continue;
+            };
+            let hours_ago = (estimation_time - event.timestamp).num_minutes() as f64 / 60.0;
+            let decay = kinetics::impact_weight(event, estimation_time, config.decay_half_life_hours, |hours, half_life| {
+                (-std::f64::consts::LN_2 / half_life * hours).exp()
+            });
+            let decayed_impact = impact * self.params.impact_scale(&event.event_type) * decay;
+            accumulated_impact += decayed_impact;
+            contributors.push(EventContribution {
+                event_id: event.event_id.clone(),
+                event_type: event.event_type.clone(),
+                impact,
+                decayed_impact,
+                hours_ago,
+            });
+        }
+
+        contributors.sort_by(|a, b| {
+            b.decayed_impact
+                .abs()
+                .partial_cmp(&a.decayed_impact.abs())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        ((definition.baseline + accumulated_impact).clamp(0.0, 1.0), contributors)
+    }
+
     fn compute_event_impacts(&self, event: &Event, estimation_time: DateTime<Utc>) -> HashMap<String, f64> {
-        match event.event_type.as_str() {
+        let mut impacts = match event.event_type.as_str() {
             "sleep" => compute_sleep_impacts(event),
-            "light_exposure" => compute_light_impacts(event, estimation_time),
+            "light_exposure" | "light_series" => compute_light_impacts(event, estimation_time),
             "meal" => compute_meal_impacts(event),
-            "caffeine" => compute_caffeine_impacts(event),
+            "caffeine" => compute_caffeine_impacts(event, &self.params),
             "exercise" => compute_exercise_impacts(event),
+            // Nap timing needs the chronotype, so adenosine clearance is in compute_adenosine_special
             "nap" => compute_nap_impacts(event),
             "stress_event" => compute_stress_impacts(event),
             "social_interaction" => compute_social_impacts(event),
             "screen_time" => compute_screen_impacts(event),
             "interruption" => compute_interruption_impacts(event),
             "wake" => HashMap::new(),
+            // Supplements act only on melatonin, in compute_melatonin_special
+            "melatonin" => HashMap::new(),
+            // Self-reports are labels for evaluation, never model inputs
+            "self_report" => HashMap::new(),
             // Health measurements don't directly impact primitives via this path
             _ if event.event_type.starts_with("health_") => HashMap::new(),
             _ => HashMap::new(),
+        };
+
+        let scale = self.params.impact_scale(&event.event_type);
+        if scale != 1.0 {
+            impacts.values_mut().for_each(|impact| *impact *= scale);
         }
+        impacts
     }
 
-    fn compute_caffeine_dopamine_boost(&self, events: &[Event], estimation_time: DateTime<Utc>) -> f64 {
+    fn compute_caffeine_dopamine_boost(&self, store: &EventStore, estimation_time: DateTime<Utc>) -> f64 {
         let cutoff_time = estimation_time - Duration::hours(12);
-        let caffeine_events: Vec<_> = events
-            .iter()
-            .filter(|e| e.event_type == "caffeine" && e.timestamp >= cutoff_time && e.timestamp <= estimation_time)
-            .collect();
+        let caffeine_events = store.of_type("caffeine", cutoff_time, estimation_time);
 
         let mut total_boost = 0.0;
         for event in caffeine_events {
@@ -1475,32 +2291,37 @@ impl PrimitiveEstimator {
         total_boost.min(0.4)
     }
 
-    fn compute_adenosine_special(&self, events: &[Event], estimation_time: DateTime<Utc>) -> (f64, Vec<EventContribution>) {
+    fn compute_adenosine_special(
+        &self,
+        store: &EventStore,
+        estimation_time: DateTime<Utc>,
+        history: &HistoryContext,
+    ) -> (f64, Vec<EventContribution>) {
         let mut contributors = Vec::new();
+        let events = store.until(estimation_time);
+        let periods = sleep::sleep_periods(events, estimation_time);
+        
+        // The most recent wake is the later of the last wake event and the last completed sleep
+        let last_wake_event = events.iter()
+            .rev()
+            .find(|e| e.event_type == "wake")
+            .map(|e| e.timestamp);
+        let last_sleep_end = periods.iter()
+            .filter(|p| p.end <= estimation_time)
+            .map(|p| p.end)
+            .max();
+        let most_recent_wake = last_wake_event.max(last_sleep_end);
+        
+        // A sleep that has started since then and is still in progress
+        let current_sleep = periods.iter().rev().find(|p| {
+            p.end > estimation_time && most_recent_wake.is_none_or(|wake| p.start >= wake)
+        });
         
-        // Find the most recent wake time at or before estimation_time
-        let mut most_recent_wake: Option<DateTime<Utc>> = None;
-        for event in events.iter() {
-            if event.timestamp > estimation_time {
-                continue;
-            }
-            if event.event_type == "wake" {
-                // A wake event marks the start of a wake interval
-                most_recent_wake = Some(event.timestamp);
-            } else if event.event_type == "sleep" {
-                // Use sleep end time if it exists and is before estimation_time
-                if let Some(end_time) = event.end_timestamp {
-                    if end_time <= estimation_time {
-                        most_recent_wake = Some(end_time);
-                    }
-                }
-            }
-        }
-        
-        let hours_awake = if let Some(wake_time) = most_recent_wake {
-            ((estimation_time - wake_time).num_minutes() as f64 / 60.0).max(0.0)
-        } else {
-            8.0
+        // Adenosine stops accumulating once asleep
+        let hours_awake = match (most_recent_wake, current_sleep) {
+            (Some(wake_time), Some(sleep)) => ((sleep.start - wake_time).num_minutes() as f64 / 60.0).max(0.0),
+            (Some(wake_time), None) => ((estimation_time - wake_time).num_minutes() as f64 / 60.0).max(0.0),
+            (None, _) => 8.0,
         };
         
         // Exponential accumulation toward 1.0 with ~16h time constant
@@ -1508,32 +2329,37 @@ impl PrimitiveEstimator {
         
         // Apply sleep clearance using the end of sleep as the effective time
         let cutoff_time = estimation_time - Duration::hours(20);
-        let sleep_events: Vec<_> = events
-            .iter()
-            .filter(|e| {
-                if e.event_type != "sleep" {
-                    return false;
-                }
-                if let Some(end_ts) = e.end_timestamp {
-                    end_ts >= cutoff_time && end_ts <= estimation_time
-                } else {
-                    false
-                }
-            })
+        let event_by_id: HashMap<&str, &Event> = events.iter()
+            .map(|e| (e.event_id.as_str(), e))
             .collect();
         
         let mut sleep_clearance = 0.0;
-        for event in sleep_events {
-            let impacts = compute_sleep_impacts(event);
-            if let Some(&clearance) = impacts.get("adenosine") {
+        for period in &periods {
+            let in_progress = current_sleep.is_some_and(|current| current.start == period.start);
+            if !in_progress && (period.end < cutoff_time || period.end > estimation_time) {
+                continue;
+            }
+            let Some(event) = event_by_id.get(period.primary_event_id.as_str()) else {
+                continue;
+            };
+            
+            let impacts = self.compute_event_impacts(event, estimation_time);
+            if let Some(&full_clearance) = impacts.get("adenosine") {
+                // An ongoing sleep has cleared its share of adenosine so far
+                let (clearance, hours_ago) = if in_progress {
+                    let elapsed = (estimation_time - period.start).num_minutes() as f64 / 60.0;
+                    let fraction = (elapsed / period.in_bed_hours().max(0.1)).clamp(0.0, 1.0);
+                    (full_clearance * fraction, 0.0)
+                } else {
+                    (full_clearance, (estimation_time - period.end).num_minutes() as f64 / 60.0)
+                };
+                
                 // Compute decay from the end of sleep (when clearance manifests)
-                let end_ts = event.end_timestamp.unwrap_or(event.timestamp);
-                let hours_ago = (estimation_time - end_ts).num_minutes() as f64 / 60.0;
                 let decay = self.exponential_decay(hours_ago, 16.0, Primitive::Adenosine);
                 sleep_clearance += clearance * decay;
                 
                 contributors.push(EventContribution {
-                    event_id: event.event_id.clone(),
+                    event_id: period.primary_event_id.clone(),
                     event_type: "sleep".to_string(),
                     impact: clearance,
                     decayed_impact: clearance * decay,
@@ -1543,14 +2369,11 @@ impl PrimitiveEstimator {
         }
         
         let cutoff_caffeine = estimation_time - Duration::hours(12);
-        let caffeine_events: Vec<_> = events
-            .iter()
-            .filter(|e| e.event_type == "caffeine" && e.timestamp >= cutoff_caffeine && e.timestamp <= estimation_time)
-            .collect();
+        let caffeine_events = store.of_type("caffeine", cutoff_caffeine, estimation_time);
         
         let mut caffeine_suppression = 0.0;
         for event in caffeine_events {
-            let impacts = compute_caffeine_impacts(event);
+            let impacts = self.compute_event_impacts(event, estimation_time);
             if let Some(&suppression) = impacts.get("adenosine") {
                 let hours_ago = (estimation_time - event.timestamp).num_minutes() as f64 / 60.0;
                 let decay = self.exponential_decay(hours_ago, 5.0, Primitive::Adenosine);
@@ -1566,29 +2389,24 @@ impl PrimitiveEstimator {
             }
         }
         
-        let nap_events: Vec<_> = events
-            .iter()
-            .filter(|e| e.event_type == "nap" && e.timestamp >= cutoff_time && e.timestamp <= estimation_time)
-            .collect();
-        
-        for event in nap_events {
-            let impacts = compute_nap_impacts(event);
-            if let Some(&clearance) = impacts.get("adenosine") {
-                let hours_ago = (estimation_time - event.timestamp).num_minutes() as f64 / 60.0;
-                let decay = self.exponential_decay(hours_ago, 8.0, Primitive::Adenosine);
-                sleep_clearance += clearance * decay;
-                
-                contributors.push(EventContribution {
-                    event_id: event.event_id.clone(),
-                    event_type: "nap".to_string(),
-                    impact: clearance,
-                    decayed_impact: clearance * decay,
-                    hours_ago,
-                });
-            }
+        // Naps clear more or less depending on when they fall against the circadian dip and bedtime
+        for event in store.of_type("nap", cutoff_time, estimation_time) {
+            let nap = NapAssessment::assess(event, store, &history.chronotype);
+            let clearance = nap.adenosine_clearance * self.params.impact_scale("nap");
+            let hours_ago = (estimation_time - event.timestamp).num_minutes() as f64 / 60.0;
+            let decay = self.exponential_decay(hours_ago, 8.0, Primitive::Adenosine);
+            sleep_clearance += clearance * decay;
+
+            contributors.push(EventContribution {
+                event_id: event.event_id.clone(),
+                event_type: "nap".to_string(),
+                impact: clearance,
+                decayed_impact: clearance * decay,
+                hours_ago,
+            });
         }
         
-        let baseline = self.baseline_values.get("adenosine").copied().unwrap_or(0.3);
+        let baseline = self.registry.get(Primitive::Adenosine).map(|d| d.baseline).unwrap_or(0.3);
         let final_score = (baseline + base_accumulation + sleep_clearance + caffeine_suppression).clamp(0.0, 1.0);
         
         contributors.push(EventContribution {
@@ -1609,158 +2427,170 @@ impl PrimitiveEstimator {
         (final_score, contributors)
     }
 
-    fn compute_circadian_phase_special(&self, events: &[Event], estimation_time: DateTime<Utc>) -> (f64, Vec<EventContribution>) {
+    fn compute_circadian_phase_special(
+        &self,
+        store: &EventStore,
+        estimation_time: DateTime<Utc>,
+        history: &HistoryContext,
+    ) -> (f64, Vec<EventContribution>) {
         let mut contributors = Vec::new();
         
-        // Gather recent sleep/wake events (last 7 days)
+        // Gather the most recent sleep days and wake events (last 7 days)
         let lookback = estimation_time - Duration::hours(168);
-        let sleep_events: Vec<_> = events
-            .iter()
-            .filter(|e| e.event_type == "sleep" && e.timestamp >= lookback)
+        let sleep_days = sleep::sleep_days(store.until(estimation_time), lookback, estimation_time);
+        let recent_days: Vec<_> = sleep_days.iter().take(7).collect();
+        
+        let mut wake_times: Vec<DateTime<Utc>> = store
+            .of_type("wake", lookback, estimation_time)
+            .rev()
+            .take(7)
+            .map(|e| e.timestamp)
             .collect();
         
-        let wake_events: Vec<_> = events
-            .iter()
-            .filter(|e| e.event_type == "wake" && e.timestamp >= lookback)
-            .collect();
+        // Without explicit wake events, the end of each day's main sleep is the wake time
+        if wake_times.is_empty() {
+            wake_times = recent_days.iter().map(|day| day.main_period().end).collect();
+        }
         
-        // Component 1: Wake Time Score (0-1)
-        // Optimal: 6:00-8:00 AM → ~1.0
-        // Good: 5:00-9:00 AM → 0.7-0.9
-        // Suboptimal: 4:00-5:00 or 9:00-10:00 AM → 0.4-0.7
-        // Poor: <4:00 or >10:00 AM → <0.4
-        let wake_time_score = if !wake_events.is_empty() {
-            let recent_wakes: Vec<f64> = wake_events.iter().take(7)
-                .map(|e| e.timestamp.hour() as f64 + e.timestamp.minute() as f64 / 60.0)
-                .collect();
-            let avg_wake_hour = recent_wakes.iter().sum::<f64>() / recent_wakes.len() as f64;
+        // Offsets from the chronotype's preferred times, wrapped so that
+        // wakes or bedtimes either side of midnight average cleanly
+        let chronotype = &history.chronotype;
+        let offset = |t: &DateTime<Utc>, preferred: f64| {
+            sleep::wrap_hours(t.hour() as f64 + t.minute() as f64 / 60.0 - preferred)
+        };
+        let wake_offsets: Vec<f64> = wake_times.iter()
+            .map(|t| offset(t, chronotype.preferred_wake_hour()))
+            .collect();
+        let need = chronotype.sleep_need_hours;
+        
+        // Component 1: Wake Time Score (0-1), by distance from the preferred wake time
+        // Within 1h → ~1.0
+        // Within 2h → 0.7-0.9
+        // Within 3h → 0.4-0.7
+        // Further → <0.4
+        let wake_time_score = if !wake_offsets.is_empty() {
+            let distance = (wake_offsets.iter().sum::<f64>() / wake_offsets.len() as f64).abs();
             
-            if avg_wake_hour >= 6.0 && avg_wake_hour <= 8.0 {
+            if distance <= 1.0 {
                 1.0
-            } else if avg_wake_hour >= 5.0 && avg_wake_hour <= 9.0 {
-                0.85 - (avg_wake_hour - 7.0).abs() * 0.15
-            } else if avg_wake_hour >= 4.0 && avg_wake_hour <= 10.0 {
-                0.6 - (avg_wake_hour - 7.0).abs() * 0.1
+            } else if distance <= 2.0 {
+                0.85 - distance * 0.15
+            } else if distance <= 3.0 {
+                0.6 - distance * 0.1
             } else {
-                (0.4 - (avg_wake_hour - 7.0).abs() * 0.05).max(0.0)
+                (0.4 - distance * 0.05).max(0.0)
             }
         } else {
             0.7 // Default if no data
         };
         
         // Component 2: Sleep Adequacy Score (0-1)
-        // Optimal: 7.5-9 hours → 1.0
-        // Minimum: 7 hours → 0.85
-        // Insufficient: <7 hours → penalty
-        // Excessive: >9.5 hours → slight penalty
-        let sleep_adequacy_score = if !sleep_events.is_empty() {
-            let recent_durations: Vec<f64> = sleep_events.iter().take(7)
-                .filter_map(|e| e.properties.get("duration_hours").and_then(|v| v.as_f64()))
-                .collect();
+        // Total sleep per sleep day, so split sleep counts as a whole
+        // Optimal: need - 0.5h to need + 1h → 1.0 (7.5-9h for an 8h need)
+        // Minimum: need - 1h → 0.85
+        // Insufficient: less → penalty
+        // Excessive: more than need + 1.5h → slight penalty
+        let sleep_adequacy_score = if !recent_days.is_empty() {
+            let avg_duration = recent_days.iter().map(|d| d.total_asleep_hours()).sum::<f64>() / recent_days.len() as f64;
             
-            if !recent_durations.is_empty() {
-                let avg_duration = recent_durations.iter().sum::<f64>() / recent_durations.len() as f64;
-                
-                if avg_duration >= 7.5 && avg_duration <= 9.0 {
-                    1.0
-                } else if avg_duration >= 7.0 && avg_duration < 7.5 {
-                    0.85 + (avg_duration - 7.0) * 0.3
-                } else if avg_duration < 7.0 {
-                    (0.85 * avg_duration / 7.0).max(0.2)
-                } else {
-                    // >9 hours, slight penalty
-                    (1.0 - (avg_duration - 9.0) * 0.1).max(0.7)
-                }
+            if (need - 0.5..=need + 1.0).contains(&avg_duration) {
+                1.0
+            } else if (need - 1.0..need - 0.5).contains(&avg_duration) {
+                0.85 + (avg_duration - (need - 1.0)) * 0.3
+            } else if avg_duration < need - 1.0 {
+                (0.85 * avg_duration / (need - 1.0)).max(0.2)
             } else {
-                0.7
+                // Well over the need, slight penalty
+                (1.0 - (avg_duration - (need + 1.0)) * 0.1).max(0.7)
             }
         } else {
             0.7
         };
         
         // Component 3: Consistency Score (0-1)
-        // Low variability in wake times → higher score
-        let consistency_score = if wake_events.len() >= 3 {
-            let recent_wakes: Vec<f64> = wake_events.iter().take(7)
-                .map(|e| e.timestamp.hour() as f64 + e.timestamp.minute() as f64 / 60.0)
-                .collect();
-            
-            let mean = recent_wakes.iter().sum::<f64>() / recent_wakes.len() as f64;
-            let variance = recent_wakes.iter()
-                .map(|&x| (x - mean).powi(2))
-                .sum::<f64>() / recent_wakes.len() as f64;
-            let std_dev = variance.sqrt();
-            
-            // Standard deviation of wake times:
-            // <0.5h → excellent consistency (0.95)
-            // ~1h → good (0.8)
-            // ~2h → fair (0.6)
-            // >3h → poor (<0.4)
-            (1.0 - (std_dev * 0.2)).clamp(0.3, 0.95)
+        // Mean of the available regularity scores: wake time and bedtime variability
+        // (std dev <0.5h → 0.95, ~1h → 0.8, ~2h → 0.6, >3h → <0.4) and the
+        // Sleep Regularity Index (90+ → 0.95, 65 → ~0.63, 40 or less → 0.3)
+        let metrics = &history.sleep_metrics;
+        let variability_score = |std_dev: f64| (1.0 - std_dev * 0.2).clamp(0.3, 0.95);
+        let regularity_scores: Vec<f64> = [
+            metrics.wake_time_std_hours.map(variability_score),
+            metrics.bedtime_std_hours.map(variability_score),
+            metrics.sleep_regularity_index.map(|sri| (0.3 + (sri - 40.0) / 50.0 * 0.65).clamp(0.3, 0.95)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let consistency_score = if !regularity_scores.is_empty() {
+            regularity_scores.iter().sum::<f64>() / regularity_scores.len() as f64
         } else {
             0.7
         };
         
         // Component 4: Bedtime Appropriateness (0-1)
-        // Does bedtime support adequate sleep before optimal wake time?
-        let bedtime_score = if !sleep_events.is_empty() && !wake_events.is_empty() {
-            let recent_sleep_hours: Vec<f64> = sleep_events.iter().take(7)
-                .map(|e| {
-                    let hour = e.timestamp.hour() as f64;
-                    // Normalize bedtime: 22-23 stays as is, 0-6 becomes 24-30
-                    if hour <= 6.0 { hour + 24.0 } else { hour }
-                })
-                .collect();
-            
-            let recent_wake_hours: Vec<f64> = wake_events.iter().take(7)
-                .map(|e| e.timestamp.hour() as f64 + e.timestamp.minute() as f64 / 60.0)
-                .collect();
+        // Does the main sleep window allow the sleep need before waking,
+        // and does it start near the chronotype's preferred bedtime?
+        let bedtime_score = if !recent_days.is_empty() {
+            let sleep_window = recent_days.iter()
+                .map(|d| d.main_period().in_bed_hours())
+                .sum::<f64>() / recent_days.len() as f64;
+            let bedtime_distance = (recent_days.iter()
+                .map(|d| offset(&d.main_period().start, chronotype.preferred_bedtime_hour()))
+                .sum::<f64>() / recent_days.len() as f64)
+                .abs();
             
-            let avg_bedtime = recent_sleep_hours.iter().sum::<f64>() / recent_sleep_hours.len() as f64;
-            let avg_wake = recent_wake_hours.iter().sum::<f64>() / recent_wake_hours.len() as f64;
-            
-            // Calculate sleep opportunity window
-            let normalized_bedtime = if avg_bedtime >= 24.0 { avg_bedtime - 24.0 } else { avg_bedtime };
-            let sleep_window = if avg_wake > normalized_bedtime {
-                avg_wake - normalized_bedtime
-            } else {
-                (24.0 - normalized_bedtime) + avg_wake
-            };
-            
-            // Ideal bedtime allows 7.5-9h sleep before wake
-            if sleep_window >= 7.5 && sleep_window <= 9.5 {
+            // Ideal window is need - 0.5h to need + 1.5h (7.5-9.5h for an 8h need)
+            let window_score = if (need - 0.5..=need + 1.5).contains(&sleep_window) {
                 1.0
-            } else if sleep_window < 7.5 {
-                (sleep_window / 7.5 * 0.9).max(0.3)
+            } else if sleep_window < need - 0.5 {
+                (sleep_window / (need - 0.5) * 0.9).max(0.3)
             } else {
-                (1.0 - (sleep_window - 9.5) * 0.1).max(0.6)
-            }
+                (1.0 - (sleep_window - (need + 1.5)) * 0.1).max(0.6)
+            };
+            // Within 1h of the preferred bedtime is fine; later or earlier costs 0.15/h
+            let timing_factor = (1.0 - (bedtime_distance - 1.0).max(0.0) * 0.15).max(0.4);
+            window_score * timing_factor
+        } else {
+            0.7
+        };
+        
+        // Component 5: Sleep Midpoint Score (0-1)
+        // Mid-sleep is the most robust behavioural marker of circadian phase
+        // Optimal: within 1h of the chronotype's mid-sleep → 1.0, dropping 0.15 per hour beyond
+        let midpoint_score = if !recent_days.is_empty() {
+            let avg_offset = recent_days.iter()
+                .map(|d| sleep::wrap_hours(sleep::hours_around_midnight(d.midpoint()) - chronotype.mid_sleep_hour))
+                .sum::<f64>() / recent_days.len() as f64;
+            let hours_outside = (avg_offset.abs() - 1.0).max(0.0);
+            (1.0 - hours_outside * 0.15).max(0.2)
         } else {
             0.7
         };
         
         // Light exposure adjustments (entrainment signals)
         let cutoff_time = estimation_time - Duration::hours(168);
-        let light_events: Vec<_> = events
+        let light_events = store
+            .range(cutoff_time, estimation_time)
             .iter()
-            .filter(|e| e.event_type == "light_exposure" && e.timestamp >= cutoff_time && e.timestamp <= estimation_time)
-            .collect();
+            .filter(|event| matches!(event.event_type.as_str(), "light_exposure" | "light_series"));
         
         let mut light_adjustment = 0.0;
         for event in light_events {
-            let impacts = compute_light_impacts(event, estimation_time);
+            let impacts = self.compute_event_impacts(event, estimation_time);
             if let Some(&impact) = impacts.get("circadian_phase") {
                 let hours_ago = (estimation_time - event.timestamp).num_minutes() as f64 / 60.0;
-                let decay = self.exponential_decay(hours_ago, 72.0, Primitive::CircadianPhase);
+                let decay = kinetics::impact_weight(event, estimation_time, 72.0, |hours, half_life| {
+                    self.exponential_decay(hours, half_life, Primitive::CircadianPhase)
+                });
                 
                 // Light can modestly improve or worsen alignment
-                // Morning light helps alignment, evening light hurts it
-                let alignment_impact = impact * decay * 0.15;
+                // Morning light (a phase advance, negative) helps alignment, evening light (a delay) hurts it
+                let alignment_impact = -impact * decay * 0.15;
                 light_adjustment += alignment_impact;
                 
                 contributors.push(EventContribution {
                     event_id: event.event_id.clone(),
-                    event_type: "light_exposure".to_string(),
+                    event_type: event.event_type.clone(),
                     impact: alignment_impact,
                     decayed_impact: alignment_impact,
                     hours_ago,
@@ -1768,22 +2598,36 @@ impl PrimitiveEstimator {
             }
         }
         
+        // Social jet lag beyond 1h costs 0.05 per hour, up to 0.1
+        let social_jet_lag_penalty = metrics.social_jet_lag_hours
+            .map_or(0.0, |hours| ((hours.abs() - 1.0).max(0.0) * 0.05).min(0.1));
+        
         // Combine components with weights
-        // Wake time is most important (40%), then adequacy (30%), then consistency (20%) and bedtime (10%)
+        // Wake time and adequacy matter most (30% each), then midpoint and consistency (15%) and bedtime (10%)
         let alignment_score = (
-            wake_time_score * 0.40 +
+            wake_time_score * 0.30 +
             sleep_adequacy_score * 0.30 +
-            consistency_score * 0.20 +
+            midpoint_score * 0.15 +
+            consistency_score * 0.15 +
             bedtime_score * 0.10 +
-            light_adjustment
+            light_adjustment -
+            social_jet_lag_penalty
         ).clamp(0.0, 1.0);
         
         // Add component scores as contributors for transparency
         contributors.push(EventContribution {
             event_id: "wake_time_alignment".to_string(),
             event_type: "circadian_component".to_string(),
-            impact: wake_time_score * 0.40,
-            decayed_impact: wake_time_score * 0.40,
+            impact: wake_time_score * 0.30,
+            decayed_impact: wake_time_score * 0.30,
+            hours_ago: 0.0,
+        });
+        
+        contributors.push(EventContribution {
+            event_id: "sleep_midpoint_alignment".to_string(),
+            event_type: "circadian_component".to_string(),
+            impact: midpoint_score * 0.15,
+            decayed_impact: midpoint_score * 0.15,
             hours_ago: 0.0,
         });
         
@@ -1795,35 +2639,109 @@ impl PrimitiveEstimator {
             hours_ago: 0.0,
         });
         
+        if social_jet_lag_penalty > 0.0 {
+            contributors.push(EventContribution {
+                event_id: "social_jet_lag".to_string(),
+                event_type: "circadian_component".to_string(),
+                impact: -social_jet_lag_penalty,
+                decayed_impact: -social_jet_lag_penalty,
+                hours_ago: 0.0,
+            });
+        }
+        
         (alignment_score, contributors)
     }
 
+    /// Melatonin (0-1, 1 = night-time peak): endogenous secretion gated by the
+    /// body clock, suppressed by light and screens, plus supplements.
+    ///
+    /// Secretion starts at dim-light melatonin onset (DLMO), 2h before the
+    /// chronotype's preferred bedtime, and ends about an hour after its
+    /// preferred wake time. Daytime levels are near zero.
+    fn compute_melatonin_special(
+        &self,
+        store: &EventStore,
+        estimation_time: DateTime<Utc>,
+        history: &HistoryContext,
+    ) -> (f64, Vec<EventContribution>) {
+        let mut contributors = Vec::new();
+        let chronotype = &history.chronotype;
+        let baseline = self.baseline(Primitive::Melatonin);
+
+        let hour_of_day = estimation_time.hour() as f64 + estimation_time.minute() as f64 / 60.0;
+        let dlmo = chronotype.preferred_bedtime_hour() - 2.0;
+        let since_onset = (hour_of_day - dlmo).rem_euclid(24.0);
+        let secretion_hours = (chronotype.preferred_wake_hour() + 1.0 - dlmo).rem_euclid(24.0);
+        let sigmoid = |x: f64| 1.0 / (1.0 + (-x).exp());
+        let rise = sigmoid((since_onset - 1.0) / 0.4);
+        let fall = sigmoid((secretion_hours - 1.5 - since_onset) / 0.5);
+        let endogenous = baseline + (0.95 - baseline) * rise * fall;
+
+        contributors.push(EventContribution {
+            event_id: "endogenous_secretion".to_string(),
+            event_type: "circadian_component".to_string(),
+            impact: endogenous,
+            decayed_impact: endogenous,
+            hours_ago: 0.0,
+        });
+
+        // Light and screens in the last 6 hours; suppressions combine multiplicatively
+        let mut unsuppressed = 1.0;
+        let cutoff = estimation_time - Duration::hours(6);
+        for event in store
+            .range(cutoff - Duration::hours(kinetics::MAX_INTERVAL_HOURS), estimation_time)
+            .iter()
+            .filter(|event| kinetics::in_window(event, cutoff))
+        {
+            let suppression = melatonin_suppression_at(event, estimation_time);
+            if suppression < 0.01 {
+                continue;
+            }
+            let reduction = endogenous * unsuppressed * suppression;
+            unsuppressed *= 1.0 - suppression;
+            contributors.push(EventContribution {
+                event_id: event.event_id.clone(),
+                event_type: event.event_type.clone(),
+                impact: -endogenous * suppression,
+                decayed_impact: -reduction,
+                hours_ago: (estimation_time - event.timestamp).num_minutes() as f64 / 60.0,
+            });
+        }
+
+        let mut exogenous = 0.0;
+        for event in store.of_type("melatonin", estimation_time - Duration::hours(12), estimation_time) {
+            let level = exogenous_melatonin(event, estimation_time);
+            exogenous += level;
+            contributors.push(EventContribution {
+                event_id: event.event_id.clone(),
+                event_type: "melatonin".to_string(),
+                impact: level,
+                decayed_impact: level,
+                hours_ago: (estimation_time - event.timestamp).num_minutes() as f64 / 60.0,
+            });
+        }
+
+        ((endogenous * unsuppressed + exogenous).clamp(0.0, 1.0), contributors)
+    }
+
     fn compute_monoamine_scores(
         &self,
         primitive: Primitive,
-        events: &[Event],
+        store: &EventStore,
         estimation_time: DateTime<Utc>,
     ) -> (f64, f64, f64, Vec<EventContribution>) {
-        let acute_config = ContextConfig::acute_for_monoamine(primitive);
-        let chronic_config = ContextConfig::chronic_for_monoamine(primitive);
+        let acute_config = self.params.acute_config(primitive);
+        let chronic_config = self.params.chronic_config(primitive);
         
-        let baseline = self.baseline_values
-            .get(primitive.as_str())
-            .copied()
-            .unwrap_or(0.5);
+        let baseline = self.baseline(primitive);
 
         let acute_cutoff = estimation_time - Duration::hours(acute_config.window_hours);
         let chronic_cutoff = estimation_time - Duration::hours(chronic_config.window_hours);
         
-        let _acute_events: Vec<_> = events
+        let chronic_events = store
+            .range(chronic_cutoff - Duration::hours(kinetics::MAX_INTERVAL_HOURS), estimation_time)
             .iter()
-            .filter(|e| e.timestamp >= acute_cutoff && e.timestamp <= estimation_time)
-            .collect();
-        
-        let chronic_events: Vec<_> = events
-            .iter()
-            .filter(|e| e.timestamp >= chronic_cutoff && e.timestamp <= estimation_time)
-            .collect();
+            .filter(|event| kinetics::in_window(event, chronic_cutoff));
 
         let mut acute_impact = 0.0;
         let mut chronic_impact = 0.0;
@@ -1834,12 +2752,13 @@ impl PrimitiveEstimator {
             let impacts = self.compute_event_impacts(event, estimation_time);
             
             if let Some(&raw_impact) = impacts.get(primitive.as_str()) {
-                let chronic_decay = self.exponential_decay(hours_ago, chronic_config.decay_half_life_hours, primitive);
+                let decay = |hours, half_life| self.exponential_decay(hours, half_life, primitive);
+                let chronic_decay = kinetics::impact_weight(event, estimation_time, chronic_config.decay_half_life_hours, decay);
                 let chronic_contribution = raw_impact * chronic_decay;
                 chronic_impact += chronic_contribution;
                 
-                if hours_ago <= acute_config.window_hours as f64 {
-                    let acute_decay = self.exponential_decay(hours_ago, acute_config.decay_half_life_hours, primitive);
+                if kinetics::in_window(event, acute_cutoff) {
+                    let acute_decay = kinetics::impact_weight(event, estimation_time, acute_config.decay_half_life_hours, decay);
                     let acute_contribution = raw_impact * acute_decay;
                     acute_impact += acute_contribution;
                 }
@@ -1863,7 +2782,8 @@ impl PrimitiveEstimator {
 
         let acute_score = (baseline + acute_impact).clamp(0.0, 1.0);
         let chronic_score = (baseline + chronic_impact).clamp(0.0, 1.0);
-        let combined_score = (chronic_score * 0.7 + acute_score * 0.3).clamp(0.0, 1.0);
+        let chronic_weight = self.params.monoamine_chronic_weight;
+        let combined_score = (chronic_score * chronic_weight + acute_score * (1.0 - chronic_weight)).clamp(0.0, 1.0);
 
         (acute_score, chronic_score, combined_score, all_contributors)
     }
@@ -1873,7 +2793,7 @@ impl PrimitiveEstimator {
         let adhd_modifier = self.adhd_decay_modifier(primitive);
         let effective_half_life = half_life_hours / adhd_modifier;
         
-        let lambda = 0.693147 / effective_half_life;
+        let lambda = std::f64::consts::LN_2 / effective_half_life;
         (-lambda * hours_ago).exp()
     }
 
@@ -1890,16 +2810,16 @@ impl PrimitiveEstimator {
         // - Returns to nadir by midnight
         
         match hour_of_day {
-            h if h >= 0.0 && h < 2.0 => {
+            h if (0.0..2.0).contains(&h) => {
                 // Midnight to 2 AM: at nadir
                 0.25
             }
-            h if h >= 2.0 && h < 6.0 => {
+            h if (2.0..6.0).contains(&h) => {
                 // 2 AM to 6 AM: pre-awakening rise (0.25 -> 0.6)
                 let progress = (h - 2.0) / 4.0;
                 0.25 + (0.35 * progress)
             }
-            h if h >= 6.0 && h < 9.0 => {
+            h if (6.0..9.0).contains(&h) => {
                 // 6 AM to 9 AM: morning peak including CAR (0.6 -> 1.0 -> 0.9)
                 let progress = (h - 6.0) / 3.0;
                 if progress < 0.5 {
@@ -1910,17 +2830,17 @@ impl PrimitiveEstimator {
                     1.0 - (0.1 * (progress - 0.5) * 2.0)
                 }
             }
-            h if h >= 9.0 && h < 12.0 => {
+            h if (9.0..12.0).contains(&h) => {
                 // 9 AM to noon: post-peak decline (0.9 -> 0.7)
                 let progress = (h - 9.0) / 3.0;
                 0.9 - (0.2 * progress)
             }
-            h if h >= 12.0 && h < 18.0 => {
+            h if (12.0..18.0).contains(&h) => {
                 // Noon to 6 PM: afternoon decline (0.7 -> 0.45)
                 let progress = (h - 12.0) / 6.0;
                 0.7 - (0.25 * progress)
             }
-            h if h >= 18.0 && h < 22.0 => {
+            h if (18.0..22.0).contains(&h) => {
                 // 6 PM to 10 PM: evening decline (0.45 -> 0.3)
                 let progress = (h - 18.0) / 4.0;
                 0.45 - (0.15 * progress)
@@ -1935,23 +2855,14 @@ impl PrimitiveEstimator {
 
     /// Calculate cortisol awakening response (CAR) boost
     /// Returns a multiplier (1.0 baseline, up to 1.75 for recent wake)
-    fn cortisol_awakening_boost(events: &[Event], estimation_time: DateTime<Utc>) -> f64 {
+    fn cortisol_awakening_boost(store: &EventStore, estimation_time: DateTime<Utc>) -> f64 {
         // Find the most recent wake event within the last 2 hours
         let lookback_time = estimation_time - Duration::hours(2);
         
-        let mut most_recent_wake: Option<DateTime<Utc>> = None;
-        for event in events.iter().rev() {
-            if event.timestamp < lookback_time {
-                break;
-            }
-            if event.timestamp > estimation_time {
-                continue;
-            }
-            if event.event_type == "wake" {
-                most_recent_wake = Some(event.timestamp);
-                break;
-            }
-        }
+        let most_recent_wake = store
+            .of_type("wake", lookback_time, estimation_time)
+            .next_back()
+            .map(|e| e.timestamp);
         
         if let Some(wake_time) = most_recent_wake {
             let minutes_since_wake = (estimation_time - wake_time).num_minutes() as f64;
@@ -1986,15 +2897,15 @@ impl PrimitiveEstimator {
         }
     }
 
-    fn compute_sleep_drive(&self, adenosine: f64, circadian_phase: f64, estimation_time: DateTime<Utc>) -> f64 {
+    fn compute_sleep_drive(&self, adenosine: f64, circadian_phase: f64, melatonin: f64, estimation_time: DateTime<Utc>) -> f64 {
         let hour_of_day = estimation_time.hour() as f64 + (estimation_time.minute() as f64 / 60.0);
         let circadian_sleep_pressure = Self::circadian_sleep_pressure(hour_of_day, circadian_phase);
         
         let homeostatic_component = adenosine;
-        let circadian_component = circadian_sleep_pressure;
+        // Melatonin opens the sleep gate: light that suppresses it keeps the circadian drive down
+        let circadian_component = circadian_sleep_pressure * 0.7 + melatonin * 0.3;
         
-        let sleep_drive = (homeostatic_component * 0.6 + circadian_component * 0.4).clamp(0.0, 1.0);
-        sleep_drive
+        (homeostatic_component * 0.6 + circadian_component * 0.4).clamp(0.0, 1.0)
     }
 
     fn circadian_sleep_pressure(hour_of_day: f64, phase: f64) -> f64 {
@@ -2018,28 +2929,30 @@ impl PrimitiveEstimator {
         sleep_pressure.clamp(0.0, 1.0)
     }
 
-    fn detect_sequences(&self, events: &[Event], estimation_time: DateTime<Utc>) -> Vec<DetectedSequence> {
+    fn detect_sequences(
+        &self,
+        store: &EventStore,
+        estimation_time: DateTime<Utc>,
+        history: &HistoryContext,
+        sleep_inertia: &SleepInertia,
+        naps: &NapReport,
+    ) -> Vec<DetectedSequence> {
         let mut sequences = Vec::new();
         
         let lookback_hours = 72;
         let cutoff_time = estimation_time - Duration::hours(lookback_hours);
-        let recent_events: Vec<_> = events
-            .iter()
-            .filter(|e| e.timestamp >= cutoff_time && e.timestamp <= estimation_time)
-            .collect();
 
-        let recent_sleep_events: Vec<_> = recent_events
-            .iter()
-            .filter(|e| e.event_type == "sleep")
+        // Most recent first, so take(3) looks at the last three nights
+        let recent_sleep_events: Vec<_> = store
+            .of_type("sleep", cutoff_time, estimation_time)
+            .rev()
             .collect();
         
         if recent_sleep_events.len() >= 2 {
             let mut poor_sleep_count = 0;
             for event in recent_sleep_events.iter().take(3) {
-                let quality = event.properties.get("quality")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("good");
-                if quality == "poor" || quality == "fair" {
+                // Fair (0.6) or poor (0.4), whether reported or derived from architecture
+                if sleep_quality_score(event) < 0.7 {
                     poor_sleep_count += 1;
                 }
             }
@@ -2049,14 +2962,112 @@ impl PrimitiveEstimator {
                     pattern_name: "chronic_sleep_deprivation".to_string(),
                     events: recent_sleep_events.iter().take(3).map(|e| e.event_id.clone()).collect(),
                     impact_on_primitive: "dopamine".to_string(),
-                    adjustment: -0.2,
+                    adjustment: -self.params.sleep_deprivation_dopamine_penalty,
                 });
                 
                 sequences.push(DetectedSequence {
                     pattern_name: "chronic_sleep_deprivation".to_string(),
                     events: recent_sleep_events.iter().take(3).map(|e| e.event_id.clone()).collect(),
                     impact_on_primitive: "serotonin".to_string(),
-                    adjustment: -0.15,
+                    adjustment: -self.params.sleep_deprivation_serotonin_penalty,
+                });
+            }
+        }
+
+        // Sleep regularity patterns, over each metric's own window
+        let metrics = &history.sleep_metrics;
+        let sleep_ids = |days: i64| -> Vec<String> {
+            store
+                .of_type("sleep", estimation_time - Duration::days(days), estimation_time)
+                .map(|e| e.event_id.clone())
+                .collect()
+        };
+
+        // Irregular timing (SRI < 60) predicts lower mood independently of sleep duration
+        if metrics.sleep_regularity_index.is_some_and(|sri| sri < 60.0) {
+            sequences.push(DetectedSequence {
+                pattern_name: "irregular_sleep".to_string(),
+                events: sleep_ids(7),
+                impact_on_primitive: "serotonin".to_string(),
+                adjustment: -self.params.irregular_sleep_serotonin_penalty,
+            });
+        }
+
+        // Social jet lag of 2h+ is a weekly shift of the clock, with raised cortisol
+        if metrics.social_jet_lag_hours.is_some_and(|hours| hours.abs() >= 2.0) {
+            sequences.push(DetectedSequence {
+                pattern_name: "social_jet_lag".to_string(),
+                events: sleep_ids(28),
+                impact_on_primitive: "cortisol".to_string(),
+                adjustment: self.params.social_jet_lag_cortisol_increase,
+            });
+        }
+
+        // Mid-sleep moving 30+ min a day: the schedule is not entrained
+        if metrics.midpoint_drift_hours_per_day.is_some_and(|drift| drift.abs() >= 0.5) {
+            sequences.push(DetectedSequence {
+                pattern_name: "circadian_drift".to_string(),
+                events: sleep_ids(14),
+                impact_on_primitive: "circadian_phase".to_string(),
+                adjustment: -self.params.circadian_drift_penalty,
+            });
+        }
+
+        // Overreaching: a load spike the body is not absorbing, which blunts
+        // motivation and keeps the HPA axis activated
+        let training = &history.training;
+        if training.overreaching {
+            sequences.push(DetectedSequence {
+                pattern_name: "overreaching".to_string(),
+                events: training.acute_session_ids.clone(),
+                impact_on_primitive: "dopamine".to_string(),
+                adjustment: -self.params.overreaching_dopamine_penalty,
+            });
+            sequences.push(DetectedSequence {
+                pattern_name: "overreaching".to_string(),
+                events: training.acute_session_ids.clone(),
+                impact_on_primitive: "cortisol".to_string(),
+                adjustment: self.params.overreaching_cortisol_increase,
+            });
+        }
+
+        // Allostatic load: weeks of uncontrollable stress and poor sleep deplete
+        // serotonin in proportion to the accumulated index
+        let allostatic = &history.allostatic_load;
+        if allostatic.status != AllostaticStatus::Low {
+            sequences.push(DetectedSequence {
+                pattern_name: "allostatic_load".to_string(),
+                events: store
+                    .of_type("stress_event", estimation_time - Duration::days(14), estimation_time)
+                    .map(|e| e.event_id.clone())
+                    .collect(),
+                impact_on_primitive: "serotonin".to_string(),
+                adjustment: -self.params.allostatic_serotonin_penalty * allostatic.index,
+            });
+        }
+
+        // Sleep inertia: the cortisol awakening response is under way, but arousal
+        // lags behind it for the first 15-60 minutes after waking
+        if sleep_inertia.level >= 0.01 {
+            sequences.push(DetectedSequence {
+                pattern_name: "sleep_inertia".to_string(),
+                events: sleep_inertia.event_id.iter().cloned().collect(),
+                impact_on_primitive: "norepinephrine".to_string(),
+                adjustment: -self.params.sleep_inertia_norepinephrine_penalty * sleep_inertia.level,
+            });
+        }
+
+        // Caffeine nap: caffeine taken just before a short nap reaches the brain as
+        // the nap ends, blocking the receptors the nap has just cleared. The bonus
+        // fades over the 3 hours after the nap.
+        for nap in naps.naps.iter().filter(|nap| nap.caffeine_nap) {
+            let hours_since_end = (estimation_time - nap.end()).num_minutes() as f64 / 60.0;
+            if (0.0..3.0).contains(&hours_since_end) {
+                sequences.push(DetectedSequence {
+                    pattern_name: "caffeine_nap".to_string(),
+                    events: nap.caffeine_event_id.iter().cloned().chain([nap.event_id.clone()]).collect(),
+                    impact_on_primitive: "adenosine".to_string(),
+                    adjustment: -self.params.caffeine_nap_adenosine_reduction * (1.0 - hours_since_end / 3.0),
                 });
             }
         }
@@ -2064,7 +3075,11 @@ impl PrimitiveEstimator {
         sequences
     }
 
-    fn apply_cross_primitive_modifiers(&self, scores: &HashMap<String, f64>) -> (HashMap<String, f64>, HashMap<String, f64>) {
+    fn apply_cross_primitive_modifiers(
+        &self,
+        scores: &HashMap<String, f64>,
+        mut trace: Option<&mut EstimationTrace>,
+    ) -> (HashMap<String, f64>, HashMap<String, f64>) {
         let mut modified_scores = scores.clone();
         let mut effective_monoamines = HashMap::new();
         
@@ -2072,22 +3087,24 @@ impl PrimitiveEstimator {
         let cortisol = scores.get("cortisol").copied().unwrap_or(0.5);
         
         // ADHD mode: Stronger suppression effects from adenosine and cortisol
-        let adhd_suppression_multiplier = if self.adhd_mode { 1.4 } else { 1.0 };
+        let adhd_suppression_multiplier = self.adhd_suppression_multiplier();
         
         if let Some(dopamine) = scores.get("dopamine") {
             // Non-linear adenosine suppression: fatigue compounds
             // Research shows sleep deprivation downregulates D2 receptors via adenosine
             // Using squared relationship to model compounding effects
             // ADHD: Even more sensitive to adenosine effects
-            let adenosine_suppression = if adenosine > 0.5 {
-                let excess = adenosine - 0.5;
-                -1.2 * adhd_suppression_multiplier * excess * excess  // Stronger in ADHD
+            let adenosine_threshold = self.params.adenosine_suppression_threshold;
+            let adenosine_suppression = if adenosine > adenosine_threshold {
+                let excess = adenosine - adenosine_threshold;
+                -self.params.adenosine_dopamine_suppression * adhd_suppression_multiplier * excess * excess  // Stronger in ADHD
             } else {
                 0.0
             };
             
-            let cortisol_suppression = if cortisol > 0.6 {
-                -0.2 * adhd_suppression_multiplier * (cortisol - 0.6)  // Stronger in ADHD
+            let cortisol_threshold = self.params.cortisol_suppression_threshold;
+            let cortisol_suppression = if cortisol > cortisol_threshold {
+                -self.params.cortisol_dopamine_suppression * adhd_suppression_multiplier * (cortisol - cortisol_threshold)  // Stronger in ADHD
             } else {
                 0.0
             };
@@ -2097,29 +3114,59 @@ impl PrimitiveEstimator {
             // Apply ADHD variability
             let final_dopamine = self.adhd_add_variability(modified_dopamine, Primitive::Dopamine);
             modified_scores.insert("dopamine".to_string(), final_dopamine);
+
+            if let Some(trace) = trace.as_deref_mut() {
+                let after_adenosine = (dopamine + adenosine_suppression).clamp(0.0, 1.0);
+                if adenosine_suppression != 0.0 {
+                    trace.step("dopamine", TracePass::CrossPrimitive, "adenosine_suppression", *dopamine, after_adenosine,
+                        format!("Adenosine {:.3} > {:.2}: -{} x {:.1} x excess^2 = {:.3}", adenosine, adenosine_threshold,
+                            self.params.adenosine_dopamine_suppression, adhd_suppression_multiplier, adenosine_suppression));
+                }
+                if cortisol_suppression != 0.0 {
+                    trace.step("dopamine", TracePass::CrossPrimitive, "cortisol_suppression", after_adenosine, modified_dopamine,
+                        format!("Cortisol {:.3} > {:.2}: -{} x {:.1} x excess = {:.3}", cortisol, cortisol_threshold,
+                            self.params.cortisol_dopamine_suppression, adhd_suppression_multiplier, cortisol_suppression));
+                }
+                if self.adhd_mode {
+                    trace.step("dopamine", TracePass::CrossPrimitive, "adhd_variability", modified_dopamine, final_dopamine,
+                        "ADHD intra-individual variability (deterministic, up to +/-0.08)".to_string());
+                }
+            }
         }
         
         if let Some(norepinephrine) = scores.get("norepinephrine") {
             // Apply ADHD variability to norepinephrine as well
             let final_ne = self.adhd_add_variability(*norepinephrine, Primitive::Norepinephrine);
             modified_scores.insert("norepinephrine".to_string(), final_ne);
+
+            if let (true, Some(trace)) = (self.adhd_mode, trace.as_deref_mut()) {
+                trace.step("norepinephrine", TracePass::CrossPrimitive, "adhd_variability", *norepinephrine, final_ne,
+                    "ADHD intra-individual variability (deterministic, up to +/-0.08)".to_string());
+            }
         }
         
         if let Some(serotonin) = scores.get("serotonin") {
-            let cortisol_suppression = if cortisol > 0.6 {
-                -0.15 * (cortisol - 0.6)
+            let cortisol_threshold = self.params.cortisol_suppression_threshold;
+            let cortisol_suppression = if cortisol > cortisol_threshold {
+                -self.params.cortisol_serotonin_suppression * (cortisol - cortisol_threshold)
             } else {
                 0.0
             };
             
             let modified_serotonin = (serotonin + cortisol_suppression).clamp(0.0, 1.0);
             modified_scores.insert("serotonin".to_string(), modified_serotonin);
+
+            if let (true, Some(trace)) = (cortisol_suppression != 0.0, trace.as_deref_mut()) {
+                trace.step("serotonin", TracePass::CrossPrimitive, "cortisol_suppression", *serotonin, modified_serotonin,
+                    format!("Cortisol {:.3} > {:.2}: -{} x excess = {:.3}", cortisol, cortisol_threshold,
+                        self.params.cortisol_serotonin_suppression, cortisol_suppression));
+            }
         }
         
         let dopamine = modified_scores.get("dopamine").copied().unwrap_or(0.5);
         let serotonin = modified_scores.get("serotonin").copied().unwrap_or(0.5);
         
-        let inhibition_strength = 0.15;
+        let inhibition_strength = self.params.reciprocal_inhibition;
         let da_inhibits_ser = dopamine * inhibition_strength;
         let ser_inhibits_da = serotonin * inhibition_strength;
         
@@ -2128,56 +3175,53 @@ impl PrimitiveEstimator {
         
         effective_monoamines.insert("dopamine".to_string(), effective_dopamine);
         effective_monoamines.insert("serotonin".to_string(), effective_serotonin);
+
+        if let Some(trace) = trace {
+            trace.step("dopamine", TracePass::CrossPrimitive, "reciprocal_inhibition", dopamine, effective_dopamine,
+                format!("Effective score: serotonin {:.3} x {} inhibits dopamine", serotonin, inhibition_strength));
+            trace.step("serotonin", TracePass::CrossPrimitive, "reciprocal_inhibition", serotonin, effective_serotonin,
+                format!("Effective score: dopamine {:.3} x {} inhibits serotonin", dopamine, inhibition_strength));
+        }
         
         (modified_scores, effective_monoamines)
     }
 
-    fn compute_functional_state(dopamine: f64, serotonin: f64) -> FunctionalState {
-        let _ratio = if serotonin > 0.01 {
-            dopamine / serotonin
-        } else {
-            dopamine / 0.01
-        };
-        
-        if dopamine >= 0.6 && serotonin >= 0.6 {
-            FunctionalState {
-                state_type: "Optimal".to_string(),
-                description: "Both motivation and mood are strong. Ideal state for productivity and well-being.".to_string(),
-                recommendations: vec![
-                    "Maintain current patterns".to_string(),
-                    "This is a good time for challenging work or important decisions".to_string(),
-                ],
-            }
-        } else if dopamine >= 0.6 && serotonin < 0.5 {
-            FunctionalState {
-                state_type: "Driven but Anxious".to_string(),
-                description: "High motivation but low contentment. Risk of stress and burnout.".to_string(),
-                recommendations: vec![
-                    "Practice stress-reduction techniques".to_string(),
-                    "Increase serotonin: social connection, outdoor time, balanced meals".to_string(),
-                    "Avoid overcommitting to new projects".to_string(),
-                ],
-            }
-        } else if dopamine < 0.5 && serotonin >= 0.6 {
-            FunctionalState {
-                state_type: "Content but Unmotivated".to_string(),
-                description: "Good mood but low drive. May struggle with initiation and focus.".to_string(),
-                recommendations: vec![
-                    "Boost dopamine: exercise (especially HIIT), achievement tasks, protein-rich meals".to_string(),
-                    "Set small, concrete goals to build momentum".to_string(),
-                    "Consider caffeine in moderation (morning only)".to_string(),
-                ],
-            }
-        } else {
-            FunctionalState {
-                state_type: "Depleted".to_string(),
-                description: "Both motivation and mood are low. Recovery is the priority.".to_string(),
-                recommendations: vec![
-                    "Prioritize rest and sleep".to_string(),
-                    "Avoid demanding decisions or high-stress situations".to_string(),
-                    "Gentle exercise, social connection, and balanced nutrition".to_string(),
-                ],
-            }
+    /// Inputs to the state classifier: final scores (effective for dopamine and
+    /// serotonin), sleep drive, time-of-day sleep pressure and hours since the last meal
+    fn classify_state(
+        &self,
+        final_scores: &HashMap<String, f64>,
+        effective_monoamines: &HashMap<String, f64>,
+        sleep_drive: f64,
+        circadian_phase: f64,
+        store: &EventStore,
+        estimation_time: DateTime<Utc>,
+    ) -> FunctionalState {
+        let mut inputs: BTreeMap<String, f64> = final_scores.iter()
+            .map(|(key, &score)| (key.clone(), effective_monoamines.get(key).copied().unwrap_or(score)))
+            .collect();
+        let hour_of_day = estimation_time.hour() as f64 + (estimation_time.minute() as f64 / 60.0);
+        inputs.insert("sleep_drive".to_string(), sleep_drive);
+        inputs.insert("circadian_sleep_pressure".to_string(), Self::circadian_sleep_pressure(hour_of_day, circadian_phase));
+
+        // Meals more than a day ago count as a day
+        let hours_since_meal = store
+            .of_type("meal", estimation_time - Duration::hours(24), estimation_time)
+            .next_back()
+            .map(|meal| (estimation_time - meal.timestamp).num_minutes() as f64 / 60.0)
+            .unwrap_or(24.0);
+        inputs.insert("hours_since_meal".to_string(), hours_since_meal);
+
+        self.states.classify(&inputs)
+    }
+
+    /// Motivation/mood quadrant from dopamine and serotonin alone, split at 0.55
+    pub(crate) fn mood_quadrant(dopamine: f64, serotonin: f64) -> &'static str {
+        match (dopamine >= 0.55, serotonin >= 0.55) {
+            (true, true) => "Optimal",
+            (true, false) => "Driven but Anxious",
+            (false, true) => "Content but Unmotivated",
+            (false, false) => "Depleted",
         }
     }
 }
@@ -0,0 +1,289 @@
+// light.rs - Light at the eye in melanopic units: wearable lux series, photic dose-response and daylight episodes
+use crate::kinetics;
+use crate::{Event, EventStore};
+use chrono::{DateTime, Duration, Timelike, Utc};
+use serde::Serialize;
+
+/// Melanopic EDI (lux) giving half the maximal circadian drive
+const HALF_DRIVE_EDI: f64 = 1000.0;
+/// Hill exponent of the drive: how sharply it rises through room-light levels
+const DRIVE_STEEPNESS: f64 = 1.5;
+/// Full-drive minutes at which a phase shift reaches 63% of its maximum
+const PHASE_DOSE_MINUTES: f64 = 90.0;
+/// Full-drive minutes of daytime light for the whole serotonin effect (30 min at 10,000 lux)
+const SEROTONIN_DOSE_MINUTES: f64 = 30.0;
+/// Photopic illuminance counted as daylight
+pub const DAYLIGHT_LUX: f64 = 1000.0;
+/// Shortest run above daylight level that counts as an episode
+const MIN_EPISODE_MINUTES: f64 = 10.0;
+/// Dips below daylight level up to this long (shade, a passing cloud) don't end an episode
+const MAX_EPISODE_GAP_MINUTES: f64 = 3.0;
+/// Constant-intensity `light_exposure` events are integrated in steps of this length
+const STEP_MINUTES: i64 = 5;
+/// Default sample interval of a `light_series`
+const DEFAULT_INTERVAL_SECONDS: f64 = 60.0;
+
+/// Light at the eye over one sample interval
+#[derive(Debug, Clone, Copy)]
+pub struct LightSample {
+    pub time: DateTime<Utc>,
+    pub minutes: f64,
+    // Photopic illuminance
+    pub lux: f64,
+    // Melanopic equivalent daylight illuminance (CIE S 026), measured or estimated from the spectrum
+    pub melanopic_edi: f64,
+}
+
+impl LightSample {
+    pub fn end(&self) -> DateTime<Utc> {
+        self.time + Duration::seconds((self.minutes * 60.0) as i64)
+    }
+}
+
+/// Melanopic EDI per photopic lux for the `wavelength` property: a named source or
+/// a peak wavelength in nm. Daylight is 1 by definition; unknown sources count as
+/// daylight so plain lux keeps its meaning.
+pub fn melanopic_ratio(event: &Event) -> f64 {
+    let Some(wavelength) = event.properties.get("wavelength") else {
+        return 1.0;
+    };
+    if let Some(nm) = wavelength.as_f64() {
+        // Melanopsin peaks near 480 nm
+        return match nm {
+            nm if nm < 420.0 => 0.5,
+            nm if nm <= 500.0 => 1.5,
+            nm if nm <= 560.0 => 0.7,
+            _ => 0.1,
+        };
+    }
+    match wavelength.as_str().map(str::to_lowercase).as_deref() {
+        Some("blue" | "blue_enriched" | "cool_white") => 1.1,
+        Some("white" | "led" | "fluorescent" | "office" | "neutral_white") => 0.6,
+        Some("warm" | "warm_white" | "incandescent" | "halogen") => 0.45,
+        Some("amber" | "red" | "night_mode" | "candle") => 0.1,
+        // "outdoor", "daylight", "sunlight", light therapy boxes
+        _ => 1.0,
+    }
+}
+
+/// Samples of a light event: the recorded series of a `light_series`, or a
+/// `light_exposure` spread evenly over its duration. Empty for other events.
+pub fn samples(event: &Event) -> Vec<LightSample> {
+    let ratio = melanopic_ratio(event);
+    match event.event_type.as_str() {
+        "light_exposure" => {
+            let lux = event.properties.get("intensity_lux").and_then(|v| v.as_f64()).unwrap_or(1000.0);
+            let melanopic_edi = event.properties.get("melanopic_edi").and_then(|v| v.as_f64()).unwrap_or(lux * ratio);
+            let duration = event.properties.get("duration_minutes").and_then(|v| v.as_f64()).unwrap_or(30.0) as i64;
+            let end = event.end_timestamp.unwrap_or(event.timestamp + Duration::minutes(duration));
+            let mut samples = Vec::new();
+            let mut time = event.timestamp;
+            while time < end {
+                let minutes = ((end - time).num_seconds() as f64 / 60.0).min(STEP_MINUTES as f64);
+                samples.push(LightSample { time, minutes, lux, melanopic_edi });
+                time += Duration::minutes(STEP_MINUTES);
+            }
+            samples
+        }
+        "light_series" => {
+            let Some(lux) = event.properties.get("lux").and_then(|v| v.as_array()) else {
+                return Vec::new();
+            };
+            let melanopic = event.properties.get("melanopic_edi").and_then(|v| v.as_array());
+            let interval = interval_seconds(event);
+            lux.iter()
+                .enumerate()
+                .filter_map(|(i, value)| {
+                    let lux = value.as_f64()?.max(0.0);
+                    // Sensors without a melanopic channel, or gaps in it, fall back to the spectrum estimate
+                    let melanopic_edi = melanopic
+                        .and_then(|values| values.get(i))
+                        .and_then(|v| v.as_f64())
+                        .unwrap_or(lux * ratio)
+                        .max(0.0);
+                    Some(LightSample {
+                        time: event.timestamp + Duration::seconds((i as f64 * interval) as i64),
+                        minutes: interval / 60.0,
+                        lux,
+                        melanopic_edi,
+                    })
+                })
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Length of a `light_series` in minutes, from its sample count and interval
+pub fn series_minutes(event: &Event) -> Option<f64> {
+    if event.event_type != "light_series" {
+        return None;
+    }
+    let count = event.properties.get("lux")?.as_array()?.len();
+    Some(count as f64 * interval_seconds(event) / 60.0)
+}
+
+fn interval_seconds(event: &Event) -> f64 {
+    event.properties.get("interval_seconds")
+        .and_then(|v| v.as_f64())
+        .filter(|seconds| *seconds > 0.0)
+        .unwrap_or(DEFAULT_INTERVAL_SECONDS)
+}
+
+/// Circadian drive (0-1) of light at this melanopic EDI: sigmoid in log
+/// illuminance, half-maximal at 1000 melanopic lux. Room light drives the clock
+/// weakly, so it takes hours of it to match a short bright exposure.
+pub fn photic_drive(melanopic_edi: f64) -> f64 {
+    if melanopic_edi <= 0.0 {
+        return 0.0;
+    }
+    1.0 / (1.0 + (HALF_DRIVE_EDI / melanopic_edi).powf(DRIVE_STEEPNESS))
+}
+
+/// Phase response by clock hour: -1 advances fully (after the core body
+/// temperature minimum, through the morning), +1 delays fully (evening and the
+/// first half of the night), with a weakly advancing midday dead zone
+pub fn phase_response(hour: f64) -> f64 {
+    match hour {
+        h if (4.0..11.0).contains(&h) => -1.0,
+        h if (11.0..16.0).contains(&h) => -0.25,
+        h if (16.0..18.0).contains(&h) => 0.0,
+        _ => 1.0,
+    }
+}
+
+/// Light dose of a set of samples, each minute weighted by its photic drive
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct PhoticDose {
+    // Full-drive minutes in the advancing part of the phase response
+    pub advancing: f64,
+    // Full-drive minutes in the delaying part
+    pub delaying: f64,
+    // Minutes between 06:00 and 18:00, weighted by melanopic EDI relative to 10,000 lux
+    pub daytime_bright: f64,
+    // Highest drive between 06:00 and 11:00 (CAR enhancement)
+    pub morning_peak: f64,
+}
+
+impl PhoticDose {
+    pub fn of(samples: &[LightSample]) -> Self {
+        samples.iter().fold(PhoticDose::default(), |mut dose, sample| {
+            let hour = sample.time.hour() as f64 + sample.time.minute() as f64 / 60.0;
+            let drive = photic_drive(sample.melanopic_edi);
+            let response = phase_response(hour);
+            if response < 0.0 {
+                dose.advancing += -response * drive * sample.minutes;
+            } else {
+                dose.delaying += response * drive * sample.minutes;
+            }
+            if (6.0..18.0).contains(&hour) {
+                dose.daytime_bright += (sample.melanopic_edi / 10000.0).min(1.0) * sample.minutes;
+            }
+            if (6.0..11.0).contains(&hour) {
+                dose.morning_peak = dose.morning_peak.max(drive);
+            }
+            dose
+        })
+    }
+
+    /// Net phase shift signal (-0.8 full advance to +0.6 full delay); both sides saturate with dose
+    pub fn phase_shift(&self) -> f64 {
+        let saturate = |minutes: f64| 1.0 - (-minutes / PHASE_DOSE_MINUTES).exp();
+        -0.8 * saturate(self.advancing) + 0.6 * saturate(self.delaying)
+    }
+
+    /// Serotonin boost from daytime bright light, up to 0.25
+    pub fn serotonin(&self) -> f64 {
+        0.25 * (self.daytime_bright / SEROTONIN_DOSE_MINUTES).min(1.0)
+    }
+}
+
+/// A continuous stretch above daylight level, derived from the samples
+#[derive(Debug, Clone, Serialize)]
+pub struct DaylightEpisode {
+    pub event_id: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub minutes: f64,
+    pub mean_lux: f64,
+    pub mean_melanopic_edi: f64,
+}
+
+/// Runs of samples at or above daylight level lasting at least 10 minutes;
+/// shorter dips below it don't split a run
+pub fn daylight_episodes(event_id: &str, samples: &[LightSample]) -> Vec<DaylightEpisode> {
+    let mut episodes = Vec::new();
+    let mut run: Vec<&LightSample> = Vec::new();
+    let mut gap = 0.0;
+    let mut close = |run: &mut Vec<&LightSample>| {
+        let minutes = run.iter().fold(0.0, |total, s| total + s.minutes);
+        if let (true, Some(first), Some(last)) = (minutes >= MIN_EPISODE_MINUTES, run.first(), run.last()) {
+            episodes.push(DaylightEpisode {
+                event_id: event_id.to_string(),
+                start: first.time,
+                end: last.end(),
+                minutes,
+                mean_lux: run.iter().fold(0.0, |total, s| total + s.lux * s.minutes) / minutes,
+                mean_melanopic_edi: run.iter().fold(0.0, |total, s| total + s.melanopic_edi * s.minutes) / minutes,
+            });
+        }
+        run.clear();
+    };
+    for sample in samples {
+        if sample.lux >= DAYLIGHT_LUX {
+            run.push(sample);
+            gap = 0.0;
+        } else if !run.is_empty() {
+            gap += sample.minutes;
+            if gap > MAX_EPISODE_GAP_MINUTES {
+                close(&mut run);
+            }
+        }
+    }
+    close(&mut run);
+    episodes
+}
+
+/// Light over the last 24 hours from light exposures and wearable series
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LightReport {
+    pub dose: PhoticDose,
+    pub daylight_episodes: Vec<DaylightEpisode>,
+    pub daylight_minutes: f64,
+    // Minutes covered by `light_series` samples
+    pub measured_minutes: f64,
+    // Time-weighted mean melanopic EDI over the measured minutes
+    pub mean_melanopic_edi: Option<f64>,
+}
+
+impl LightReport {
+    pub fn compute(store: &EventStore, time: DateTime<Utc>) -> Self {
+        let cutoff = time - Duration::hours(24);
+        let mut report = LightReport::default();
+        let mut all_samples = Vec::new();
+        let mut measured_edi_minutes = 0.0;
+        for event in store
+            .range(cutoff - Duration::hours(kinetics::MAX_INTERVAL_HOURS), time)
+            .iter()
+            .filter(|event| kinetics::in_window(event, cutoff))
+        {
+            let samples: Vec<LightSample> = samples(event)
+                .into_iter()
+                .filter(|sample| sample.time >= cutoff && sample.time < time)
+                .collect();
+            if event.event_type == "light_series" {
+                for sample in &samples {
+                    report.measured_minutes += sample.minutes;
+                    measured_edi_minutes += sample.melanopic_edi * sample.minutes;
+                }
+            }
+            report.daylight_episodes.extend(daylight_episodes(&event.event_id, &samples));
+            all_samples.extend(samples);
+        }
+        report.dose = PhoticDose::of(&all_samples);
+        report.daylight_episodes.sort_by_key(|episode| episode.start);
+        report.daylight_minutes = report.daylight_episodes.iter().fold(0.0, |total, e| total + e.minutes);
+        report.mean_melanopic_edi = (report.measured_minutes > 0.0).then(|| measured_edi_minutes / report.measured_minutes);
+        report
+    }
+}
@@ -0,0 +1,320 @@
+// live.rs - Incremental estimator for events pushed one at a time from a live feed
+use crate::allostatic;
+use crate::sleep::{self, SleepDay, SleepPeriod};
+use crate::training::{self, LoadMethod};
+use crate::{sleep_quality_score, Event, EventStore, EstimationResult, MeasurementType, PrimitiveEstimator};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Longest context window of any primitive (circadian phase, 7 days)
+pub const CIRCADIAN_LOOKBACK_HOURS: i64 = 168;
+
+/// Extra history kept beyond the circadian lookback so the oldest night
+/// in the window is complete even when it started before the window opened
+pub const SLEEP_DEBT_HORIZON_HOURS: i64 = 24;
+
+/// Default delay allowed for events that arrive after newer ones
+/// (wearables typically sync in batches every few hours)
+pub const DEFAULT_REORDER_TOLERANCE_HOURS: i64 = 6;
+
+/// Number of daily summaries kept for evicted history
+const MAX_SUMMARY_DAYS: usize = 90;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LiveError {
+    /// The event is older than the reorder tolerance allows
+    TooLate { event_id: String, timestamp: DateTime<Utc>, earliest_accepted: DateTime<Utc> },
+}
+
+impl fmt::Display for LiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LiveError::TooLate { event_id, timestamp, earliest_accepted } => write!(
+                f,
+                "event '{}' at {} arrived too late (earliest accepted: {})",
+                event_id, timestamp, earliest_accepted
+            ),
+        }
+    }
+}
+
+/// Compact record of one day of events that have been evicted from the live window.
+/// Besides the totals, it keeps what the multi-week history summaries (allostatic
+/// load, training load, chronotype and sleep regularity) read for that day.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DailySummary {
+    pub date: NaiveDate,
+    pub event_counts: BTreeMap<String, usize>,
+    // Sleep is filed under its sleep day (the day it is slept off into)
+    pub sleep_hours: f64,
+    pub sleep_periods: Vec<SleepPeriod>,
+    // Quality score of the longest sleep record
+    pub sleep_quality: Option<f64>,
+    pub caffeine_mg: f64,
+    pub exercise_minutes: f64,
+    // Training load of the day's sessions by each method, and sessions without an average heart rate
+    pub session_rpe_load: f64,
+    pub trimp_load: f64,
+    pub sessions_without_heart_rate: usize,
+    pub stress_events: usize,
+    // Summed stressor severity as used by allostatic load
+    pub stress_severity: f64,
+    // First HRV reading of the day (ms)
+    pub morning_hrv: Option<f64>,
+    pub blunted_car: Option<f64>,
+}
+
+impl DailySummary {
+    fn add(&mut self, event: &Event) {
+        *self.event_counts.entry(event.event_type.clone()).or_insert(0) += 1;
+
+        let number = |key: &str| event.properties.get(key).and_then(|v| v.as_f64()).unwrap_or(0.0);
+        match event.event_type.as_str() {
+            "sleep" => {
+                let Some(period) = sleep::sleep_periods(std::slice::from_ref(event), event.timestamp).pop() else {
+                    return;
+                };
+                if self.sleep_periods.iter().all(|p| period.asleep_hours > p.asleep_hours) {
+                    self.sleep_quality = Some(sleep_quality_score(event));
+                }
+                self.sleep_hours += period.asleep_hours;
+                self.sleep_periods.push(period);
+            }
+            "caffeine" => self.caffeine_mg += number("dose_mg"),
+            "exercise" => {
+                self.exercise_minutes += number("duration_minutes");
+                self.session_rpe_load += training::session_load(event, LoadMethod::SessionRpe);
+                self.trimp_load += training::session_load(event, LoadMethod::Trimp);
+                if event.properties.get("heart_rate_avg").and_then(|v| v.as_f64()).is_none() {
+                    self.sessions_without_heart_rate += 1;
+                }
+            }
+            "stress_event" => {
+                self.stress_events += 1;
+                self.stress_severity += allostatic::stressor_severity(event);
+            }
+            "health_hrv" if self.morning_hrv.is_none() => self.morning_hrv = training::hrv_ms(event),
+            _ => {}
+        }
+    }
+
+    /// Training load of the day's sessions by `method`
+    pub fn training_load(&self, method: LoadMethod) -> f64 {
+        match method {
+            LoadMethod::SessionRpe => self.session_rpe_load,
+            LoadMethod::Trimp => self.trimp_load,
+        }
+    }
+
+    pub fn exercise_sessions(&self) -> usize {
+        self.event_counts.get("exercise").copied().unwrap_or(0)
+    }
+}
+
+/// Sleep days from the store and the periods kept in `summaries`, most recent day first
+pub(crate) fn sleep_days_with_summaries(
+    store: &EventStore,
+    summaries: &BTreeMap<NaiveDate, DailySummary>,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Vec<SleepDay> {
+    let summarized = summaries.values().flat_map(|summary| summary.sleep_periods.iter().cloned());
+    sleep::group_sleep_days(summarized.chain(sleep::sleep_periods(store.until(until), until)), since, until)
+}
+
+/// Stateful estimator for live event feeds.
+///
+/// Events are pushed as they happen. Only the history the primitives read
+/// directly is retained (the circadian lookback plus sleep horizon); older
+/// events are folded into daily summaries, which serve the multi-week history
+/// windows. The estimate is computed when it is first read after a change and
+/// cached until the next one.
+pub struct LiveEstimator {
+    estimator: PrimitiveEstimator,
+    store: EventStore,
+    clock: Option<DateTime<Utc>>,
+    reorder_tolerance: Duration,
+    summaries: BTreeMap<NaiveDate, DailySummary>,
+    // Events before this have been summarized, so late arrivals before it are rejected
+    summarized_until: Option<DateTime<Utc>>,
+    current: Option<EstimationResult>,
+}
+
+impl LiveEstimator {
+    pub fn new(estimator: PrimitiveEstimator) -> Self {
+        LiveEstimator {
+            estimator,
+            store: EventStore::default(),
+            clock: None,
+            reorder_tolerance: Duration::hours(DEFAULT_REORDER_TOLERANCE_HOURS),
+            summaries: BTreeMap::new(),
+            summarized_until: None,
+            current: None,
+        }
+    }
+
+    pub fn with_reorder_tolerance(mut self, tolerance: Duration) -> Self {
+        self.reorder_tolerance = tolerance;
+        self
+    }
+
+    /// How far back events are kept before being summarized: the longest
+    /// context window of any primitive (circadian phase) plus the sleep horizon.
+    /// Events are summarized a whole day at a time, so up to a day more is kept.
+    pub fn retention() -> Duration {
+        Duration::hours(CIRCADIAN_LOOKBACK_HOURS + SLEEP_DEBT_HORIZON_HOURS)
+    }
+
+    /// Add one event. The estimate is recomputed when next read.
+    /// Returns Ok(false) if the event duplicated one already received.
+    pub fn push(&mut self, event: Event) -> Result<bool, LiveError> {
+        let stored = self.accept(event)?;
+        self.current = None;
+        Ok(stored)
+    }
+
+    /// Add a batch of events in any order.
+    /// Events outside the reorder tolerance are returned as errors; the rest are kept.
+    pub fn push_all(&mut self, events: Vec<Event>) -> Vec<LiveError> {
+        let mut events = events;
+        events.sort_by_key(|e| e.timestamp);
+
+        let rejected = events
+            .into_iter()
+            .filter_map(|event| self.accept(event).err())
+            .collect();
+        self.current = None;
+        rejected
+    }
+
+    /// Move the clock forward without a new event (e.g. to read the state "now")
+    pub fn advance_to(&mut self, now: DateTime<Utc>) {
+        if self.clock.is_some_and(|clock| now <= clock) {
+            return;
+        }
+        self.clock = Some(now);
+        self.evict();
+        self.current = None;
+    }
+
+    /// The estimate at `clock()`, computed on the first read after a change
+    pub fn current_state(&mut self) -> Option<&EstimationResult> {
+        if self.current.is_none() {
+            let clock = self.clock?;
+            self.current = Some(self.estimator.run_passes_with_summaries(&self.store, clock, &self.summaries));
+        }
+        self.current.as_ref()
+    }
+
+    /// Time of the latest event received or the latest `advance_to`
+    pub fn clock(&self) -> Option<DateTime<Utc>> {
+        self.clock
+    }
+
+    /// Events still inside the live window, in canonical order
+    pub fn events(&self) -> &[Event] {
+        self.store.events()
+    }
+
+    /// Daily summaries of evicted events, oldest first
+    pub fn summaries(&self) -> impl Iterator<Item = &DailySummary> {
+        self.summaries.values()
+    }
+
+    fn accept(&mut self, event: Event) -> Result<bool, LiveError> {
+        if let Some(clock) = self.clock {
+            let mut earliest_accepted = clock - self.reorder_tolerance;
+            if let Some(summarized_until) = self.summarized_until {
+                earliest_accepted = earliest_accepted.max(summarized_until);
+            }
+            if event.timestamp < earliest_accepted {
+                return Err(LiveError::TooLate {
+                    event_id: event.event_id,
+                    timestamp: event.timestamp,
+                    earliest_accepted,
+                });
+            }
+        }
+
+        let timestamp = event.timestamp;
+        let stored = self.store.insert(event);
+        if self.clock.is_none_or(|clock| timestamp > clock) {
+            self.clock = Some(timestamp);
+            self.evict();
+        }
+        Ok(stored)
+    }
+
+    /// Fold whole days older than the retention window into daily summaries
+    fn evict(&mut self) {
+        let Some(clock) = self.clock else { return };
+        let cutoff = (clock - Self::retention()).date_naive().and_time(NaiveTime::MIN).and_utc();
+        if self.summarized_until.is_some_and(|until| cutoff <= until) {
+            return;
+        }
+        self.summarized_until = Some(cutoff);
+
+        // The cortisol awakening response needs the whole morning, so read it before eviction
+        let blunted_car: Vec<(NaiveDate, f64)> = self
+            .store
+            .events()
+            .first()
+            .map(|first| first.timestamp.date_naive())
+            .into_iter()
+            .flat_map(|first| first.iter_days().take_while(|date| *date < cutoff.date_naive()))
+            .filter_map(|date| Some((date, allostatic::blunted_car(&self.store, date, clock)?)))
+            .collect();
+        let evicted = self.store.evict_before(cutoff);
+
+        // Measurements with a longer lookback than the window keep their latest reading
+        let retention_hours = Self::retention().num_hours();
+        let mut carried_over: Vec<Event> = Vec::new();
+        let mut summarized: Vec<Event> = Vec::new();
+        for event in evicted.into_iter().rev() {
+            let long_lookback = MeasurementType::from_event_type(&event.event_type)
+                .map(|m| m.lookback_hours())
+                .filter(|&hours| hours > retention_hours);
+            if let Some(lookback_hours) = long_lookback {
+                let still_relevant = event.timestamp >= clock - Duration::hours(lookback_hours);
+                let superseded = carried_over.iter().any(|e| e.event_type == event.event_type)
+                    || self.store.events().iter().any(|e| e.event_type == event.event_type);
+                if still_relevant && !superseded {
+                    carried_over.push(event);
+                    continue;
+                }
+            }
+            summarized.push(event);
+        }
+
+        // Oldest first, so the first reading of a day is the one kept
+        for event in summarized.iter().rev() {
+            let date = if event.event_type == "sleep" {
+                sleep::sleep_periods(std::slice::from_ref(event), event.timestamp)
+                    .first()
+                    .map_or(event.timestamp.date_naive(), |period| period.sleep_day())
+            } else {
+                event.timestamp.date_naive()
+            };
+            self.summary(date).add(event);
+        }
+        for (date, car) in blunted_car {
+            self.summary(date).blunted_car = Some(car);
+        }
+
+        for event in carried_over {
+            self.store.insert(event);
+        }
+
+        while self.summaries.len() > MAX_SUMMARY_DAYS {
+            self.summaries.pop_first();
+        }
+    }
+
+    fn summary(&mut self, date: NaiveDate) -> &mut DailySummary {
+        self.summaries
+            .entry(date)
+            .or_insert_with(|| DailySummary { date, ..Default::default() })
+    }
+}
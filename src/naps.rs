@@ -0,0 +1,215 @@
+// naps.rs - Nap timing against the circadian dip and bedtime, caffeine naps and the best nap window
+use crate::sleep;
+use crate::{Chronotype, Event, EventStore};
+use chrono::{DateTime, Duration, Timelike, Utc};
+use serde::Serialize;
+
+/// The post-lunch dip falls this many hours after the preferred wake time
+const DIP_HOURS_AFTER_WAKE: f64 = 7.5;
+/// Naps ending this close to habitual bedtime eat into that night's sleep pressure
+const LATE_NAP_HOURS: f64 = 7.0;
+/// Sleep onset delay for an hour-long nap ending at bedtime
+const MAX_ONSET_DELAY_MINUTES: f64 = 45.0;
+/// Caffeine this long before a short nap takes effect just as the nap ends
+const CAFFEINE_NAP_LEAD_MINUTES: i64 = 30;
+/// Longest nap that still counts as a caffeine nap (it must end before caffeine acts)
+const CAFFEINE_NAP_MAX_MINUTES: f64 = 30.0;
+/// Length of the recommended nap: short enough to avoid deep sleep and inertia
+const RECOMMENDED_NAP_MINUTES: i64 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NapTiming {
+    // Well before the dip: little pressure to clear, mostly light sleep
+    Early,
+    // Around the post-lunch dip: easy to fall asleep, restorative
+    CircadianDip,
+    // Within 7h of bedtime: clears pressure the night's sleep needs
+    Late,
+}
+
+/// One nap, placed against the sleeper's circadian dip and habitual bedtime
+#[derive(Debug, Clone, Serialize)]
+pub struct NapAssessment {
+    pub event_id: String,
+    pub start: DateTime<Utc>,
+    pub duration_minutes: f64,
+    pub timing: NapTiming,
+    // Nap midpoint relative to the post-lunch dip (negative = before)
+    pub hours_from_dip: f64,
+    // From the end of the nap to habitual bedtime
+    pub hours_before_bedtime: f64,
+    // Adenosine cleared (negative), before decay
+    pub adenosine_clearance: f64,
+    // Predicted delay of that night's sleep onset
+    pub onset_delay_minutes: f64,
+    // Caffeine in the 30 min before a nap of 30 min or less
+    pub caffeine_nap: bool,
+    pub caffeine_event_id: Option<String>,
+}
+
+impl NapAssessment {
+    pub fn assess(event: &Event, store: &EventStore, chronotype: &Chronotype) -> Self {
+        let duration_minutes = event.properties.get("duration_minutes")
+            .and_then(|v| v.as_f64())
+            .unwrap_or(20.0);
+        let end = event.end_timestamp.unwrap_or(event.timestamp + Duration::minutes(duration_minutes as i64));
+        let midpoint = event.timestamp + (end - event.timestamp) / 2;
+
+        let hours_from_dip = sleep::wrap_hours(clock_hours(midpoint) - dip_hour(chronotype));
+        let hours_before_bedtime = (chronotype.preferred_bedtime_hour() - clock_hours(end)).rem_euclid(24.0);
+        let timing = if hours_before_bedtime < LATE_NAP_HOURS {
+            NapTiming::Late
+        } else if hours_from_dip < -2.0 {
+            NapTiming::Early
+        } else {
+            NapTiming::CircadianDip
+        };
+
+        // Short naps clear proportionally to their length; longer ones reach slow-wave sleep
+        let clearance = if duration_minutes <= 30.0 {
+            -0.25 * (duration_minutes / 30.0)
+        } else {
+            -0.4 * (duration_minutes / 90.0).min(1.0)
+        };
+        // Sleep pressure is lower before the dip and higher late in the day, so the same nap clears less or more
+        let efficiency = match timing {
+            NapTiming::Early => 0.7,
+            NapTiming::CircadianDip => 1.0,
+            NapTiming::Late => 1.2,
+        };
+        let onset_delay_minutes = if timing == NapTiming::Late {
+            MAX_ONSET_DELAY_MINUTES * (duration_minutes / 60.0).min(1.5) * (1.0 - hours_before_bedtime / LATE_NAP_HOURS)
+        } else {
+            0.0
+        };
+
+        let caffeine = (duration_minutes <= CAFFEINE_NAP_MAX_MINUTES)
+            .then(|| {
+                store
+                    .of_type("caffeine", event.timestamp - Duration::minutes(CAFFEINE_NAP_LEAD_MINUTES), event.timestamp)
+                    .next_back()
+            })
+            .flatten();
+
+        NapAssessment {
+            event_id: event.event_id.clone(),
+            start: event.timestamp,
+            duration_minutes,
+            timing,
+            hours_from_dip,
+            hours_before_bedtime,
+            adenosine_clearance: clearance * efficiency,
+            onset_delay_minutes,
+            caffeine_nap: caffeine.is_some(),
+            caffeine_event_id: caffeine.map(|e| e.event_id.clone()),
+        }
+    }
+
+    pub fn end(&self) -> DateTime<Utc> {
+        self.start + Duration::minutes(self.duration_minutes as i64)
+    }
+}
+
+/// Best time for a short nap today
+#[derive(Debug, Clone, Serialize)]
+pub struct NapWindow {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    // Combined homeostatic and circadian sleep propensity at the start (0-1)
+    pub sleep_propensity: f64,
+}
+
+/// Today's naps and when the next one would do most good
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NapReport {
+    // Naps since the last main sleep, oldest first
+    pub naps: Vec<NapAssessment>,
+    // Predicted delay of tonight's sleep onset from late naps
+    pub onset_delay_minutes: f64,
+    // None once it is too close to bedtime for a nap
+    pub best_window: Option<NapWindow>,
+}
+
+impl NapReport {
+    /// `adenosine` and `circadian_phase` are the current Process S and C scores
+    pub fn compute(
+        store: &EventStore,
+        time: DateTime<Utc>,
+        chronotype: &Chronotype,
+        adenosine: f64,
+        circadian_phase: f64,
+    ) -> Self {
+        let naps = naps_since_main_sleep(store, time, chronotype);
+        let onset_delay_minutes = naps.iter().fold(0.0, |total, nap| total + nap.onset_delay_minutes);
+
+        NapReport {
+            naps,
+            onset_delay_minutes,
+            best_window: best_nap_window(time, chronotype, adenosine, circadian_phase),
+        }
+    }
+}
+
+/// Naps since the end of the last main sleep (3h or more), looking back at most 24 hours
+pub(crate) fn naps_since_main_sleep(store: &EventStore, time: DateTime<Utc>, chronotype: &Chronotype) -> Vec<NapAssessment> {
+    let since = sleep::sleep_periods(store.until(time), time)
+        .iter()
+        .filter(|period| period.end <= time && period.asleep_hours >= 3.0)
+        .map(|period| period.end)
+        .max()
+        .unwrap_or(time - Duration::hours(24))
+        .max(time - Duration::hours(24));
+    store
+        .of_type("nap", since, time)
+        .map(|event| NapAssessment::assess(event, store, chronotype))
+        .collect()
+}
+
+/// Clock hour (0-24) of the post-lunch circadian dip
+pub fn dip_hour(chronotype: &Chronotype) -> f64 {
+    (chronotype.preferred_wake_hour() + DIP_HOURS_AFTER_WAKE).rem_euclid(24.0)
+}
+
+/// The 20-minute window from now until 7h before bedtime with the highest sleep
+/// propensity: adenosine projected forward (Process S, ~16h time constant) and the
+/// post-lunch dip (Process C), shifted by circadian phase like the sleep drive.
+fn best_nap_window(
+    time: DateTime<Utc>,
+    chronotype: &Chronotype,
+    adenosine: f64,
+    circadian_phase: f64,
+) -> Option<NapWindow> {
+    let wake = chronotype.preferred_wake_hour();
+    let cutoff = chronotype.preferred_bedtime_hour() - LATE_NAP_HOURS;
+    // Between the cutoff and the next habitual wake there is no nap left today
+    if (clock_hours(time) - wake).rem_euclid(24.0) > (cutoff - wake).rem_euclid(24.0) {
+        return None;
+    }
+    let hours_to_cutoff = (cutoff - clock_hours(time)).rem_euclid(24.0);
+    let dip = dip_hour(chronotype) - (circadian_phase - 0.5) * 4.0;
+    let latest_start = time + Duration::minutes((hours_to_cutoff * 60.0) as i64 - RECOMMENDED_NAP_MINUTES);
+
+    let mut best: Option<NapWindow> = None;
+    // Candidates on the quarter hour
+    let mut start = time.with_second(0)?.with_nanosecond(0)? + Duration::minutes((15 - time.minute() as i64 % 15) % 15);
+    while start <= latest_start {
+        let hours_ahead = (start - time).num_minutes() as f64 / 60.0;
+        let process_s = adenosine + (1.0 - adenosine) * (1.0 - (-hours_ahead / 16.0).exp());
+        let process_c = (-(sleep::wrap_hours(clock_hours(start) - dip) / 1.5).powi(2)).exp();
+        let sleep_propensity = 0.5 * process_s + 0.5 * process_c;
+        if best.as_ref().is_none_or(|b| sleep_propensity > b.sleep_propensity) {
+            best = Some(NapWindow {
+                start,
+                end: start + Duration::minutes(RECOMMENDED_NAP_MINUTES),
+                sleep_propensity,
+            });
+        }
+        start += Duration::minutes(15);
+    }
+    best
+}
+
+fn clock_hours(time: DateTime<Utc>) -> f64 {
+    time.hour() as f64 + time.minute() as f64 / 60.0
+}
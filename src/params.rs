@@ -0,0 +1,273 @@
+// params.rs - Tunable model coefficients, with access by name for analysis and fitting
+use crate::{ContextConfig, Primitive};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Coefficients of the estimator that are candidates for calibration.
+///
+/// Defaults reproduce the published model. Missing fields in a parameter
+/// file fall back to their defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelParameters {
+    // Decay half-lives (hours) of event impacts
+    pub glucose_half_life_hours: f64,
+    pub norepinephrine_half_life_hours: f64,
+    pub cortisol_half_life_hours: f64,
+    pub dopamine_acute_half_life_hours: f64,
+    pub dopamine_chronic_half_life_hours: f64,
+    pub serotonin_acute_half_life_hours: f64,
+    pub serotonin_chronic_half_life_hours: f64,
+    // Weight of the chronic component in dopamine/serotonin (acute gets the rest)
+    pub monoamine_chronic_weight: f64,
+
+    // Shifts added to the resting baseline of each primitive
+    pub dopamine_baseline_shift: f64,
+    pub serotonin_baseline_shift: f64,
+    pub norepinephrine_baseline_shift: f64,
+    pub cortisol_baseline_shift: f64,
+    pub glucose_baseline_shift: f64,
+
+    // Multipliers on every impact of an event type
+    pub sleep_impact_scale: f64,
+    pub light_impact_scale: f64,
+    pub meal_impact_scale: f64,
+    pub caffeine_impact_scale: f64,
+    pub exercise_impact_scale: f64,
+    pub nap_impact_scale: f64,
+    pub stress_impact_scale: f64,
+    pub social_impact_scale: f64,
+    pub screen_impact_scale: f64,
+    pub interruption_impact_scale: f64,
+
+    // Caffeine A2A receptor occupancy: dose / (dose + ED50)
+    pub caffeine_ed50_mg: f64,
+    // Adenosine reduction at full receptor occupancy
+    pub caffeine_adenosine_blockade: f64,
+
+    // Chronic sleep deprivation sequence adjustments (subtracted)
+    pub sleep_deprivation_dopamine_penalty: f64,
+    pub sleep_deprivation_serotonin_penalty: f64,
+    // Sleep regularity sequence adjustments
+    pub irregular_sleep_serotonin_penalty: f64,
+    pub social_jet_lag_cortisol_increase: f64,
+    pub circadian_drift_penalty: f64,
+    // Overreaching (high training load, falling HRV, poor sleep) sequence adjustments
+    pub overreaching_dopamine_penalty: f64,
+    pub overreaching_cortisol_increase: f64,
+    // Allostatic load (0-1) effects: share of the cortisol rhythm and CAR flattened
+    // at full load, extra stressor half-life, and serotonin penalty
+    pub allostatic_rhythm_flattening: f64,
+    pub allostatic_stress_recovery_slowing: f64,
+    pub allostatic_serotonin_penalty: f64,
+    // Norepinephrine lost at full sleep inertia (0-1), fading as the inertia clears
+    pub sleep_inertia_norepinephrine_penalty: f64,
+    // Extra adenosine reduction right after a caffeine nap, fading over 3 hours
+    pub caffeine_nap_adenosine_reduction: f64,
+
+    // Cross-primitive modifiers
+    pub adenosine_suppression_threshold: f64,
+    pub adenosine_dopamine_suppression: f64,
+    pub cortisol_suppression_threshold: f64,
+    pub cortisol_dopamine_suppression: f64,
+    pub cortisol_serotonin_suppression: f64,
+    pub reciprocal_inhibition: f64,
+
+    // Physiological validation: weight of a direct measurement in an override
+    pub override_measurement_weight: f64,
+    // Constraint thresholds
+    pub hrv_low_ms: f64,
+    pub hrv_high_ms: f64,
+    pub hr_elevated_bpm: f64,
+    pub hr_low_bpm: f64,
+    pub glucose_low_mg_dl: f64,
+    pub glucose_high_mg_dl: f64,
+}
+
+impl Default for ModelParameters {
+    fn default() -> Self {
+        ModelParameters {
+            glucose_half_life_hours: 2.0,
+            norepinephrine_half_life_hours: 4.0,
+            cortisol_half_life_hours: 12.0,
+            dopamine_acute_half_life_hours: 6.0,
+            dopamine_chronic_half_life_hours: 24.0,
+            serotonin_acute_half_life_hours: 8.0,
+            serotonin_chronic_half_life_hours: 36.0,
+            monoamine_chronic_weight: 0.7,
+
+            dopamine_baseline_shift: 0.0,
+            serotonin_baseline_shift: 0.0,
+            norepinephrine_baseline_shift: 0.0,
+            cortisol_baseline_shift: 0.0,
+            glucose_baseline_shift: 0.0,
+
+            sleep_impact_scale: 1.0,
+            light_impact_scale: 1.0,
+            meal_impact_scale: 1.0,
+            caffeine_impact_scale: 1.0,
+            exercise_impact_scale: 1.0,
+            nap_impact_scale: 1.0,
+            stress_impact_scale: 1.0,
+            social_impact_scale: 1.0,
+            screen_impact_scale: 1.0,
+            interruption_impact_scale: 1.0,
+
+            caffeine_ed50_mg: 65.0,
+            caffeine_adenosine_blockade: 0.5,
+
+            sleep_deprivation_dopamine_penalty: 0.2,
+            sleep_deprivation_serotonin_penalty: 0.15,
+            irregular_sleep_serotonin_penalty: 0.05,
+            social_jet_lag_cortisol_increase: 0.03,
+            circadian_drift_penalty: 0.05,
+            overreaching_dopamine_penalty: 0.1,
+            overreaching_cortisol_increase: 0.08,
+            allostatic_rhythm_flattening: 0.6,
+            allostatic_stress_recovery_slowing: 1.0,
+            allostatic_serotonin_penalty: 0.15,
+            sleep_inertia_norepinephrine_penalty: 0.25,
+            caffeine_nap_adenosine_reduction: 0.08,
+
+            adenosine_suppression_threshold: 0.5,
+            adenosine_dopamine_suppression: 1.2,
+            cortisol_suppression_threshold: 0.6,
+            cortisol_dopamine_suppression: 0.2,
+            cortisol_serotonin_suppression: 0.15,
+            reciprocal_inhibition: 0.15,
+
+            override_measurement_weight: 0.7,
+            hrv_low_ms: 30.0,
+            hrv_high_ms: 70.0,
+            hr_elevated_bpm: 80.0,
+            hr_low_bpm: 55.0,
+            glucose_low_mg_dl: 70.0,
+            glucose_high_mg_dl: 140.0,
+        }
+    }
+}
+
+impl ModelParameters {
+    /// Parse a parameter file (JSON object of name → value)
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let value: Value = serde_json::from_str(json).map_err(|e| format!("Invalid parameter file: {}", e))?;
+        if let Some(object) = value.as_object() {
+            let known = Self::default().values();
+            if let Some(unknown) = object.keys().find(|name| !known.contains_key(*name)) {
+                return Err(format!("Unknown parameter '{}'", unknown));
+            }
+        }
+        serde_json::from_value(value).map_err(|e| format!("Invalid parameter file: {}", e))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// All parameters by name
+    pub fn values(&self) -> BTreeMap<String, f64> {
+        match serde_json::to_value(self) {
+            Ok(Value::Object(map)) => map
+                .into_iter()
+                .filter_map(|(name, value)| value.as_f64().map(|v| (name, v)))
+                .collect(),
+            _ => BTreeMap::new(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.values().get(name).copied()
+    }
+
+    /// Copy with one parameter changed
+    pub fn with_value(&self, name: &str, value: f64) -> Result<Self, String> {
+        self.with_values(&BTreeMap::from([(name.to_string(), value)]))
+    }
+
+    /// Copy with several parameters changed
+    pub fn with_values(&self, changes: &BTreeMap<String, f64>) -> Result<Self, String> {
+        let mut values = self.values();
+        for (name, &value) in changes {
+            match values.get_mut(name) {
+                Some(slot) => *slot = value,
+                None => return Err(format!("Unknown parameter '{}'", name)),
+            }
+        }
+        serde_json::to_value(values)
+            .and_then(serde_json::from_value)
+            .map_err(|e| format!("Invalid parameter values: {}", e))
+    }
+
+    /// Typical magnitude of a parameter: its research default, or 0.1 for
+    /// parameters that default to zero (baseline shifts)
+    pub fn typical_scale(name: &str) -> f64 {
+        match Self::default().get(name) {
+            Some(value) if value.abs() > f64::EPSILON => value.abs(),
+            _ => 0.1,
+        }
+    }
+
+    /// Multiplier on the impacts of one event type
+    pub fn impact_scale(&self, event_type: &str) -> f64 {
+        match event_type {
+            "sleep" => self.sleep_impact_scale,
+            "light_exposure" | "light_series" => self.light_impact_scale,
+            "meal" => self.meal_impact_scale,
+            "caffeine" => self.caffeine_impact_scale,
+            "exercise" => self.exercise_impact_scale,
+            "nap" => self.nap_impact_scale,
+            "stress_event" => self.stress_impact_scale,
+            "social_interaction" => self.social_impact_scale,
+            "screen_time" => self.screen_impact_scale,
+            "interruption" => self.interruption_impact_scale,
+            _ => 1.0,
+        }
+    }
+
+    /// Shift added to a primitive's resting baseline
+    pub fn baseline_shift(&self, primitive: Primitive) -> f64 {
+        match primitive {
+            Primitive::Dopamine => self.dopamine_baseline_shift,
+            Primitive::Serotonin => self.serotonin_baseline_shift,
+            Primitive::Norepinephrine => self.norepinephrine_baseline_shift,
+            Primitive::Cortisol => self.cortisol_baseline_shift,
+            Primitive::Glucose => self.glucose_baseline_shift,
+            _ => 0.0,
+        }
+    }
+
+    /// Context window for the single-window path, with this parameter set's half-life
+    pub fn context_config(&self, primitive: Primitive) -> ContextConfig {
+        let mut config = ContextConfig::for_primitive(primitive);
+        match primitive {
+            Primitive::Glucose => config.decay_half_life_hours = self.glucose_half_life_hours,
+            Primitive::Norepinephrine => config.decay_half_life_hours = self.norepinephrine_half_life_hours,
+            Primitive::Cortisol => config.decay_half_life_hours = self.cortisol_half_life_hours,
+            Primitive::Dopamine => config.decay_half_life_hours = self.dopamine_chronic_half_life_hours,
+            Primitive::Serotonin => config.decay_half_life_hours = self.serotonin_chronic_half_life_hours,
+            _ => {}
+        }
+        config
+    }
+
+    pub fn acute_config(&self, primitive: Primitive) -> ContextConfig {
+        let mut config = ContextConfig::acute_for_monoamine(primitive);
+        match primitive {
+            Primitive::Dopamine => config.decay_half_life_hours = self.dopamine_acute_half_life_hours,
+            Primitive::Serotonin => config.decay_half_life_hours = self.serotonin_acute_half_life_hours,
+            _ => return self.context_config(primitive),
+        }
+        config
+    }
+
+    pub fn chronic_config(&self, primitive: Primitive) -> ContextConfig {
+        let mut config = ContextConfig::chronic_for_monoamine(primitive);
+        match primitive {
+            Primitive::Dopamine => config.decay_half_life_hours = self.dopamine_chronic_half_life_hours,
+            Primitive::Serotonin => config.decay_half_life_hours = self.serotonin_chronic_half_life_hours,
+            _ => return self.context_config(primitive),
+        }
+        config
+    }
+}
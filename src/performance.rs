@@ -0,0 +1,254 @@
+// performance.rs - Cognitive performance predicted from primitives and sleep history
+use crate::naps;
+use crate::sleep;
+use crate::{Chronotype, Event, EventStore, PrimitiveEstimator};
+use chrono::{DateTime, Duration, Timelike, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+/// Debt at which its contribution saturates (two weeks of 6h nights ≈ 28h)
+const MAX_SLEEP_DEBT_HOURS: f64 = 28.0;
+/// Norepinephrine level at the top of the Yerkes-Dodson curve
+const OPTIMAL_AROUSAL: f64 = 0.55;
+/// Length of the best and worst windows in the outlook
+const WINDOW_HOURS: usize = 2;
+
+/// Inputs to the performance model, all on their usual scales
+#[derive(Debug, Clone, Copy)]
+pub struct PerformanceInputs {
+    pub adenosine: f64,
+    pub circadian_phase: f64,
+    // Hours of local clock time (0-24)
+    pub hour_of_day: f64,
+    pub sleep_debt_hours: f64,
+    pub norepinephrine: f64,
+    pub glucose: f64,
+    // Grogginess after waking, 0-1 (see `SleepInertia`)
+    pub sleep_inertia: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PerformancePrediction {
+    // Lapses (RT > 500 ms) in a 10-minute psychomotor vigilance test
+    pub pvt_lapses: f64,
+    // Median PVT reaction time
+    pub reaction_time_ms: f64,
+    // Items held in working memory (digit span)
+    pub working_memory_items: f64,
+    // Karolinska Sleepiness Scale, 1 (extremely alert) to 9 (fighting sleep)
+    pub sleepiness_kss: f64,
+    // 0 (severely impaired) to 1 (unimpaired)
+    pub performance_index: f64,
+    pub sleep_debt_hours: f64,
+    // Largest contributor to impairment: "sleep pressure", "circadian timing", ...
+    pub limiting_factor: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HourlyPerformance {
+    pub timestamp: DateTime<Utc>,
+    // Inside a recorded, planned or projected sleep
+    pub asleep: bool,
+    pub prediction: PerformancePrediction,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PerformanceWindow {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub mean_performance_index: f64,
+}
+
+/// Hourly predictions ahead and the best and worst waking windows
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PerformanceOutlook {
+    pub hourly: Vec<HourlyPerformance>,
+    pub best_window: Option<PerformanceWindow>,
+    pub worst_window: Option<PerformanceWindow>,
+}
+
+/// Circadian alertness, -1 at the early-morning trough (~05:00) to 1 at the
+/// evening peak (~17:00). Phase shifts the curve like `circadian_sleep_pressure`.
+pub(crate) fn circadian_alertness(hour_of_day: f64, phase: f64) -> f64 {
+    let adjusted_hour = hour_of_day + (phase - 0.5) * 4.0;
+    (2.0 * PI * (adjusted_hour - 17.0) / 24.0).cos()
+}
+
+/// Inverted-U (Yerkes-Dodson): 1 at optimal arousal, falling off on both sides
+fn arousal_fit(norepinephrine: f64) -> f64 {
+    (-((norepinephrine - OPTIMAL_AROUSAL) / 0.25).powi(2)).exp()
+}
+
+/// Impairment from glucose outside the comfortable range (hypoglycaemia, post-meal spike)
+fn glucose_penalty(glucose: f64) -> f64 {
+    if glucose < 0.3 {
+        (0.3 - glucose) / 0.3
+    } else if glucose > 0.8 {
+        (glucose - 0.8) / 0.2 * 0.5
+    } else {
+        0.0
+    }
+}
+
+/// Predict performance from primitive levels.
+///
+/// Impairment combines homeostatic pressure (adenosine), the circadian trough
+/// (amplified when pressure is high, as in the two-process interaction), cumulative
+/// sleep debt, sleep inertia after waking, arousal away from the Yerkes-Dodson
+/// optimum and glucose extremes.
+/// Lapses grow with its square, so they stay near 1 when rested and reach ~10 after
+/// a night awake, in line with PVT data from sleep deprivation studies.
+pub fn predict_performance(inputs: &PerformanceInputs) -> PerformancePrediction {
+    let circadian_dip = (1.0 - circadian_alertness(inputs.hour_of_day, inputs.circadian_phase)) / 2.0;
+    let debt = (inputs.sleep_debt_hours / MAX_SLEEP_DEBT_HOURS).clamp(0.0, 1.0);
+    let arousal = arousal_fit(inputs.norepinephrine);
+
+    let factors = [
+        ("sleep pressure", 0.4 * inputs.adenosine),
+        ("circadian timing", 0.25 * circadian_dip * (0.5 + inputs.adenosine)),
+        ("sleep debt", 0.2 * debt),
+        ("sleep inertia", 0.35 * inputs.sleep_inertia),
+        ("arousal", 0.1 * (1.0 - arousal)),
+        ("glucose", 0.05 * glucose_penalty(inputs.glucose)),
+    ];
+    let impairment: f64 = factors.iter().map(|(_, v)| v).sum::<f64>().clamp(0.0, 1.0);
+    let limiting_factor = factors
+        .iter()
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(name, _)| name.to_string())
+        .unwrap_or_default();
+
+    // Arousal masks subjective sleepiness, so KSS tracks pressure more than impairment
+    let sleepiness = (0.55 * inputs.adenosine + 0.35 * circadian_dip + 0.1 * debt + 0.3 * inputs.sleep_inertia
+        - 0.15 * (inputs.norepinephrine - 0.5))
+        .clamp(0.0, 1.0);
+
+    PerformancePrediction {
+        pvt_lapses: 0.5 + 15.0 * impairment.powi(2),
+        reaction_time_ms: 250.0 + 150.0 * impairment.powf(1.5),
+        working_memory_items: 7.0 * (1.0 - 0.3 * impairment) * (0.85 + 0.15 * arousal),
+        sleepiness_kss: 1.0 + 8.0 * sleepiness,
+        performance_index: 1.0 - impairment,
+        sleep_debt_hours: inputs.sleep_debt_hours,
+        limiting_factor,
+    }
+}
+
+/// Shortfall against the sleeper's nightly need (`Chronotype::sleep_need_hours`)
+/// over the last 7 sleep days
+pub fn sleep_debt_hours(store: &EventStore, time: DateTime<Utc>, sleep_need_hours: f64) -> f64 {
+    sleep::sleep_days(store.until(time), time - Duration::days(8), time)
+        .iter()
+        .take(7)
+        .map(|day| (sleep_need_hours - day.total_asleep_hours()).max(0.0))
+        .sum()
+}
+
+/// Last night's main sleep repeated on the following nights that have no sleep
+/// recorded, so a forecast does not assume the user stays awake. Late naps
+/// taken since that sleep push tonight's onset back; the wake time stays.
+fn projected_sleep(store: &EventStore, chronotype: &Chronotype, start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<Event> {
+    let days = sleep::sleep_days(store.until(start), start - Duration::hours(48), start);
+    let Some(last) = days.first().map(|day| day.main_period()) else {
+        return Vec::new();
+    };
+    let Some(template) = store.events().iter().find(|e| e.event_id == last.primary_event_id) else {
+        return Vec::new();
+    };
+
+    let onset_delay_minutes = naps::naps_since_main_sleep(store, start, chronotype)
+        .iter()
+        .fold(0.0, |total, nap| total + nap.onset_delay_minutes);
+
+    let mut projected = Vec::new();
+    for day in 1..=2 {
+        let shift = Duration::days(day);
+        let onset_delay = if day == 1 { Duration::minutes(onset_delay_minutes.round() as i64) } else { Duration::zero() };
+        let (sleep_start, sleep_end) = (last.start + shift + onset_delay, last.end + shift);
+        let recorded = store
+            .of_type("sleep", sleep_start - Duration::hours(12), sleep_end)
+            .any(|e| e.end_timestamp.unwrap_or(e.timestamp) > sleep_start);
+        if sleep_start < start || sleep_start > end || recorded {
+            continue;
+        }
+        let mut event = template.clone();
+        event.event_id = format!("projected_sleep_{}", day);
+        event.timestamp = sleep_start;
+        event.end_timestamp = Some(sleep_end);
+        projected.push(event);
+    }
+    projected
+}
+
+/// Predict performance every hour for `hours` from `start`, assuming the most
+/// recent night's sleep repeats when no sleep is recorded or planned.
+/// Best and worst windows are the 2-hour waking stretches with the highest and
+/// lowest mean performance index.
+pub fn performance_outlook(
+    estimator: &PrimitiveEstimator,
+    store: &EventStore,
+    start: DateTime<Utc>,
+    hours: i64,
+) -> PerformanceOutlook {
+    let end = start + Duration::hours(hours);
+    let mut forecast = store.clone();
+    for event in projected_sleep(store, &estimator.chronotype(store, start), start, end) {
+        forecast.insert(event);
+    }
+    let sleeps: Vec<(DateTime<Utc>, DateTime<Utc>)> = sleep::sleep_periods(forecast.until(end), end)
+        .iter()
+        .map(|p| (p.start, p.end))
+        .collect();
+
+    let hourly: Vec<HourlyPerformance> = (0..hours)
+        .map(|h| {
+            let timestamp = start + Duration::hours(h);
+            HourlyPerformance {
+                timestamp,
+                asleep: sleeps.iter().any(|&(s, e)| s <= timestamp && timestamp < e),
+                prediction: estimator.run_passes(&forecast, timestamp).performance,
+            }
+        })
+        .collect();
+
+    let windows: Vec<PerformanceWindow> = hourly
+        .windows(WINDOW_HOURS)
+        .filter(|w| w.iter().all(|h| !h.asleep))
+        .map(|w| PerformanceWindow {
+            start: w[0].timestamp,
+            end: w[w.len() - 1].timestamp + Duration::hours(1),
+            mean_performance_index: w.iter().map(|h| h.prediction.performance_index).sum::<f64>() / w.len() as f64,
+        })
+        .collect();
+    let by_index = |a: &&PerformanceWindow, b: &&PerformanceWindow| {
+        a.mean_performance_index
+            .partial_cmp(&b.mean_performance_index)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    };
+    // Earliest window wins ties, so scan with reduce rather than max_by
+    let best_window = windows.iter().reduce(|best, w| if by_index(&w, &best).is_gt() { w } else { best }).cloned();
+    let worst_window = windows.iter().reduce(|worst, w| if by_index(&w, &worst).is_lt() { w } else { worst }).cloned();
+
+    PerformanceOutlook { hourly, best_window, worst_window }
+}
+
+/// Inputs for `predict_performance` at `time`, from final primitive scores
+pub(crate) fn inputs_at(
+    scores: &HashMap<String, f64>,
+    store: &EventStore,
+    time: DateTime<Utc>,
+    sleep_need_hours: f64,
+    sleep_inertia: f64,
+) -> PerformanceInputs {
+    let score = |key: &str| scores.get(key).copied().unwrap_or(0.5);
+    PerformanceInputs {
+        adenosine: score("adenosine"),
+        circadian_phase: score("circadian_phase"),
+        hour_of_day: time.hour() as f64 + time.minute() as f64 / 60.0,
+        sleep_debt_hours: sleep_debt_hours(store, time, sleep_need_hours),
+        norepinephrine: score("norepinephrine"),
+        glucose: score("glucose"),
+        sleep_inertia,
+    }
+}
@@ -42,10 +42,10 @@ fn create_rng(profile_id: &str, day: i64) -> StdRng {
 
 pub fn get_all_profiles() -> Vec<Profile> {
     // Generate schedule with same window as estimation system for consistency
-    // The estimation system uses 4 display days + 7 padding days = 11 total
+    // The estimation system uses 4 display days + 28 padding days = 32 total
     // We must use the same window to ensure event timestamps match
     let display_days = 4;   // Matches estimate_profile endpoint
-    let padding_days = 7;   // Matches max ContextConfig window (168h)
+    let padding_days = 28;  // Matches the chronic training-load window (28 days)
     let total_days = display_days + padding_days;
     
     // Round to start of current day to ensure consistent base_time across all calls
@@ -79,6 +79,12 @@ pub fn get_all_profiles() -> Vec<Profile> {
             description: "Intense exercise routine with optimized nutrition and recovery".to_string(),
             schedule: generate_athlete(base_time, total_days),
         },
+        Profile {
+            id: "overtrained_athlete".to_string(),
+            name: "Overtrained Athlete".to_string(),
+            description: "Training volume doubled for the last 10 days on short sleep, with HRV falling".to_string(),
+            schedule: generate_overtrained_athlete(base_time, total_days),
+        },
         Profile {
             id: "shift_worker".to_string(),
             name: "Shift Worker".to_string(),
@@ -101,6 +107,7 @@ pub fn generate_profile_events(profile_id: &str, days: i64) -> EventData {
         "sleep_deprived" => generate_sleep_deprived(base_time, days),
         "high_stress" => generate_high_stress(base_time, days),
         "athlete" => generate_athlete(base_time, days),
+        "overtrained_athlete" => generate_overtrained_athlete(base_time, days),
         "shift_worker" => generate_shift_worker(base_time, days),
         _ => generate_healthy_routine(base_time, days),
     };
@@ -123,7 +130,7 @@ fn generate_healthy_routine(base_time: DateTime<Utc>, days: i64) -> Vec<Event> {
         let sleep_start_base = day_start.with_hour(23).unwrap().with_minute(0).unwrap();
         let sleep_start = add_time_jitter(sleep_start_base, &mut rng, 15);
         let sleep_duration = add_value_jitter(8.0, &mut rng, 5.0);
-        let sleep_efficiency = add_value_jitter(0.88, &mut rng, 3.0).min(0.98).max(0.75);
+        let sleep_efficiency = add_value_jitter(0.88, &mut rng, 3.0).clamp(0.75, 0.98);
         
         events.push(Event {
             event_id: format!("evt_{}", event_counter),
@@ -286,7 +293,7 @@ fn generate_sleep_deprived(base_time: DateTime<Utc>, days: i64) -> Vec<Event> {
         let sleep_start_base = day_start.with_hour(1).unwrap().with_minute(0).unwrap();
         let sleep_start = add_time_jitter(sleep_start_base, &mut rng, 20);
         let sleep_duration = add_value_jitter(4.5, &mut rng, 10.0);
-        let sleep_efficiency = add_value_jitter(0.60, &mut rng, 8.0).min(0.70).max(0.45);
+        let sleep_efficiency = add_value_jitter(0.60, &mut rng, 8.0).clamp(0.45, 0.70);
         
         events.push(Event {
             event_id: format!("evt_{}", event_counter),
@@ -430,7 +437,7 @@ fn generate_high_stress(base_time: DateTime<Utc>, days: i64) -> Vec<Event> {
         let sleep_start_base = day_start.with_hour(0).unwrap().with_minute(0).unwrap();
         let sleep_start = add_time_jitter(sleep_start_base, &mut rng, 20);
         let sleep_duration = add_value_jitter(6.0, &mut rng, 10.0);
-        let sleep_efficiency = add_value_jitter(0.72, &mut rng, 8.0).min(0.85).max(0.55);
+        let sleep_efficiency = add_value_jitter(0.72, &mut rng, 8.0).clamp(0.55, 0.85);
         
         events.push(Event {
             event_id: format!("evt_{}", event_counter),
@@ -581,7 +588,7 @@ fn generate_athlete(base_time: DateTime<Utc>, days: i64) -> Vec<Event> {
         let sleep_start_base = day_start.with_hour(22).unwrap().with_minute(0).unwrap();
         let sleep_start = add_time_jitter(sleep_start_base, &mut rng, 15);
         let sleep_duration = add_value_jitter(8.5, &mut rng, 5.0);
-        let sleep_efficiency = add_value_jitter(0.92, &mut rng, 3.0).min(0.98).max(0.85);
+        let sleep_efficiency = add_value_jitter(0.92, &mut rng, 3.0).clamp(0.85, 0.98);
         
         events.push(Event {
             event_id: format!("evt_{}", event_counter),
@@ -731,6 +738,143 @@ fn generate_athlete(base_time: DateTime<Utc>, days: i64) -> Vec<Event> {
     events
 }
 
+fn generate_overtrained_athlete(base_time: DateTime<Utc>, days: i64) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut event_counter = 1;
+    let overload_start = days - 10;
+    
+    for day in 0..=days {
+        let day_start = base_time + Duration::days(day);
+        let mut rng = create_rng("overtrained_athlete", day);
+        // 0 before the overload block, rising to 1 on its last day
+        let strain = ((day - overload_start) as f64 / 10.0).clamp(0.0, 1.0);
+        let overloaded = day >= overload_start;
+        
+        // Sleep shortens and fragments as the block goes on: 8.5h → 6h
+        let sleep_start_base = day_start.with_hour(22).unwrap().with_minute(0).unwrap();
+        let sleep_start = add_time_jitter(sleep_start_base, &mut rng, 15);
+        let sleep_duration = add_value_jitter(8.5 - 2.5 * strain, &mut rng, 5.0);
+        let sleep_efficiency = add_value_jitter(0.92 - 0.12 * strain, &mut rng, 3.0).clamp(0.7, 0.98);
+        
+        events.push(Event {
+            event_id: format!("evt_{}", event_counter),
+            event_type: "sleep".to_string(),
+            timestamp: sleep_start,
+            end_timestamp: Some(sleep_start + Duration::minutes((sleep_duration * 60.0) as i64)),
+            properties: HashMap::from([
+                ("duration_hours".to_string(), json!(sleep_duration)),
+                ("quality".to_string(), json!(if strain > 0.3 { "fair" } else { "excellent" })),
+                ("sleep_efficiency".to_string(), json!(sleep_efficiency)),
+            ]),
+        });
+        event_counter += 1;
+        
+        let wake_time = sleep_start + Duration::minutes((sleep_duration * 60.0) as i64);
+        events.push(Event {
+            event_id: format!("evt_{}", event_counter),
+            event_type: "wake".to_string(),
+            timestamp: wake_time,
+            end_timestamp: None,
+            properties: HashMap::new(),
+        });
+        event_counter += 1;
+        
+        // Morning HRV falls from 85 ms towards 55 ms
+        events.push(Event {
+            event_id: format!("evt_{}", event_counter),
+            event_type: "health_hrv".to_string(),
+            timestamp: add_time_jitter(wake_time + Duration::minutes(15), &mut rng, 5),
+            end_timestamp: None,
+            properties: HashMap::from([
+                ("value".to_string(), json!(add_value_jitter(85.0 - 30.0 * strain, &mut rng, 6.0))),
+                ("unit".to_string(), json!("ms")),
+            ]),
+        });
+        event_counter += 1;
+        
+        events.push(Event {
+            event_id: format!("evt_{}", event_counter),
+            event_type: "meal".to_string(),
+            timestamp: add_time_jitter(wake_time + Duration::hours(1), &mut rng, 10),
+            end_timestamp: None,
+            properties: HashMap::from([
+                ("meal_type".to_string(), json!("breakfast")),
+                ("carb_grams".to_string(), json!(add_value_jitter(50.0, &mut rng, 10.0))),
+                ("protein_grams".to_string(), json!(add_value_jitter(40.0, &mut rng, 10.0))),
+                ("fat_grams".to_string(), json!(add_value_jitter(20.0, &mut rng, 10.0))),
+                ("glycemic_index".to_string(), json!("low")),
+            ]),
+        });
+        event_counter += 1;
+        
+        // Morning HIIT, longer and harder during the overload block
+        events.push(Event {
+            event_id: format!("evt_{}", event_counter),
+            event_type: "exercise".to_string(),
+            timestamp: add_time_jitter(wake_time + Duration::hours(2), &mut rng, 15),
+            end_timestamp: None,
+            properties: HashMap::from([
+                ("duration_minutes".to_string(), json!(add_value_jitter(if overloaded { 75.0 } else { 45.0 }, &mut rng, 10.0))),
+                ("intensity".to_string(), json!("high_intensity")),
+                ("type".to_string(), json!("hiit")),
+                ("rpe".to_string(), json!(if overloaded { 9.0 } else { 8.0 })),
+                ("vo2max_percentage".to_string(), json!(add_value_jitter(85.0, &mut rng, 6.0))),
+            ]),
+        });
+        event_counter += 1;
+        
+        events.push(Event {
+            event_id: format!("evt_{}", event_counter),
+            event_type: "meal".to_string(),
+            timestamp: add_time_jitter(wake_time + Duration::hours(5), &mut rng, 15),
+            end_timestamp: None,
+            properties: HashMap::from([
+                ("meal_type".to_string(), json!("lunch")),
+                ("carb_grams".to_string(), json!(add_value_jitter(60.0, &mut rng, 12.0))),
+                ("protein_grams".to_string(), json!(add_value_jitter(35.0, &mut rng, 12.0))),
+                ("fat_grams".to_string(), json!(add_value_jitter(15.0, &mut rng, 15.0))),
+                ("glycemic_index".to_string(), json!("medium")),
+            ]),
+        });
+        event_counter += 1;
+        
+        // Afternoon cardio every other day, then daily and longer in the block
+        if overloaded || day % 2 == 0 {
+            events.push(Event {
+                event_id: format!("evt_{}", event_counter),
+                event_type: "exercise".to_string(),
+                timestamp: add_time_jitter(wake_time + Duration::hours(8), &mut rng, 20),
+                end_timestamp: None,
+                properties: HashMap::from([
+                    ("duration_minutes".to_string(), json!(add_value_jitter(if overloaded { 90.0 } else { 60.0 }, &mut rng, 12.0))),
+                    ("intensity".to_string(), json!("vigorous")),
+                    ("type".to_string(), json!("cardio")),
+                    ("rpe".to_string(), json!(if overloaded { 7.0 } else { 5.0 })),
+                    ("vo2max_percentage".to_string(), json!(add_value_jitter(70.0, &mut rng, 8.0))),
+                ]),
+            });
+            event_counter += 1;
+        }
+        
+        events.push(Event {
+            event_id: format!("evt_{}", event_counter),
+            event_type: "meal".to_string(),
+            timestamp: add_time_jitter(wake_time + Duration::hours(11), &mut rng, 20),
+            end_timestamp: None,
+            properties: HashMap::from([
+                ("meal_type".to_string(), json!("dinner")),
+                ("carb_grams".to_string(), json!(add_value_jitter(55.0, &mut rng, 10.0))),
+                ("protein_grams".to_string(), json!(add_value_jitter(45.0, &mut rng, 10.0))),
+                ("fat_grams".to_string(), json!(add_value_jitter(20.0, &mut rng, 10.0))),
+                ("glycemic_index".to_string(), json!("low")),
+            ]),
+        });
+        event_counter += 1;
+    }
+    
+    events
+}
+
 fn generate_shift_worker(base_time: DateTime<Utc>, days: i64) -> Vec<Event> {
     let mut events = Vec::new();
     let mut event_counter = 1;
@@ -752,7 +896,7 @@ fn generate_shift_worker(base_time: DateTime<Utc>, days: i64) -> Vec<Event> {
         let sleep_start = add_time_jitter(sleep_start_base, &mut rng, 25);
         let sleep_duration = add_value_jitter(6.0, &mut rng, 12.0);
         let base_efficiency = if day % 3 == 0 { 0.75 } else { 0.62 };
-        let sleep_efficiency = add_value_jitter(base_efficiency, &mut rng, 10.0).min(0.85).max(0.45);
+        let sleep_efficiency = add_value_jitter(base_efficiency, &mut rng, 10.0).clamp(0.45, 0.85);
         
         events.push(Event {
             event_id: format!("evt_{}", event_counter),
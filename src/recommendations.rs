@@ -0,0 +1,624 @@
+// recommendations.rs - Ranked, context-aware recommendations with simulated effects
+use crate::{Event, EventStore, EstimationResult, PrimitiveEstimator};
+use chrono::{DateTime, Duration, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+
+/// Effects smaller than this are left out of `expected_effects`
+const MIN_REPORTED_EFFECT: f64 = 0.005;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    Sleep,
+    Nap,
+    Caffeine,
+    LightExposure,
+    AvoidScreens,
+    Exercise,
+    Meal,
+    Relaxation,
+    SocialConnection,
+    FocusedWork,
+}
+
+/// What the user can or won't do. Hours are local clock hours, like event timestamps.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserConstraints {
+    pub bedtime_hour: u32,
+    pub wake_hour: u32,
+    pub no_caffeine: bool,
+    // No caffeine within this many hours of bedtime
+    pub caffeine_cutoff_hours: f64,
+    pub max_daily_caffeine_mg: f64,
+    // Actions never to recommend
+    pub avoid: Vec<ActionKind>,
+}
+
+impl Default for UserConstraints {
+    fn default() -> Self {
+        UserConstraints {
+            bedtime_hour: 23,
+            wake_hour: 7,
+            no_caffeine: false,
+            caffeine_cutoff_hours: 8.0,
+            max_daily_caffeine_mg: 400.0,
+            avoid: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Recommendation {
+    pub action: ActionKind,
+    pub title: String,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    // Change in each primitive's final score if the action is taken (simulated)
+    pub expected_effects: BTreeMap<String, f64>,
+    pub rationale: String,
+    // 0-1 urgency used for ranking
+    pub priority: f64,
+}
+
+/// An action the state called for but that was ruled out, and why
+#[derive(Debug, Clone, Serialize)]
+pub struct SuppressedRecommendation {
+    pub action: ActionKind,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecommendationReport {
+    pub timestamp: DateTime<Utc>,
+    // Highest priority first
+    pub recommendations: Vec<Recommendation>,
+    pub suppressed: Vec<SuppressedRecommendation>,
+}
+
+/// How the expected effect of an action is simulated
+enum Simulation {
+    // Not an event the model knows (relaxation, focused work)
+    None,
+    // Add the event and compare at the given time
+    Add(Event, DateTime<Utc>),
+    // Compare against the event happening (the effect of not doing it)
+    Avoid(Event, DateTime<Utc>),
+    // Effect of a planned event already in the history (by id)
+    Planned(String, DateTime<Utc>),
+}
+
+struct Candidate {
+    action: ActionKind,
+    title: String,
+    window: (DateTime<Utc>, DateTime<Utc>),
+    simulation: Simulation,
+    rationale: String,
+    priority: f64,
+}
+
+/// Everything the rules look at, gathered once
+struct Situation<'a> {
+    result: &'a EstimationResult,
+    time: DateTime<Utc>,
+    hour: f64,
+    bedtime: DateTime<Utc>,
+    hours_until_bedtime: f64,
+    in_sleep_window: bool,
+    caffeine_today_mg: f64,
+    hours_since_caffeine: Option<f64>,
+    hours_since_meal: Option<f64>,
+    hours_since_exercise: Option<f64>,
+    hours_since_stress: Option<f64>,
+    recent_screen_time: bool,
+    sleep_deprived: bool,
+    planned: Vec<&'a Event>,
+}
+
+impl Situation<'_> {
+    fn score(&self, primitive: &str) -> f64 {
+        self.result.primitives.get(primitive).map(|s| s.final_score()).unwrap_or(0.5)
+    }
+
+    /// "adenosine 0.62 (largest influence: caffeine 3h ago, -0.12)"
+    fn explain(&self, primitive: &str) -> String {
+        let score = self.score(primitive);
+        match self.result.primitives.get(primitive).and_then(|s| s.contributors.first()) {
+            Some(top) => format!(
+                "{} {:.2} (largest influence: {} {:.0}h ago, {:+.2})",
+                primitive, score, top.event_type, top.hours_ago, top.decayed_impact
+            ),
+            None => format!("{} {:.2}", primitive, score),
+        }
+    }
+
+    fn planned(&self, event_type: &str, within: Duration) -> Option<&Event> {
+        self.planned.iter().copied().find(|e| e.event_type == event_type && e.timestamp <= self.time + within)
+    }
+}
+
+fn hours_between(from: DateTime<Utc>, to: DateTime<Utc>) -> f64 {
+    (to - from).num_minutes() as f64 / 60.0
+}
+
+/// The next occurrence of a clock hour at or after `time`
+fn next_hour(time: DateTime<Utc>, hour: u32) -> DateTime<Utc> {
+    let midnight = time - Duration::seconds(time.num_seconds_from_midnight() as i64);
+    let candidate = midnight + Duration::hours(hour as i64);
+    if candidate >= time { candidate } else { candidate + Duration::days(1) }
+}
+
+fn hypothetical(event_type: &str, timestamp: DateTime<Utc>, end: Option<DateTime<Utc>>, properties: serde_json::Value) -> Event {
+    let properties: HashMap<String, serde_json::Value> = properties
+        .as_object()
+        .map(|map| map.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+        .unwrap_or_default();
+    Event {
+        event_id: format!("recommended_{}", event_type),
+        event_type: event_type.to_string(),
+        timestamp,
+        end_timestamp: end,
+        properties,
+    }
+}
+
+/// Recommend actions for the state estimated from `store` at `time`.
+///
+/// Each rule fires on a need in the current state (e.g. high adenosine), then
+/// checks the action against the time of day, bedtime, recent and planned
+/// events and the user's constraints; actions that would contradict the
+/// state are reported in `suppressed` instead. Expected effects come from
+/// running the estimator with and without a representative event.
+pub fn recommend(
+    estimator: &PrimitiveEstimator,
+    store: &EventStore,
+    time: DateTime<Utc>,
+    constraints: &UserConstraints,
+) -> RecommendationReport {
+    // Nothing to recommend while a recorded sleep is in progress
+    let asleep = store.of_type("sleep", time - Duration::hours(16), time)
+        .any(|e| e.end_timestamp.is_some_and(|end| end > time));
+    if asleep {
+        return RecommendationReport { timestamp: time, recommendations: Vec::new(), suppressed: Vec::new() };
+    }
+
+    let result = &estimator.run_passes(store, time);
+    let hours_since = |event_type: &str, lookback: i64| {
+        store.of_type(event_type, time - Duration::hours(lookback), time)
+            .next_back()
+            .map(|e| hours_between(e.end_timestamp.unwrap_or(e.timestamp).min(time), time))
+    };
+
+    let planned: Vec<&Event> = store.range(time, time + Duration::hours(24))
+        .iter()
+        .filter(|e| e.timestamp > time)
+        .collect();
+    let hour = time.hour() as f64 + time.minute() as f64 / 60.0;
+    let in_sleep_window = {
+        let (bed, wake) = (constraints.bedtime_hour as f64, constraints.wake_hour as f64);
+        if bed > wake { hour >= bed || hour < wake } else { hour >= bed && hour < wake }
+    };
+    // Inside the sleep window it is bedtime now; otherwise a planned night's
+    // sleep sets bedtime, falling back to the user's usual bedtime
+    let bedtime = if in_sleep_window {
+        time
+    } else {
+        planned.iter()
+            .find(|e| e.event_type == "sleep")
+            .map(|e| e.timestamp)
+            .unwrap_or_else(|| next_hour(time, constraints.bedtime_hour))
+    };
+
+    let situation = Situation {
+        result,
+        time,
+        hour,
+        bedtime,
+        hours_until_bedtime: hours_between(time, bedtime),
+        in_sleep_window,
+        caffeine_today_mg: store.of_type("caffeine", time - Duration::hours(24), time)
+            .filter_map(|e| e.properties.get("dose_mg").and_then(|v| v.as_f64()))
+            .sum(),
+        hours_since_caffeine: hours_since("caffeine", 24),
+        hours_since_meal: hours_since("meal", 24),
+        hours_since_exercise: hours_since("exercise", 24),
+        hours_since_stress: hours_since("stress_event", 24),
+        recent_screen_time: hours_since("screen_time", 2).is_some(),
+        sleep_deprived: result.detected_sequences.iter().any(|s| s.pattern_name == "chronic_sleep_deprivation"),
+        planned,
+    };
+
+    let mut candidates = Vec::new();
+    let mut suppressed = Vec::new();
+    let mut suppress = |action: ActionKind, reason: String| suppressed.push(SuppressedRecommendation { action, reason });
+
+    sleep_rule(&situation, constraints, &mut candidates);
+    nap_rule(&situation, &mut candidates, &mut suppress);
+    caffeine_rule(&situation, constraints, &mut candidates, &mut suppress);
+    light_rule(&situation, constraints, &mut candidates, &mut suppress);
+    screens_rule(&situation, &mut candidates);
+    exercise_rule(&situation, &mut candidates, &mut suppress);
+    meal_rule(&situation, &mut candidates, &mut suppress);
+    relaxation_rule(&situation, &mut candidates);
+    social_rule(&situation, &mut candidates, &mut suppress);
+    focus_rule(&situation, &mut candidates, &mut suppress);
+
+    let (kept, excluded): (Vec<Candidate>, Vec<Candidate>) = candidates
+        .into_iter()
+        .partition(|c| !constraints.avoid.contains(&c.action));
+    for candidate in excluded {
+        suppress(candidate.action, "excluded by the user's constraints".to_string());
+    }
+
+    let mut recommendations: Vec<Recommendation> = kept.into_iter()
+        .map(|candidate| Recommendation {
+            action: candidate.action,
+            title: candidate.title,
+            window_start: candidate.window.0,
+            window_end: candidate.window.1,
+            expected_effects: simulate(estimator, store, &candidate.simulation),
+            rationale: candidate.rationale,
+            priority: candidate.priority.clamp(0.0, 1.0),
+        })
+        .collect();
+
+    recommendations.sort_by(|a, b| b.priority.partial_cmp(&a.priority).unwrap_or(std::cmp::Ordering::Equal));
+
+    RecommendationReport {
+        timestamp: time,
+        recommendations,
+        suppressed,
+    }
+}
+
+/// Final scores with the simulated change minus final scores without it
+fn simulate(estimator: &PrimitiveEstimator, store: &EventStore, simulation: &Simulation) -> BTreeMap<String, f64> {
+    let inserted = |event: &Event| {
+        let mut with = store.clone();
+        with.insert(event.clone());
+        with
+    };
+    let (with, without, at) = match simulation {
+        Simulation::None => return BTreeMap::new(),
+        Simulation::Add(event, at) => (inserted(event), store.clone(), *at),
+        Simulation::Avoid(event, at) => (store.clone(), inserted(event), *at),
+        Simulation::Planned(event_id, at) => {
+            let kept: Vec<Event> = store.events().iter().filter(|e| &e.event_id != event_id).cloned().collect();
+            (store.clone(), EventStore::new(&kept), *at)
+        }
+    };
+    let after = estimator.run_passes(&with, at);
+    let before = estimator.run_passes(&without, at);
+
+    after.primitives
+        .iter()
+        .filter_map(|(key, state)| {
+            let baseline = before.primitives.get(key)?.final_score();
+            let effect = state.final_score() - baseline;
+            (effect.abs() >= MIN_REPORTED_EFFECT).then(|| (key.clone(), effect))
+        })
+        .collect()
+}
+
+fn sleep_rule(s: &Situation, constraints: &UserConstraints, candidates: &mut Vec<Candidate>) {
+    if !s.in_sleep_window && s.hours_until_bedtime > 2.0 {
+        return;
+    }
+    let planned = s.planned("sleep", Duration::hours(24));
+    let start = s.bedtime.max(s.time);
+    let mut rationale = format!("Sleep drive is {:.2} and bedtime is ", s.result.sleep_drive);
+    rationale.push_str(&if s.in_sleep_window { "now".to_string() } else { format!("in {:.1}h", s.hours_until_bedtime) });
+    if s.sleep_deprived {
+        rationale.push_str("; recent nights have been short, so tonight's sleep matters most");
+    }
+    let (title, simulation) = match planned {
+        Some(event) => (
+            format!("Keep your planned bedtime of {}", event.timestamp.format("%H:%M")),
+            Simulation::Planned(
+                event.event_id.clone(),
+                event.end_timestamp.unwrap_or(event.timestamp + Duration::hours(8)) + Duration::hours(1),
+            ),
+        ),
+        None => {
+            let wake = next_hour(start + Duration::hours(4), constraints.wake_hour);
+            let sleep = hypothetical("sleep", start, Some(wake), json!({ "duration_hours": hours_between(start, wake), "quality": "good" }));
+            (format!("Sleep by {}", start.format("%H:%M")), Simulation::Add(sleep, wake + Duration::hours(1)))
+        }
+    };
+    candidates.push(Candidate {
+        action: ActionKind::Sleep,
+        title,
+        window: (start, start + Duration::minutes(30)),
+        simulation,
+        rationale,
+        priority: 0.6 + 0.4 * s.result.sleep_drive + if s.sleep_deprived { 0.2 } else { 0.0 },
+    });
+}
+
+fn nap_rule(s: &Situation, candidates: &mut Vec<Candidate>, suppress: &mut impl FnMut(ActionKind, String)) {
+    let adenosine = s.score("adenosine");
+    if adenosine < 0.55 && s.result.sleep_drive < 0.6 {
+        return;
+    }
+    if s.in_sleep_window {
+        return;
+    }
+    // The nap model leaves no window within 7h of bedtime
+    let Some(window) = &s.result.naps.best_window else {
+        suppress(ActionKind::Nap, format!("A nap {:.1}h before bedtime would cut into tonight's sleep", s.hours_until_bedtime));
+        return;
+    };
+    candidates.push(Candidate {
+        action: ActionKind::Nap,
+        title: "Take a 20-minute nap".to_string(),
+        window: (window.start, window.end),
+        simulation: Simulation::Add(
+            hypothetical("nap", window.start, Some(window.end), json!({ "duration_minutes": 20, "quality": "good" })),
+            window.start + Duration::hours(1),
+        ),
+        rationale: format!(
+            "{}; a short nap clears adenosine without grogginess, best at {} when sleep pressure and the circadian dip combine",
+            s.explain("adenosine"),
+            window.start.format("%H:%M")
+        ),
+        priority: adenosine,
+    });
+}
+
+fn caffeine_rule(
+    s: &Situation,
+    constraints: &UserConstraints,
+    candidates: &mut Vec<Candidate>,
+    suppress: &mut impl FnMut(ActionKind, String),
+) {
+    let adenosine = s.score("adenosine");
+    if adenosine < 0.5 {
+        return;
+    }
+    let reason = if constraints.no_caffeine {
+        Some("the user avoids caffeine".to_string())
+    } else if s.in_sleep_window {
+        Some("high adenosine in the sleep window calls for sleep".to_string())
+    } else if s.hours_until_bedtime < constraints.caffeine_cutoff_hours {
+        Some(format!(
+            "high adenosine {:.1}h before bedtime calls for sleep, and caffeine would still be active then",
+            s.hours_until_bedtime
+        ))
+    } else if s.hours_since_caffeine.is_some_and(|h| h < 3.0) {
+        Some("caffeine was taken less than 3 hours ago".to_string())
+    } else if s.caffeine_today_mg + 100.0 > constraints.max_daily_caffeine_mg {
+        Some(format!("{:.0} mg already today (limit {:.0} mg)", s.caffeine_today_mg, constraints.max_daily_caffeine_mg))
+    } else if s.score("cortisol") > 0.65 {
+        Some(format!("cortisol is {:.2}; caffeine would amplify the stress response", s.score("cortisol")))
+    } else if s.planned("caffeine", Duration::hours(3)).is_some() {
+        Some("caffeine is already planned".to_string())
+    } else {
+        None
+    };
+    if let Some(reason) = reason {
+        suppress(ActionKind::Caffeine, reason);
+        return;
+    }
+
+    let cutoff = s.bedtime - Duration::minutes((constraints.caffeine_cutoff_hours * 60.0) as i64);
+    let mut rationale = format!("{}; caffeine blocks adenosine receptors for several hours", s.explain("adenosine"));
+    if s.sleep_deprived {
+        rationale.push_str(". It masks sleep debt rather than repaying it");
+    }
+    candidates.push(Candidate {
+        action: ActionKind::Caffeine,
+        title: "Have about 100 mg of caffeine".to_string(),
+        window: (s.time, cutoff.min(s.time + Duration::hours(1))),
+        simulation: Simulation::Add(
+            hypothetical("caffeine", s.time, None, json!({ "dose_mg": 100, "form": "coffee" })),
+            s.time + Duration::hours(1),
+        ),
+        rationale,
+        priority: adenosine * if s.sleep_deprived { 0.5 } else { 0.75 },
+    });
+}
+
+fn light_rule(
+    s: &Situation,
+    constraints: &UserConstraints,
+    candidates: &mut Vec<Candidate>,
+    suppress: &mut impl FnMut(ActionKind, String),
+) {
+    let wake = constraints.wake_hour as f64;
+    let morning = s.hour >= wake && s.hour < wake + 3.0;
+    let circadian = s.score("circadian_phase");
+    if !morning && circadian >= 0.5 {
+        return;
+    }
+    if s.in_sleep_window {
+        return;
+    }
+    if s.hours_until_bedtime < 4.0 {
+        suppress(ActionKind::LightExposure, "bright light this close to bedtime would delay the body clock".to_string());
+        return;
+    }
+    let daylight_today = s.result.light.daylight_episodes.iter()
+        .filter(|episode| episode.start.date_naive() == s.time.date_naive())
+        .fold(0.0, |total, episode| total + episode.minutes);
+    if daylight_today >= 30.0 {
+        suppress(ActionKind::LightExposure, format!("already {:.0} min of daylight today", daylight_today));
+        return;
+    }
+    candidates.push(Candidate {
+        action: ActionKind::LightExposure,
+        title: "Get 30 minutes of outdoor light".to_string(),
+        window: (s.time, s.time + Duration::hours(1)),
+        simulation: Simulation::Add(
+            hypothetical("light_exposure", s.time, None, json!({ "intensity_lux": 8000, "duration_minutes": 30, "wavelength": "outdoor" })),
+            s.time + Duration::hours(1),
+        ),
+        rationale: if morning {
+            format!("Morning light anchors the circadian rhythm ({})", s.explain("circadian_phase"))
+        } else {
+            format!("Circadian alignment is low ({})", s.explain("circadian_phase"))
+        },
+        priority: 0.4 + (0.5 - circadian).max(0.0),
+    });
+}
+
+fn screens_rule(s: &Situation, candidates: &mut Vec<Candidate>) {
+    if s.in_sleep_window || s.hours_until_bedtime > 3.0 {
+        return;
+    }
+    candidates.push(Candidate {
+        action: ActionKind::AvoidScreens,
+        title: "Put screens away and dim the lights".to_string(),
+        window: (s.time, s.bedtime),
+        simulation: Simulation::Avoid(
+            hypothetical("screen_time", s.time, None, json!({
+                "duration_minutes": 60, "device": "phone", "blue_light_intensity": "high",
+                "hours_before_sleep": s.hours_until_bedtime,
+            })),
+            s.bedtime.max(s.time + Duration::hours(1)),
+        ),
+        rationale: format!(
+            "Bedtime is in {:.1}h; evening blue light suppresses melatonin and delays sleep onset",
+            s.hours_until_bedtime
+        ),
+        priority: 0.5 + if s.recent_screen_time { 0.2 } else { 0.0 },
+    });
+}
+
+fn exercise_rule(s: &Situation, candidates: &mut Vec<Candidate>, suppress: &mut impl FnMut(ActionKind, String)) {
+    let dopamine = s.score("dopamine");
+    if dopamine >= 0.55 || s.in_sleep_window {
+        return;
+    }
+    if s.hours_since_exercise.is_some_and(|h| h < 12.0) || s.planned("exercise", Duration::hours(12)).is_some() {
+        return;
+    }
+    if s.hours_until_bedtime < 3.0 {
+        suppress(ActionKind::Exercise, "exercise within 3 hours of bedtime raises arousal when it should fall".to_string());
+        return;
+    }
+    if s.score("adenosine") > 0.8 {
+        suppress(ActionKind::Exercise, format!("adenosine is {:.2}; rest will help more than training", s.score("adenosine")));
+        return;
+    }
+    // Gentle movement when tired or stressed, moderate cardio otherwise
+    let gentle = s.score("adenosine") > 0.6 || s.score("cortisol") > 0.65;
+    let intensity = if gentle { "light" } else { "moderate" };
+    let start = s.time;
+    candidates.push(Candidate {
+        action: ActionKind::Exercise,
+        title: if gentle { "Take a 30-minute walk".to_string() } else { "Do 30 minutes of moderate cardio".to_string() },
+        window: (start, (s.bedtime - Duration::hours(3)).min(start + Duration::hours(3))),
+        simulation: Simulation::Add(
+            hypothetical("exercise", start, Some(start + Duration::minutes(30)), json!({ "duration_minutes": 30, "intensity": intensity, "type": "cardio" })),
+            start + Duration::minutes(90),
+        ),
+        rationale: format!("{}; exercise raises dopamine for hours afterwards", s.explain("dopamine")),
+        priority: 0.3 + (0.55 - dopamine),
+    });
+}
+
+fn meal_rule(s: &Situation, candidates: &mut Vec<Candidate>, suppress: &mut impl FnMut(ActionKind, String)) {
+    let glucose = s.score("glucose");
+    let fasted = s.hours_since_meal.is_none_or(|h| h > 4.5);
+    if !fasted || glucose >= 0.45 || s.in_sleep_window {
+        return;
+    }
+    if s.planned("meal", Duration::hours(2)).is_some() {
+        return;
+    }
+    if s.hours_until_bedtime < 2.0 {
+        suppress(ActionKind::Meal, "a full meal right before bed disrupts sleep; a light snack is enough".to_string());
+        return;
+    }
+    candidates.push(Candidate {
+        action: ActionKind::Meal,
+        title: "Eat a balanced meal with protein".to_string(),
+        window: (s.time, s.time + Duration::hours(1)),
+        simulation: Simulation::Add(
+            hypothetical("meal", s.time, None, json!({
+                "meal_type": "lunch", "carb_grams": 50, "protein_grams": 30, "fat_grams": 15,
+                "glycemic_index": "medium", "tyrosine_mg": 900, "tryptophan_mg": 250,
+            })),
+            s.time + Duration::hours(1),
+        ),
+        rationale: match s.hours_since_meal {
+            Some(hours) => format!("Glucose is {:.2} and the last meal was {:.0}h ago", glucose, hours),
+            None => format!("Glucose is {:.2} and there is no meal in the last day", glucose),
+        },
+        priority: 0.4 + (0.45 - glucose),
+    });
+}
+
+fn relaxation_rule(s: &Situation, candidates: &mut Vec<Candidate>) {
+    let cortisol = s.score("cortisol");
+    let upcoming_stress = s.planned("stress_event", Duration::hours(4));
+    // Morning cortisol is circadian, so elevated cortisol alone needs a recent stressor or to be high
+    let recent_stress = s.hours_since_stress.is_some_and(|h| h < 4.0);
+    let stressed = cortisol > 0.75 || (cortisol > 0.55 && recent_stress);
+    if !stressed && upcoming_stress.is_none() {
+        return;
+    }
+    let (end, rationale) = match upcoming_stress {
+        Some(event) => (
+            event.timestamp,
+            format!("A stressful event is planned at {}; lowering arousal beforehand blunts the cortisol response", event.timestamp.format("%H:%M")),
+        ),
+        None => (s.time + Duration::minutes(30), format!("{}; slow breathing lowers sympathetic arousal", s.explain("cortisol"))),
+    };
+    candidates.push(Candidate {
+        action: ActionKind::Relaxation,
+        title: "10 minutes of slow breathing or a quiet walk".to_string(),
+        window: (s.time, end),
+        simulation: Simulation::None,
+        rationale,
+        priority: cortisol.max(if upcoming_stress.is_some() { 0.6 } else { 0.0 }),
+    });
+}
+
+fn social_rule(s: &Situation, candidates: &mut Vec<Candidate>, suppress: &mut impl FnMut(ActionKind, String)) {
+    let serotonin = s.score("serotonin");
+    if serotonin >= 0.45 || s.in_sleep_window {
+        return;
+    }
+    if s.hours_until_bedtime < 2.0 {
+        suppress(ActionKind::SocialConnection, "too close to bedtime; plan time with others for tomorrow".to_string());
+        return;
+    }
+    candidates.push(Candidate {
+        action: ActionKind::SocialConnection,
+        title: "Spend time with someone you like".to_string(),
+        window: (s.time, (s.time + Duration::hours(3)).min(s.bedtime)),
+        simulation: Simulation::Add(
+            hypothetical("social_interaction", s.time, Some(s.time + Duration::hours(1)), json!({
+                "duration_minutes": 60, "quality": "positive", "interaction_type": "reciprocal",
+            })),
+            s.time + Duration::minutes(90),
+        ),
+        rationale: format!("{}; positive social contact supports serotonin", s.explain("serotonin")),
+        priority: 0.3 + (0.45 - serotonin),
+    });
+}
+
+fn focus_rule(s: &Situation, candidates: &mut Vec<Candidate>, suppress: &mut impl FnMut(ActionKind, String)) {
+    let dopamine = s.score("dopamine");
+    if dopamine < 0.6 {
+        return;
+    }
+    if s.in_sleep_window {
+        suppress(ActionKind::FocusedWork, "drive is high, but this is the sleep window".to_string());
+        return;
+    }
+    if s.score("adenosine") >= 0.5 {
+        return;
+    }
+    candidates.push(Candidate {
+        action: ActionKind::FocusedWork,
+        title: "Do your most demanding task now".to_string(),
+        window: (s.time, (s.time + Duration::minutes(90)).min(s.bedtime)),
+        simulation: Simulation::None,
+        rationale: format!("{} with low sleep pressure ({})", s.explain("dopamine"), s.explain("adenosine")),
+        priority: dopamine * 0.8,
+    });
+}
@@ -0,0 +1,283 @@
+// registry.rs - Primitive definitions (baseline, context window, dependencies) and computation order
+use crate::{ContextConfig, Primitive, PrimitiveState};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Score of a derived primitive from the base scores of its dependencies
+pub type DerivedScore = Arc<dyn Fn(&BTreeMap<String, f64>) -> f64 + Send + Sync>;
+
+/// How a primitive's base score is computed in the first pass
+#[derive(Clone)]
+pub enum Computation {
+    // One of the model's own primitives, with its dedicated computation
+    Builtin(Primitive),
+    // Baseline plus each event's impact by event type, decayed over the context window
+    EventImpacts(BTreeMap<String, f64>),
+    // A function of the base scores of the primitive's dependencies
+    Derived(DerivedScore),
+}
+
+impl fmt::Debug for Computation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Computation::Builtin(primitive) => write!(f, "Builtin({})", primitive.as_str()),
+            Computation::EventImpacts(impacts) => f.debug_tuple("EventImpacts").field(impacts).finish(),
+            Computation::Derived(_) => write!(f, "Derived"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PrimitiveDefinition {
+    pub name: String,
+    pub baseline: f64,
+    pub context: ContextConfig,
+    // Primitives whose base scores must be computed first
+    pub dependencies: Vec<String>,
+    pub computation: Computation,
+}
+
+impl PrimitiveDefinition {
+    fn builtin(primitive: Primitive, baseline: f64, dependencies: &[Primitive]) -> Self {
+        PrimitiveDefinition {
+            name: primitive.as_str().to_string(),
+            baseline,
+            context: ContextConfig::for_primitive(primitive),
+            dependencies: dependencies.iter().map(|p| p.as_str().to_string()).collect(),
+            computation: Computation::Builtin(primitive),
+        }
+    }
+
+    /// A primitive driven by events: add impacts with `with_impact`
+    pub fn event_driven(name: &str, baseline: f64, window_hours: i64, decay_half_life_hours: f64) -> Self {
+        PrimitiveDefinition {
+            name: name.to_string(),
+            baseline,
+            context: ContextConfig { window_hours, decay_half_life_hours },
+            dependencies: Vec::new(),
+            computation: Computation::EventImpacts(BTreeMap::new()),
+        }
+    }
+
+    /// A primitive computed from other primitives' base scores, e.g. an
+    /// inflammation index from cortisol and adenosine
+    pub fn derived(
+        name: &str,
+        dependencies: &[&str],
+        score: impl Fn(&BTreeMap<String, f64>) -> f64 + Send + Sync + 'static,
+    ) -> Self {
+        PrimitiveDefinition {
+            name: name.to_string(),
+            baseline: 0.5,
+            context: ContextConfig { window_hours: 0, decay_half_life_hours: 1.0 },
+            dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+            computation: Computation::Derived(Arc::new(score)),
+        }
+    }
+
+    /// Impact of each event of `event_type` at the moment it happens (event-driven only)
+    pub fn with_impact(mut self, event_type: &str, impact: f64) -> Self {
+        if let Computation::EventImpacts(impacts) = &mut self.computation {
+            impacts.insert(event_type.to_string(), impact);
+        }
+        self
+    }
+
+    pub fn with_dependency(mut self, name: &str) -> Self {
+        self.dependencies.push(name.to_string());
+        self
+    }
+
+    /// Parse event-driven definitions (JSON object with a `primitives` array)
+    pub fn list_from_json(json: &str) -> Result<Vec<Self>, String> {
+        let file: DefinitionFile = serde_json::from_str(json)
+            .map_err(|e| format!("Invalid primitive definitions: {}", e))?;
+        Ok(file
+            .primitives
+            .into_iter()
+            .map(|entry| PrimitiveDefinition {
+                name: entry.name,
+                baseline: entry.baseline,
+                context: ContextConfig {
+                    window_hours: entry.window_hours,
+                    decay_half_life_hours: entry.decay_half_life_hours,
+                },
+                dependencies: entry.dependencies,
+                computation: Computation::EventImpacts(entry.impacts),
+            })
+            .collect())
+    }
+}
+
+#[derive(Deserialize)]
+struct DefinitionFile {
+    primitives: Vec<DefinitionEntry>,
+}
+
+#[derive(Deserialize)]
+struct DefinitionEntry {
+    name: String,
+    baseline: f64,
+    window_hours: i64,
+    decay_half_life_hours: f64,
+    // Event type -> impact at the moment of the event
+    impacts: BTreeMap<String, f64>,
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+/// Primitives the estimator computes, in computation order. Dependencies must be
+/// registered before the primitives that use them, so registration order is
+/// always a valid computation order and outputs keep that order.
+#[derive(Debug, Clone)]
+pub struct PrimitiveRegistry {
+    definitions: Vec<PrimitiveDefinition>,
+}
+
+impl PrimitiveRegistry {
+    /// The model's primitives. ADHD mode lowers the dopamine and norepinephrine
+    /// baselines (reduced baseline catecholamine signaling).
+    pub fn builtin(adhd_mode: bool) -> Self {
+        let (dopamine, norepinephrine) = if adhd_mode { (0.35, 0.38) } else { (0.5, 0.5) };
+        PrimitiveRegistry {
+            definitions: vec![
+                PrimitiveDefinition::builtin(Primitive::Dopamine, dopamine, &[]),
+                PrimitiveDefinition::builtin(Primitive::Norepinephrine, norepinephrine, &[]),
+                PrimitiveDefinition::builtin(Primitive::Serotonin, 0.5, &[]),
+                PrimitiveDefinition::builtin(Primitive::Adenosine, 0.3, &[]),
+                // Circadian alignment is gated by the current adenosine level
+                PrimitiveDefinition::builtin(Primitive::CircadianPhase, 0.5, &[Primitive::Adenosine]),
+                PrimitiveDefinition::builtin(Primitive::Cortisol, 0.4, &[]),
+                PrimitiveDefinition::builtin(Primitive::Glucose, 0.5, &[]),
+                PrimitiveDefinition::builtin(Primitive::Melatonin, 0.05, &[]),
+            ],
+        }
+    }
+
+    /// Add a primitive after the existing ones. Rejects duplicate names,
+    /// baselines outside 0-1 and dependencies that are not registered yet.
+    pub fn register(&mut self, definition: PrimitiveDefinition) -> Result<(), String> {
+        if definition.name.is_empty() {
+            return Err("Primitive name must not be empty".to_string());
+        }
+        if self.get(definition.name.as_str()).is_some() {
+            return Err(format!("Primitive '{}' is already registered", definition.name));
+        }
+        if !(0.0..=1.0).contains(&definition.baseline) {
+            return Err(format!("Baseline of '{}' must be between 0 and 1", definition.name));
+        }
+        if definition.context.decay_half_life_hours <= 0.0 {
+            return Err(format!("Half-life of '{}' must be positive", definition.name));
+        }
+        if let Some(missing) = definition.dependencies.iter().find(|d| self.get(d.as_str()).is_none()) {
+            return Err(format!("'{}' depends on '{}', which is not registered", definition.name, missing));
+        }
+        self.definitions.push(definition);
+        Ok(())
+    }
+
+    pub fn get(&self, key: impl PrimitiveKey) -> Option<&PrimitiveDefinition> {
+        self.definitions.iter().find(|d| d.name == key.key())
+    }
+
+    /// Definitions in computation order
+    pub fn definitions(&self) -> &[PrimitiveDefinition] {
+        &self.definitions
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.definitions.iter().map(|d| d.name.as_str())
+    }
+}
+
+/// Anything that names a primitive: a built-in `Primitive` or a registered name
+pub trait PrimitiveKey {
+    fn key(&self) -> &str;
+}
+
+impl PrimitiveKey for Primitive {
+    fn key(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PrimitiveKey for &str {
+    fn key(&self) -> &str {
+        self
+    }
+}
+
+impl PrimitiveKey for &String {
+    fn key(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PrimitiveKey for String {
+    fn key(&self) -> &str {
+        self.as_str()
+    }
+}
+
+/// Primitive states in registry order. Serializes as a JSON object with keys in
+/// that order, so output no longer depends on hash iteration order.
+#[derive(Debug, Clone, Default)]
+pub struct PrimitiveMap {
+    entries: Vec<(String, PrimitiveState)>,
+}
+
+impl PrimitiveMap {
+    pub fn get(&self, key: impl PrimitiveKey) -> Option<&PrimitiveState> {
+        self.entries.iter().find(|(name, _)| name == key.key()).map(|(_, state)| state)
+    }
+
+    /// Final score of a primitive (see `PrimitiveState::final_score`)
+    pub fn score(&self, key: impl PrimitiveKey) -> Option<f64> {
+        self.get(key).map(|state| state.final_score())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &PrimitiveState)> {
+        self.entries.iter().map(|(name, state)| (name, state))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.entries.iter().map(|(name, _)| name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub(crate) fn push(&mut self, name: String, state: PrimitiveState) {
+        self.entries.push((name, state));
+    }
+}
+
+impl<'a> IntoIterator for &'a PrimitiveMap {
+    type Item = (&'a String, &'a PrimitiveState);
+    type IntoIter = std::iter::Map<
+        std::slice::Iter<'a, (String, PrimitiveState)>,
+        fn(&'a (String, PrimitiveState)) -> (&'a String, &'a PrimitiveState),
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter().map(|(name, state)| (name, state))
+    }
+}
+
+impl Serialize for PrimitiveMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.entries.len()))?;
+        for (name, state) in &self.entries {
+            map.serialize_entry(name, state)?;
+        }
+        map.end()
+    }
+}
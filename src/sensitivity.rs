@@ -0,0 +1,256 @@
+// sensitivity.rs - Local and global (Morris) sensitivity of primitive outputs to model coefficients
+use crate::sampling::SplitMix64;
+use crate::{EventStore, ModelParameters, PrimitiveEstimator};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Fixed seed so repeated analyses of the same scenario agree
+const MORRIS_SEED: u64 = 0x5E45_1717_0001;
+
+/// Relative step for central finite differences
+const DERIVATIVE_STEP: f64 = 0.01;
+
+#[derive(Debug, Clone)]
+pub struct SensitivityOptions {
+    // Each parameter is varied over nominal * (1 ± relative_range); parameters
+    // at zero (baseline shifts) over ± relative_range * their typical scale
+    pub relative_range: f64,
+    // Morris trajectories (each costs one estimate per parameter, plus one)
+    pub trajectories: usize,
+    // Grid levels per parameter (even)
+    pub levels: usize,
+    // Restrict the analysis to these parameters; all when empty
+    pub parameters: Vec<String>,
+}
+
+impl Default for SensitivityOptions {
+    fn default() -> Self {
+        SensitivityOptions {
+            relative_range: 0.25,
+            trajectories: 10,
+            levels: 4,
+            parameters: Vec::new(),
+        }
+    }
+}
+
+/// Sensitivity of one primitive's final score to one parameter
+#[derive(Debug, Clone, Serialize)]
+pub struct SensitivityRow {
+    pub parameter: String,
+    pub primitive: String,
+    pub nominal_value: f64,
+    // d(score) / d(parameter) at the nominal value
+    pub derivative: f64,
+    // Relative change in score per relative change in parameter
+    pub elasticity: f64,
+    // Mean absolute elementary effect: score change across the parameter's full range
+    pub morris_mu_star: f64,
+    // Spread of elementary effects: high values mean non-linear or interacting
+    pub morris_sigma: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SensitivityReport {
+    pub estimation_times: Vec<DateTime<Utc>>,
+    pub relative_range: f64,
+    pub trajectories: usize,
+    pub levels: usize,
+    // Ranked by Morris mu*, most influential first
+    pub rows: Vec<SensitivityRow>,
+}
+
+impl SensitivityReport {
+    /// Parameters ranked by their largest mu* across primitives
+    pub fn ranked_parameters(&self) -> Vec<(String, f64)> {
+        let mut largest: BTreeMap<&str, f64> = BTreeMap::new();
+        for row in &self.rows {
+            let entry = largest.entry(row.parameter.as_str()).or_insert(0.0);
+            *entry = entry.max(row.morris_mu_star);
+        }
+        let mut ranked: Vec<(String, f64)> = largest.into_iter().map(|(name, mu)| (name.to_string(), mu)).collect();
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        ranked
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("parameter,primitive,nominal_value,derivative,elasticity,morris_mu_star,morris_sigma\n");
+        for row in &self.rows {
+            csv.push_str(&format!(
+                "{},{},{},{:.6},{:.6},{:.6},{:.6}\n",
+                row.parameter, row.primitive, row.nominal_value,
+                row.derivative, row.elasticity, row.morris_mu_star, row.morris_sigma,
+            ));
+        }
+        csv
+    }
+}
+
+/// Sensitivity of every primitive's final score to every model parameter.
+///
+/// The output for a parameter set is each primitive's final score averaged
+/// over `estimation_times`. Local derivatives use central differences at the
+/// estimator's current parameters; global indices use Morris elementary
+/// effects over nominal * (1 ± relative_range), which also captures the
+/// step changes caused by thresholds.
+pub fn analyze_sensitivity(
+    estimator: &PrimitiveEstimator,
+    store: &EventStore,
+    estimation_times: &[DateTime<Utc>],
+    options: &SensitivityOptions,
+) -> Result<SensitivityReport, String> {
+    if estimation_times.is_empty() {
+        return Err("At least one estimation time is required".to_string());
+    }
+    if options.levels < 2 || !options.levels.is_multiple_of(2) {
+        return Err("Morris levels must be an even number of at least 2".to_string());
+    }
+
+    let nominal = estimator.parameters().clone();
+    let nominal_values = nominal.values();
+    let names: Vec<String> = if options.parameters.is_empty() {
+        nominal_values.keys().cloned().collect()
+    } else {
+        for name in &options.parameters {
+            if !nominal_values.contains_key(name) {
+                return Err(format!("Unknown parameter '{}'", name));
+            }
+        }
+        options.parameters.clone()
+    };
+
+    let evaluate = |changes: &BTreeMap<String, f64>| -> Result<BTreeMap<String, f64>, String> {
+        let params = nominal.with_values(changes)?;
+        Ok(mean_final_scores(estimator, params, store, estimation_times))
+    };
+
+    let baseline = evaluate(&BTreeMap::new())?;
+
+    // Local derivatives by central differences
+    let mut derivatives: BTreeMap<&str, BTreeMap<String, f64>> = BTreeMap::new();
+    for name in &names {
+        let value = nominal_values[name];
+        let step = if value.abs() > f64::EPSILON { value.abs() * DERIVATIVE_STEP } else { DERIVATIVE_STEP };
+        let up = evaluate(&BTreeMap::from([(name.clone(), value + step)]))?;
+        let down = evaluate(&BTreeMap::from([(name.clone(), value - step)]))?;
+        let derivative = up.iter()
+            .map(|(key, &high)| (key.clone(), (high - down.get(key).copied().unwrap_or(high)) / (2.0 * step)))
+            .collect();
+        derivatives.insert(name.as_str(), derivative);
+    }
+
+    // Morris elementary effects over the unit hypercube, mapped onto each parameter's range
+    let to_value = |name: &str, x: f64| {
+        let value = nominal_values[name];
+        let scale = if value.abs() > f64::EPSILON { value.abs() } else { ModelParameters::typical_scale(name) };
+        value + scale * options.relative_range * (2.0 * x - 1.0)
+    };
+    let grid_step = 1.0 / (options.levels - 1) as f64;
+    let delta = options.levels as f64 / (2.0 * (options.levels - 1) as f64);
+
+    let mut effects: BTreeMap<&str, BTreeMap<String, Vec<f64>>> = BTreeMap::new();
+    let mut rng = SplitMix64(MORRIS_SEED);
+    for _ in 0..options.trajectories {
+        // Start each parameter on the lower half of the grid, or delta above it, so every move stays in [0, 1]
+        let mut point: BTreeMap<&str, f64> = names.iter()
+            .map(|name| {
+                let level = (rng.next() % (options.levels / 2) as u64) as f64 * grid_step;
+                let start = if rng.next_f64() < 0.5 { level } else { level + delta };
+                (name.as_str(), start)
+            })
+            .collect();
+        let mut order: Vec<&str> = names.iter().map(String::as_str).collect();
+        rng.shuffle(&mut order);
+
+        let changes = |point: &BTreeMap<&str, f64>| -> BTreeMap<String, f64> {
+            point.iter().map(|(&name, &x)| (name.to_string(), to_value(name, x))).collect()
+        };
+
+        let mut previous = evaluate(&changes(&point))?;
+        for name in order {
+            let x = point[name];
+            let moved = if x + delta <= 1.0 + 1e-9 { x + delta } else { x - delta };
+            point.insert(name, moved);
+            let current = evaluate(&changes(&point))?;
+
+            let per_primitive = effects.entry(name).or_default();
+            for (key, &score) in &current {
+                let before = previous.get(key).copied().unwrap_or(score);
+                per_primitive.entry(key.clone()).or_default().push((score - before) / (moved - x));
+            }
+            previous = current;
+        }
+    }
+
+    let mut rows = Vec::new();
+    for name in &names {
+        let nominal_value = nominal_values[name];
+        for key in estimator.registry().names() {
+            let derivative = derivatives.get(name.as_str()).and_then(|d| d.get(key)).copied().unwrap_or(0.0);
+            let score = baseline.get(key).copied().unwrap_or(0.0);
+            let elasticity = if score.abs() > 1e-9 { derivative * nominal_value / score } else { 0.0 };
+
+            let samples = effects.get(name.as_str()).and_then(|e| e.get(key)).cloned().unwrap_or_default();
+            let (mu_star, sigma) = if samples.is_empty() {
+                (0.0, 0.0)
+            } else {
+                let n = samples.len() as f64;
+                let mean = samples.iter().sum::<f64>() / n;
+                let mu_star = samples.iter().map(|e| e.abs()).sum::<f64>() / n;
+                let variance = samples.iter().map(|e| (e - mean).powi(2)).sum::<f64>() / n;
+                (mu_star, variance.sqrt())
+            };
+
+            rows.push(SensitivityRow {
+                parameter: name.clone(),
+                primitive: key.to_string(),
+                nominal_value,
+                derivative,
+                elasticity,
+                morris_mu_star: mu_star,
+                morris_sigma: sigma,
+            });
+        }
+    }
+
+    rows.sort_by(|a, b| {
+        b.morris_mu_star
+            .partial_cmp(&a.morris_mu_star)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| b.derivative.abs().partial_cmp(&a.derivative.abs()).unwrap_or(std::cmp::Ordering::Equal))
+    });
+
+    Ok(SensitivityReport {
+        estimation_times: estimation_times.to_vec(),
+        relative_range: options.relative_range,
+        trajectories: options.trajectories,
+        levels: options.levels,
+        rows,
+    })
+}
+
+/// Each primitive's final score averaged over the estimation times
+pub(crate) fn mean_final_scores(
+    estimator: &PrimitiveEstimator,
+    params: ModelParameters,
+    store: &EventStore,
+    estimation_times: &[DateTime<Utc>],
+) -> BTreeMap<String, f64> {
+    // Same chronotype, state rules and registered primitives as the configured estimator
+    let estimator = estimator.clone().with_parameters(params);
+    let mut totals: BTreeMap<String, f64> = BTreeMap::new();
+    for &time in estimation_times {
+        let result = estimator.run_passes(store, time);
+        for (key, state) in &result.primitives {
+            *totals.entry(key.clone()).or_insert(0.0) += state.final_score();
+        }
+    }
+    let n = estimation_times.len().max(1) as f64;
+    totals.values_mut().for_each(|total| *total /= n);
+    totals
+}
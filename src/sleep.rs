@@ -0,0 +1,204 @@
+// sleep.rs - Interval-based sleep periods: duration reconciliation, merging and split sleep
+use crate::Event;
+use chrono::{DateTime, Duration, NaiveDate, Timelike, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Duration assumed when a sleep record has neither an end time nor a stated duration
+pub const DEFAULT_SLEEP_HOURS: f64 = 7.0;
+
+/// Records overlapping by more than this fraction of the shorter one describe the same sleep
+const SAME_SLEEP_OVERLAP: f64 = 0.5;
+
+/// A stated duration this much longer than the interval is treated as a data error
+const DURATION_TOLERANCE_HOURS: f64 = 0.5;
+
+/// One continuous sleep interval, possibly merged from several source records
+#[derive(Debug, Clone, Serialize)]
+pub struct SleepPeriod {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub asleep_hours: f64,
+    // Record whose properties describe this period (longest asleep duration)
+    pub primary_event_id: String,
+    pub event_ids: Vec<String>,
+}
+
+impl SleepPeriod {
+    pub fn in_bed_hours(&self) -> f64 {
+        (self.end - self.start).num_minutes() as f64 / 60.0
+    }
+
+    pub fn midpoint(&self) -> DateTime<Utc> {
+        self.start + (self.end - self.start) / 2
+    }
+
+    /// Calendar day the sleep "belongs to": the day it is slept off into.
+    /// Shifting the end by 6h keeps a sleep that ends just before midnight
+    /// with the night that follows it.
+    pub fn sleep_day(&self) -> NaiveDate {
+        (self.end + Duration::hours(6)).date_naive()
+    }
+}
+
+/// All sleep periods that make up one sleep day (a consolidated night or split sleep)
+#[derive(Debug, Clone, Serialize)]
+pub struct SleepDay {
+    pub date: NaiveDate,
+    pub periods: Vec<SleepPeriod>,
+}
+
+impl SleepDay {
+    pub fn total_asleep_hours(&self) -> f64 {
+        self.periods.iter().map(|p| p.asleep_hours).sum()
+    }
+
+    /// The longest period of the day, which anchors bedtime and wake time
+    pub fn main_period(&self) -> &SleepPeriod {
+        self.periods
+            .iter()
+            .max_by(|a, b| a.asleep_hours.partial_cmp(&b.asleep_hours).unwrap_or(std::cmp::Ordering::Equal))
+            .expect("sleep day has at least one period")
+    }
+
+    pub fn is_split(&self) -> bool {
+        self.periods.len() > 1
+    }
+
+    /// Sleep-weighted midpoint across all periods of the day
+    pub fn midpoint(&self) -> DateTime<Utc> {
+        let anchor = self.main_period().midpoint();
+        let total_weight: f64 = self.periods.iter().map(|p| p.asleep_hours).sum();
+        if total_weight <= 0.0 {
+            return anchor;
+        }
+        let weighted_offset_minutes: f64 = self.periods
+            .iter()
+            .map(|p| (p.midpoint() - anchor).num_minutes() as f64 * p.asleep_hours)
+            .sum::<f64>() / total_weight;
+        anchor + Duration::minutes(weighted_offset_minutes.round() as i64)
+    }
+}
+
+/// Time asleep and end of sleep for a single record.
+///
+/// The interval from `timestamp` to `end_timestamp` is time in bed. A stated
+/// `duration_hours` is time asleep, so it may be shorter than the interval but
+/// not meaningfully longer; when it is, the timestamps win.
+pub fn sleep_interval(event: &Event) -> (DateTime<Utc>, f64) {
+    let stated = event.properties.get("duration_hours").and_then(|v| v.as_f64());
+
+    match (event.end_timestamp, stated) {
+        (Some(end), Some(stated)) => {
+            let interval = (end - event.timestamp).num_minutes() as f64 / 60.0;
+            if interval <= 0.0 {
+                (event.timestamp + hours(stated), stated)
+            } else if stated > interval + DURATION_TOLERANCE_HOURS {
+                (end, interval)
+            } else {
+                (end, stated.min(interval))
+            }
+        }
+        (Some(end), None) => {
+            let interval = (end - event.timestamp).num_minutes() as f64 / 60.0;
+            if interval > 0.0 {
+                (end, interval)
+            } else {
+                (event.timestamp + hours(DEFAULT_SLEEP_HOURS), DEFAULT_SLEEP_HOURS)
+            }
+        }
+        (None, Some(stated)) => (event.timestamp + hours(stated), stated),
+        (None, None) => (event.timestamp + hours(DEFAULT_SLEEP_HOURS), DEFAULT_SLEEP_HOURS),
+    }
+}
+
+/// Hours asleep for a sleep record, reconciled with its interval
+pub fn asleep_hours(event: &Event) -> f64 {
+    sleep_interval(event).1
+}
+
+fn hours(h: f64) -> Duration {
+    Duration::minutes((h * 60.0).round() as i64)
+}
+
+/// Sleep periods that started at or before `until`, sorted by start time,
+/// with overlapping records from multiple sources merged.
+pub fn sleep_periods(events: &[Event], until: DateTime<Utc>) -> Vec<SleepPeriod> {
+    let mut periods: Vec<SleepPeriod> = events
+        .iter()
+        .filter(|e| e.event_type == "sleep" && e.timestamp <= until)
+        .map(|e| {
+            let (end, asleep_hours) = sleep_interval(e);
+            SleepPeriod {
+                start: e.timestamp,
+                end,
+                asleep_hours,
+                primary_event_id: e.event_id.clone(),
+                event_ids: vec![e.event_id.clone()],
+            }
+        })
+        .collect();
+
+    periods.sort_by(|a, b| a.start.cmp(&b.start).then_with(|| a.primary_event_id.cmp(&b.primary_event_id)));
+
+    let mut merged: Vec<SleepPeriod> = Vec::new();
+    for period in periods {
+        if let Some(last) = merged.last_mut() {
+            let overlap_start = last.start.max(period.start);
+            let overlap_end = last.end.min(period.end);
+            let overlap_hours = (overlap_end - overlap_start).num_minutes() as f64 / 60.0;
+            let shorter = last.in_bed_hours().min(period.in_bed_hours()).max(f64::EPSILON);
+
+            if overlap_hours / shorter > SAME_SLEEP_OVERLAP {
+                if period.asleep_hours > last.asleep_hours {
+                    last.primary_event_id = period.primary_event_id.clone();
+                }
+                last.start = last.start.min(period.start);
+                last.end = last.end.max(period.end);
+                last.asleep_hours = last.asleep_hours.max(period.asleep_hours).min(last.in_bed_hours());
+                last.event_ids.extend(period.event_ids);
+                continue;
+            }
+        }
+        merged.push(period);
+    }
+
+    merged
+}
+
+/// Completed sleep periods grouped by sleep day, most recent day first
+pub fn sleep_days(events: &[Event], since: DateTime<Utc>, until: DateTime<Utc>) -> Vec<SleepDay> {
+    group_sleep_days(sleep_periods(events, until), since, until)
+}
+
+/// Like `sleep_days`, from periods that are already merged (e.g. recorded sleep
+/// together with the periods kept in a live feed's daily summaries)
+pub fn group_sleep_days(
+    periods: impl IntoIterator<Item = SleepPeriod>,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Vec<SleepDay> {
+    let mut days: BTreeMap<NaiveDate, Vec<SleepPeriod>> = BTreeMap::new();
+    for period in periods {
+        if period.end < since || period.end > until {
+            continue;
+        }
+        days.entry(period.sleep_day()).or_default().push(period);
+    }
+
+    days.into_iter()
+        .rev()
+        .map(|(date, periods)| SleepDay { date, periods })
+        .collect()
+}
+
+/// Clock time as hours, centred on midnight (-12..12) so night-time values average cleanly
+pub fn hours_around_midnight(time: DateTime<Utc>) -> f64 {
+    let h = time.hour() as f64 + time.minute() as f64 / 60.0;
+    if h >= 12.0 { h - 24.0 } else { h }
+}
+
+/// Hours wrapped into -12..12, e.g. the signed difference between two clock times
+pub fn wrap_hours(hours: f64) -> f64 {
+    (hours + 12.0).rem_euclid(24.0) - 12.0
+}
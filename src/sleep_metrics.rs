@@ -0,0 +1,143 @@
+// sleep_metrics.rs - Sleep regularity: SRI, social jet lag, midpoint drift, timing variability
+use crate::chronotype::is_free_day;
+use crate::live::{self, DailySummary};
+use crate::sleep::{self, SleepDay};
+use crate::EventStore;
+use chrono::{DateTime, Duration, NaiveDate, Timelike, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Window for the Sleep Regularity Index and timing variability
+const REGULARITY_WINDOW_DAYS: i64 = 7;
+/// Window for social jet lag, long enough to include several weekends
+const SOCIAL_JET_LAG_WINDOW_DAYS: i64 = 28;
+const _: () = assert!(SOCIAL_JET_LAG_WINDOW_DAYS <= crate::HISTORY_WINDOW_DAYS);
+/// Window for midpoint drift
+const DRIFT_WINDOW_DAYS: i64 = 14;
+/// Epoch length when comparing sleep/wake state 24h apart
+const SRI_EPOCH_MINUTES: i64 = 10;
+/// Fewest sleep days for a variability or drift estimate
+const MIN_DAYS: usize = 3;
+
+/// Regularity of sleep timing over rolling windows ending at the estimation time.
+/// Each metric is None when its window holds too little sleep data.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SleepMetrics {
+    // -100 (random) to 100 (identical days): chance of the same sleep/wake state 24h apart, last 7 days
+    pub sleep_regularity_index: Option<f64>,
+    // Free-day minus workday sleep midpoint (hours, last 28 days); positive = later on free days
+    pub social_jet_lag_hours: Option<f64>,
+    // Slope of the sleep midpoint (hours per day, last 14 days); positive = drifting later
+    pub midpoint_drift_hours_per_day: Option<f64>,
+    // Standard deviation of bedtimes and wake times (hours, last 7 sleep days)
+    pub bedtime_std_hours: Option<f64>,
+    pub wake_time_std_hours: Option<f64>,
+}
+
+impl SleepMetrics {
+    /// Nights older than the store's events are read from `summaries` (live feeds)
+    pub fn compute(store: &EventStore, time: DateTime<Utc>, summaries: &BTreeMap<NaiveDate, DailySummary>) -> Self {
+        let sleep_days = |days: i64| live::sleep_days_with_summaries(store, summaries, time - Duration::days(days), time);
+        let week_start = time - Duration::days(REGULARITY_WINDOW_DAYS);
+        let week: Vec<SleepDay> = sleep_days(REGULARITY_WINDOW_DAYS);
+
+        // Explicit wake events win over the end of each day's main sleep
+        let mut wakes: Vec<DateTime<Utc>> = store
+            .of_type("wake", week_start, time)
+            .map(|e| e.timestamp)
+            .collect();
+        if wakes.is_empty() {
+            wakes = week.iter().map(|day| day.main_period().end).collect();
+        }
+        let bedtimes: Vec<DateTime<Utc>> = week.iter().map(|day| day.main_period().start).collect();
+
+        SleepMetrics {
+            sleep_regularity_index: sleep_regularity_index(store, week_start, time),
+            social_jet_lag_hours: social_jet_lag(&sleep_days(SOCIAL_JET_LAG_WINDOW_DAYS)),
+            midpoint_drift_hours_per_day: midpoint_drift(&sleep_days(DRIFT_WINDOW_DAYS)),
+            bedtime_std_hours: clock_std(&bedtimes),
+            wake_time_std_hours: clock_std(&wakes),
+        }
+    }
+}
+
+fn clock_hours(time: &DateTime<Utc>) -> f64 {
+    time.hour() as f64 + time.minute() as f64 / 60.0
+}
+
+/// Standard deviation of clock times, measured around the first so that times
+/// either side of midnight stay together
+fn clock_std(times: &[DateTime<Utc>]) -> Option<f64> {
+    let first = clock_hours(times.first()?);
+    if times.len() < MIN_DAYS {
+        return None;
+    }
+    let offsets: Vec<f64> = times.iter().map(|t| sleep::wrap_hours(clock_hours(t) - first)).collect();
+    let mean = offsets.iter().sum::<f64>() / offsets.len() as f64;
+    let variance = offsets.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / offsets.len() as f64;
+    Some(variance.sqrt())
+}
+
+/// Sleep Regularity Index (Phillips et al., 2017): -100 + 200 × the share of
+/// epochs whose sleep/wake state matches the epoch 24h later. Only the span
+/// from the first recorded sleep onwards is compared, and at least a day of
+/// comparisons is needed.
+fn sleep_regularity_index(store: &EventStore, from: DateTime<Utc>, to: DateTime<Utc>) -> Option<f64> {
+    let periods: Vec<(DateTime<Utc>, DateTime<Utc>)> = sleep::sleep_periods(store.until(to), to)
+        .iter()
+        .filter(|p| p.end > from)
+        .map(|p| (p.start, p.end))
+        .collect();
+    let first = periods.first()?.0.max(from);
+    if to - first < Duration::hours(48) {
+        return None;
+    }
+
+    let asleep = |t: DateTime<Utc>| periods.iter().any(|&(start, end)| start <= t && t < end);
+    let (mut same, mut compared) = (0usize, 0usize);
+    let mut t = first;
+    while t + Duration::hours(24) <= to {
+        if asleep(t) == asleep(t + Duration::hours(24)) {
+            same += 1;
+        }
+        compared += 1;
+        t += Duration::minutes(SRI_EPOCH_MINUTES);
+    }
+    Some(-100.0 + 200.0 * same as f64 / compared as f64)
+}
+
+/// Free-day minus workday mean sleep midpoint; needs at least one of each
+fn social_jet_lag(days: &[SleepDay]) -> Option<f64> {
+    let mean_midpoint = |free: bool| {
+        let midpoints: Vec<f64> = days
+            .iter()
+            .filter(|day| is_free_day(day.date) == free)
+            .map(|day| sleep::hours_around_midnight(day.midpoint()))
+            .collect();
+        (!midpoints.is_empty()).then(|| midpoints.iter().sum::<f64>() / midpoints.len() as f64)
+    };
+    Some(mean_midpoint(true)? - mean_midpoint(false)?)
+}
+
+/// Least-squares slope of the sleep midpoint against the date
+fn midpoint_drift(days: &[SleepDay]) -> Option<f64> {
+    if days.len() < MIN_DAYS {
+        return None;
+    }
+    let anchor = days.last()?;
+    let anchor_midpoint = sleep::hours_around_midnight(anchor.midpoint());
+    let points: Vec<(f64, f64)> = days
+        .iter()
+        .map(|day| {
+            let x = (day.date - anchor.date).num_days() as f64;
+            let y = anchor_midpoint + sleep::wrap_hours(sleep::hours_around_midnight(day.midpoint()) - anchor_midpoint);
+            (x, y)
+        })
+        .collect();
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let covariance: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    (variance > 0.0).then(|| covariance / variance)
+}
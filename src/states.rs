@@ -0,0 +1,348 @@
+// states.rs - Rule-based functional state classification over all primitives
+use crate::FunctionalState;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Width of the soft threshold for 0-1 inputs when a condition gives none
+const DEFAULT_SOFTNESS: f64 = 0.05;
+
+/// One requirement of a state: the largest of `inputs` above and/or below a threshold
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateCondition {
+    // Primitive names, "sleep_drive", "circadian_sleep_pressure" or "hours_since_meal"
+    pub inputs: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub above: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub below: Option<f64>,
+    // Distance from the threshold at which membership reaches ~73%
+    #[serde(default = "default_softness")]
+    pub softness: f64,
+}
+
+fn default_softness() -> f64 {
+    DEFAULT_SOFTNESS
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateRule {
+    pub name: String,
+    pub description: String,
+    #[serde(default)]
+    pub recommendations: Vec<String>,
+    pub conditions: Vec<StateCondition>,
+}
+
+/// How strongly one input supported the chosen state
+#[derive(Debug, Clone, Serialize)]
+pub struct StateDriver {
+    pub input: String,
+    pub value: f64,
+    // "above 0.60", "below 0.45", ...
+    pub requirement: String,
+    // 0-1 degree to which the requirement holds
+    pub membership: f64,
+}
+
+/// A state that was considered, with its share of the total score
+#[derive(Debug, Clone, Serialize)]
+pub struct StateCandidate {
+    pub state_type: String,
+    pub score: f64,
+    pub probability: f64,
+}
+
+/// Functional state rules. The built-in set can be replaced by rules loaded
+/// from JSON (`{"states": [...]}`); earlier rules win ties.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateClassifier {
+    pub states: Vec<StateRule>,
+}
+
+impl StateCondition {
+    fn new(inputs: &[&str], above: Option<f64>, below: Option<f64>) -> Self {
+        StateCondition {
+            inputs: inputs.iter().map(|s| s.to_string()).collect(),
+            above,
+            below,
+            softness: DEFAULT_SOFTNESS,
+        }
+    }
+
+    fn above(input: &str, threshold: f64) -> Self {
+        Self::new(&[input], Some(threshold), None)
+    }
+
+    fn below(input: &str, threshold: f64) -> Self {
+        Self::new(&[input], None, Some(threshold))
+    }
+
+    fn between(input: &str, low: f64, high: f64) -> Self {
+        Self::new(&[input], Some(low), Some(high))
+    }
+
+    fn softness(mut self, softness: f64) -> Self {
+        self.softness = softness;
+        self
+    }
+
+    /// The deciding input and its value; None if no input is available
+    fn value(&self, inputs: &BTreeMap<String, f64>) -> Option<(&str, f64)> {
+        self.inputs
+            .iter()
+            .filter_map(|name| inputs.get(name).map(|&v| (name.as_str(), v)))
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+    }
+
+    fn membership(&self, value: f64) -> f64 {
+        let softness = self.softness.max(1e-6);
+        let sigmoid = |x: f64| 1.0 / (1.0 + (-x).exp());
+        let above = self.above.map_or(1.0, |t| sigmoid((value - t) / softness));
+        let below = self.below.map_or(1.0, |t| sigmoid((t - value) / softness));
+        above * below
+    }
+
+    fn requirement(&self) -> String {
+        match (self.above, self.below) {
+            (Some(low), Some(high)) => format!("between {:.2} and {:.2}", low, high),
+            (Some(low), None) => format!("above {:.2}", low),
+            (None, Some(high)) => format!("below {:.2}", high),
+            (None, None) => "any".to_string(),
+        }
+    }
+}
+
+impl StateRule {
+    fn new(name: &str, description: &str, recommendations: &[&str], conditions: Vec<StateCondition>) -> Self {
+        StateRule {
+            name: name.to_string(),
+            description: description.to_string(),
+            recommendations: recommendations.iter().map(|s| s.to_string()).collect(),
+            conditions,
+        }
+    }
+
+    /// Geometric mean of the conditions' memberships, with each one's driver
+    fn score(&self, inputs: &BTreeMap<String, f64>) -> (f64, Vec<StateDriver>) {
+        let mut drivers = Vec::new();
+        let mut log_sum = 0.0;
+        for condition in &self.conditions {
+            let Some((input, value)) = condition.value(inputs) else {
+                return (0.0, Vec::new());
+            };
+            let membership = condition.membership(value);
+            log_sum += membership.max(1e-12).ln();
+            drivers.push(StateDriver {
+                input: input.to_string(),
+                value,
+                requirement: condition.requirement(),
+                membership,
+            });
+        }
+        if drivers.is_empty() {
+            return (0.0, drivers);
+        }
+        ((log_sum / drivers.len() as f64).exp(), drivers)
+    }
+}
+
+impl Default for StateClassifier {
+    fn default() -> Self {
+        StateClassifier {
+            states: vec![
+                StateRule::new(
+                    "Wired and Tired",
+                    "Stress hormones are keeping you up despite high sleep pressure. Alertness is borrowed, not restored.",
+                    &[
+                        "Wind down: dim lights, no screens, slow breathing",
+                        "Avoid caffeine and intense exercise for the rest of the day",
+                        "Protect tonight's sleep opportunity",
+                    ],
+                    vec![
+                        StateCondition::new(&["cortisol", "norepinephrine"], Some(0.6), None),
+                        StateCondition::above("adenosine", 0.6),
+                    ],
+                ),
+                StateRule::new(
+                    "Sleep-Pressure Crash",
+                    "High adenosine without the arousal to counter it. Attention and reaction time are impaired.",
+                    &[
+                        "A 10-20 minute nap if it is before mid-afternoon",
+                        "Avoid driving and error-critical work",
+                        "Go to bed early tonight rather than adding caffeine",
+                    ],
+                    vec![
+                        StateCondition::above("sleep_drive", 0.65),
+                        StateCondition::above("adenosine", 0.65),
+                        StateCondition::below("norepinephrine", 0.45),
+                    ],
+                ),
+                StateRule::new(
+                    "Post-Prandial Dip",
+                    "A recent meal is pulling arousal down while glucose is elevated.",
+                    &[
+                        "A short walk (10-15 minutes) blunts the glucose peak and the dip",
+                        "Schedule routine tasks for the next hour",
+                        "Favour protein and fibre over refined carbohydrates at the next meal",
+                    ],
+                    vec![
+                        StateCondition::below("hours_since_meal", 2.0).softness(0.5),
+                        StateCondition::above("glucose", 0.6),
+                        StateCondition::below("norepinephrine", 0.5),
+                    ],
+                ),
+                StateRule::new(
+                    "Circadian Trough",
+                    "Your body clock is in its low phase. Sleepiness is from timing rather than sleep debt.",
+                    &[
+                        "Bright light and movement if you need to stay awake",
+                        "Expect slower thinking until the clock swings back",
+                        "If this is your usual bedtime, this is a good time to sleep",
+                    ],
+                    vec![
+                        StateCondition::above("circadian_sleep_pressure", 0.7),
+                        StateCondition::below("adenosine", 0.6),
+                    ],
+                ),
+                StateRule::new(
+                    "Flow-Ready",
+                    "Drive and arousal are in the productive range with low stress and sleep pressure.",
+                    &[
+                        "Start your most demanding task now",
+                        "Block interruptions for the next 60-90 minutes",
+                    ],
+                    vec![
+                        StateCondition::above("dopamine", 0.6),
+                        StateCondition::between("norepinephrine", 0.5, 0.75),
+                        StateCondition::below("cortisol", 0.55),
+                        StateCondition::below("adenosine", 0.45),
+                    ],
+                ),
+                StateRule::new(
+                    "Optimal",
+                    "Both motivation and mood are strong. Ideal state for productivity and well-being.",
+                    &[
+                        "Maintain current patterns",
+                        "This is a good time for challenging work or important decisions",
+                    ],
+                    vec![
+                        StateCondition::above("dopamine", 0.6),
+                        StateCondition::above("serotonin", 0.6),
+                    ],
+                ),
+                StateRule::new(
+                    "Driven but Anxious",
+                    "High motivation but low contentment. Risk of stress and burnout.",
+                    &[
+                        "Practice stress-reduction techniques",
+                        "Increase serotonin: social connection, outdoor time, balanced meals",
+                        "Avoid overcommitting to new projects",
+                    ],
+                    vec![
+                        StateCondition::above("dopamine", 0.6),
+                        StateCondition::below("serotonin", 0.5),
+                    ],
+                ),
+                StateRule::new(
+                    "Content but Unmotivated",
+                    "Good mood but low drive. May struggle with initiation and focus.",
+                    &[
+                        "Boost dopamine: exercise (especially HIIT), achievement tasks, protein-rich meals",
+                        "Set small, concrete goals to build momentum",
+                        "Consider caffeine in moderation (morning only)",
+                    ],
+                    vec![
+                        StateCondition::below("dopamine", 0.5),
+                        StateCondition::above("serotonin", 0.6),
+                    ],
+                ),
+                StateRule::new(
+                    "Balanced",
+                    "Motivation and mood are both moderate. Steady, but without much reserve.",
+                    &[
+                        "Keep sleep, meals and movement regular",
+                        "Mix demanding work with lighter tasks",
+                    ],
+                    vec![
+                        StateCondition::between("dopamine", 0.45, 0.65),
+                        StateCondition::between("serotonin", 0.45, 0.65),
+                    ],
+                ),
+                StateRule::new(
+                    "Depleted",
+                    "Both motivation and mood are low. Recovery is the priority.",
+                    &[
+                        "Prioritize rest and sleep",
+                        "Avoid demanding decisions or high-stress situations",
+                        "Gentle exercise, social connection, and balanced nutrition",
+                    ],
+                    vec![
+                        StateCondition::below("dopamine", 0.5),
+                        StateCondition::below("serotonin", 0.5),
+                    ],
+                ),
+            ],
+        }
+    }
+}
+
+impl StateClassifier {
+    /// Parse rule definitions (JSON object with a `states` array)
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let classifier: StateClassifier = serde_json::from_str(json)
+            .map_err(|e| format!("Invalid state rules: {}", e))?;
+        if classifier.states.is_empty() {
+            return Err("State rules define no states".to_string());
+        }
+        if let Some(rule) = classifier.states.iter().find(|r| r.conditions.is_empty()) {
+            return Err(format!("State '{}' has no conditions", rule.name));
+        }
+        Ok(classifier)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// Score every state and return the best, with its drivers and the alternatives.
+    /// Probabilities are each state's share of the summed scores.
+    pub fn classify(&self, inputs: &BTreeMap<String, f64>) -> FunctionalState {
+        let scored: Vec<(&StateRule, f64, Vec<StateDriver>)> = self.states
+            .iter()
+            .map(|rule| {
+                let (score, drivers) = rule.score(inputs);
+                (rule, score, drivers)
+            })
+            .collect();
+        let total: f64 = scored.iter().map(|(_, score, _)| score).sum();
+        let probability = |score: f64| if total > 0.0 { score / total } else { 0.0 };
+
+        let mut alternatives: Vec<StateCandidate> = scored.iter()
+            .map(|(rule, score, _)| StateCandidate {
+                state_type: rule.name.clone(),
+                score: *score,
+                probability: probability(*score),
+            })
+            .collect();
+        alternatives.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+
+        // First rule wins a tie, so scan in rule order
+        let Some((rule, score, mut drivers)) = scored.into_iter()
+            .reduce(|best, next| if next.1 > best.1 { next } else { best })
+        else {
+            return FunctionalState::default();
+        };
+        alternatives.retain(|a| a.state_type != rule.name);
+        drivers.sort_by(|a, b| b.membership.partial_cmp(&a.membership).unwrap_or(std::cmp::Ordering::Equal));
+
+        FunctionalState {
+            state_type: rule.name.clone(),
+            description: rule.description.clone(),
+            recommendations: rule.recommendations.clone(),
+            score,
+            probability: probability(score),
+            drivers,
+            alternatives,
+        }
+    }
+}
@@ -0,0 +1,78 @@
+// trace.rs - Opt-in record of how each pass of the estimator changed each primitive
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Estimation pass that produced a trace step, in the order passes run
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TracePass {
+    BaseScore,
+    Sequences,
+    CrossPrimitive,
+    PhysiologicalValidation,
+}
+
+/// One change to a primitive's score: which rule fired, and why
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceStep {
+    pub pass: TracePass,
+    pub rule: String,
+    pub before: f64,
+    pub after: f64,
+    pub reason: String,
+}
+
+/// Internals of the cortisol base score (circadian baseline plus stress response)
+#[derive(Debug, Clone, Serialize)]
+pub struct CortisolTrace {
+    pub allostatic_index: f64,
+    pub accumulated_impact: f64,
+    pub circadian_multiplier: f64,
+    pub healthy_baseline: f64,
+    pub awakening_boost: f64,
+    pub circadian_stress_sensitivity: f64,
+    pub stress_load: f64,
+    pub stress_response: f64,
+    pub relaxation_effect: f64,
+}
+
+/// Plasma-tracked caffeine boost to dopamine.
+/// Only the base-score path adds it; the monoamine path used for dopamine
+/// gets caffeine's dopamine effect from the event impact instead.
+#[derive(Debug, Clone, Serialize)]
+pub struct CaffeineBoostTrace {
+    pub boost: f64,
+    pub applied: bool,
+}
+
+/// ADHD-mode adjustments in effect for this estimate
+#[derive(Debug, Clone, Serialize)]
+pub struct AdhdTrace {
+    pub baselines: BTreeMap<String, f64>,
+    // Half-lives are divided by these factors (faster decay)
+    pub decay_modifiers: BTreeMap<String, f64>,
+    // Multiplier on adenosine and cortisol suppression of dopamine
+    pub suppression_multiplier: f64,
+}
+
+/// Full record of one estimate, produced by `PrimitiveEstimator::estimate_with_trace`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EstimationTrace {
+    // Steps per primitive, in the order they were applied
+    pub steps: BTreeMap<String, Vec<TraceStep>>,
+    pub cortisol: Option<CortisolTrace>,
+    pub caffeine_dopamine_boost: Option<CaffeineBoostTrace>,
+    pub adhd: Option<AdhdTrace>,
+}
+
+impl EstimationTrace {
+    pub(crate) fn step(&mut self, primitive: &str, pass: TracePass, rule: &str, before: f64, after: f64, reason: String) {
+        self.steps.entry(primitive.to_string()).or_default().push(TraceStep {
+            pass,
+            rule: rule.to_string(),
+            before,
+            after,
+            reason,
+        });
+    }
+}
@@ -0,0 +1,234 @@
+// training.rs - Training load (session RPE or TRIMP), acute:chronic ratio, recovery and overreaching
+use crate::live::DailySummary;
+use crate::sleep;
+use crate::units;
+use crate::{sleep_quality_score, Event, EventStore, MeasurementType};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Acute load window (fatigue)
+const ACUTE_DAYS: i64 = 7;
+/// Chronic load window (fitness)
+const CHRONIC_DAYS: i64 = 28;
+/// History needed before chronic load, and so the ratio, is meaningful
+const MIN_CHRONIC_DAYS: i64 = 14;
+/// Days of morning HRV and sleep that describe current recovery
+const RECENT_DAYS: i64 = 3;
+// The HRV baseline reaches back a chronic window before the recent days
+const _: () = assert!(CHRONIC_DAYS + RECENT_DAYS <= crate::HISTORY_WINDOW_DAYS);
+/// Ratio above which injury and illness risk rise (Gabbett, 2016)
+const HIGH_ACWR: f64 = 1.3;
+/// Recent morning HRV this far below its baseline is a falling trend
+const HRV_DROP_RATIO: f64 = 0.93;
+const POOR_SLEEP_HOURS: f64 = 7.0;
+// Used for TRIMP when the session does not record them
+const DEFAULT_RESTING_HR: f64 = 60.0;
+const DEFAULT_MAX_HR: f64 = 190.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadMethod {
+    // Session RPE (CR10) × minutes (Foster, 2001); RPE estimated from intensity when not reported
+    #[default]
+    SessionRpe,
+    // Banister's TRIMP from average heart rate, used when every session has one
+    Trimp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecoveryStatus {
+    #[default]
+    Recovered,
+    Recovering,
+    Strained,
+}
+
+/// Training load and recovery at the estimation time. Loads are mean daily load
+/// in the units of `method`; ratios and trends are None without enough history.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TrainingLoad {
+    pub method: LoadMethod,
+    // Mean daily load over the last 7 days
+    pub acute_load: f64,
+    // Mean daily load over the last 28 days (or the history available, at least 14 days)
+    pub chronic_load: Option<f64>,
+    // Acute:chronic workload ratio; 0.8-1.3 is the usual safe range
+    pub acute_chronic_ratio: Option<f64>,
+    // Mean morning HRV of the last 3 days / the 28 days before them
+    pub hrv_trend: Option<f64>,
+    // Mean sleep over the last 3 nights
+    pub recent_sleep_hours: Option<f64>,
+    // 0 (strained) to 1 (fully recovered): HRV trend, sleep and load ratio
+    pub recovery_score: f64,
+    pub recovery_status: RecoveryStatus,
+    // Sustained high load with falling HRV and poor sleep
+    pub overreaching: bool,
+    // Sessions in the acute window
+    pub acute_session_ids: Vec<String>,
+}
+
+impl TrainingLoad {
+    /// Days older than the store's events are read from `summaries` (live feeds)
+    pub fn compute(store: &EventStore, time: DateTime<Utc>, summaries: &BTreeMap<NaiveDate, DailySummary>) -> Self {
+        let chronic_start = time - Duration::days(CHRONIC_DAYS);
+        let sessions: Vec<&Event> = store.of_type("exercise", chronic_start, time).collect();
+        // Whole summarized days inside the chronic window
+        let summarized: Vec<&DailySummary> = summaries
+            .range(chronic_start.date_naive().succ_opt().unwrap_or(NaiveDate::MAX)..=time.date_naive())
+            .map(|(_, summary)| summary)
+            .collect();
+        let summarized_sessions: usize = summarized.iter().map(|s| s.exercise_sessions()).sum();
+        let method = if (!sessions.is_empty() || summarized_sessions > 0)
+            && sessions.iter().all(|e| number(e, "heart_rate_avg").is_some())
+            && summarized.iter().all(|s| s.sessions_without_heart_rate == 0)
+        {
+            LoadMethod::Trimp
+        } else {
+            LoadMethod::SessionRpe
+        };
+
+        let acute_start = time - Duration::days(ACUTE_DAYS);
+        let load_since = |start: DateTime<Utc>| -> f64 {
+            sessions
+                .iter()
+                .filter(|e| e.timestamp >= start)
+                .fold(0.0, |total, e| total + session_load(e, method))
+        };
+        let acute_load = load_since(acute_start) / ACUTE_DAYS as f64;
+
+        // History starts at the first event of any kind, so untracked weeks are not read as rest
+        let first_summary = summaries.keys().next().map(|date| date.and_time(NaiveTime::MIN).and_utc());
+        let history_days = store
+            .events()
+            .first()
+            .map(|first| first.timestamp)
+            .into_iter()
+            .chain(first_summary)
+            .min()
+            .map(|first| (time - first).num_days().min(CHRONIC_DAYS))
+            .unwrap_or(0);
+        let summarized_load: f64 = summarized.iter().map(|s| s.training_load(method)).sum();
+        let chronic_load = (history_days >= MIN_CHRONIC_DAYS)
+            .then(|| (load_since(chronic_start) + summarized_load) / history_days as f64);
+        let acute_chronic_ratio = chronic_load.filter(|&c| c > 0.0).map(|c| acute_load / c);
+
+        let hrv_trend = hrv_trend(store, time, summaries);
+        let nights = sleep::sleep_days(store.until(time), time - Duration::days(RECENT_DAYS), time);
+        let recent_sleep_hours = (!nights.is_empty())
+            .then(|| nights.iter().map(|d| d.total_asleep_hours()).sum::<f64>() / nights.len() as f64);
+        let poor_sleep = recent_sleep_hours.is_some_and(|hours| hours < POOR_SLEEP_HOURS) || {
+            let recent: Vec<&Event> = store.of_type("sleep", time - Duration::days(RECENT_DAYS), time).collect();
+            recent.len() >= 2 && recent.iter().filter(|e| sleep_quality_score(e) < 0.7).count() >= 2
+        };
+
+        // Each available signal scores 0-1; recovery is their mean
+        let signals: Vec<f64> = [
+            hrv_trend.map(|trend| ((trend - 0.85) / 0.15).clamp(0.0, 1.0)),
+            recent_sleep_hours.map(|hours| ((hours - 5.5) / 2.0).clamp(0.0, 1.0)),
+            acute_chronic_ratio.map(|ratio| (1.0 - (ratio - HIGH_ACWR) / 0.5).clamp(0.0, 1.0)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let recovery_score = if signals.is_empty() { 1.0 } else { signals.iter().sum::<f64>() / signals.len() as f64 };
+        let recovery_status = match recovery_score {
+            s if s >= 0.7 => RecoveryStatus::Recovered,
+            s if s >= 0.4 => RecoveryStatus::Recovering,
+            _ => RecoveryStatus::Strained,
+        };
+
+        let high_load = acute_chronic_ratio.is_some_and(|ratio| ratio >= HIGH_ACWR);
+        let falling_hrv = hrv_trend.is_some_and(|trend| trend < HRV_DROP_RATIO);
+
+        TrainingLoad {
+            method,
+            acute_load,
+            chronic_load,
+            acute_chronic_ratio,
+            hrv_trend,
+            recent_sleep_hours,
+            recovery_score,
+            recovery_status,
+            overreaching: high_load && falling_hrv && poor_sleep,
+            acute_session_ids: sessions
+                .iter()
+                .filter(|e| e.timestamp >= acute_start)
+                .map(|e| e.event_id.clone())
+                .collect(),
+        }
+    }
+}
+
+fn number(event: &Event, key: &str) -> Option<f64> {
+    event.properties.get(key).and_then(|v| v.as_f64())
+}
+
+/// Borg CR10 rating, reported or estimated from the intensity label
+fn session_rpe(event: &Event) -> f64 {
+    if let Some(rpe) = number(event, "rpe") {
+        return rpe.clamp(0.0, 10.0);
+    }
+    if event.properties.get("type").and_then(|v| v.as_str()) == Some("hiit") {
+        return 8.0;
+    }
+    match event.properties.get("intensity").and_then(|v| v.as_str()) {
+        Some("light") => 3.0,
+        Some("moderate") => 5.0,
+        Some("vigorous") => 7.0,
+        Some("high_intensity") => 8.0,
+        _ => number(event, "vo2max_percentage").map(|pct| (pct / 10.0).clamp(1.0, 10.0)).unwrap_or(5.0),
+    }
+}
+
+/// Load of one session. TRIMP = minutes × HRr × 0.64·e^(1.92·HRr), with HRr the
+/// fraction of heart rate reserve (Banister, 1991).
+pub fn session_load(event: &Event, method: LoadMethod) -> f64 {
+    let minutes = number(event, "duration_minutes").unwrap_or(30.0);
+    match method {
+        LoadMethod::SessionRpe => session_rpe(event) * minutes,
+        LoadMethod::Trimp => {
+            let rest = number(event, "heart_rate_rest").unwrap_or(DEFAULT_RESTING_HR);
+            let max = number(event, "heart_rate_max").unwrap_or(DEFAULT_MAX_HR);
+            let Some(average) = number(event, "heart_rate_avg") else {
+                return 0.0;
+            };
+            let reserve = ((average - rest) / (max - rest).max(1.0)).clamp(0.0, 1.0);
+            minutes * reserve * 0.64 * (1.92 * reserve).exp()
+        }
+    }
+}
+
+/// HRV reading in ms, normalized from its unit
+pub(crate) fn hrv_ms(event: &Event) -> Option<f64> {
+    let value = number(event, "value")?;
+    let unit = event.properties.get("unit").and_then(|v| v.as_str()).unwrap_or("");
+    units::normalize_reading(&MeasurementType::HeartRateVariability, value, unit, &HashMap::new(), &event.properties)
+        .ok()
+        .map(|reading| reading.value)
+}
+
+/// Recent mean morning HRV relative to the preceding baseline. The first reading
+/// of each day stands for that morning; needs 2 recent and 3 baseline mornings.
+fn hrv_trend(store: &EventStore, time: DateTime<Utc>, summaries: &BTreeMap<NaiveDate, DailySummary>) -> Option<f64> {
+    let window_start = time - Duration::days(CHRONIC_DAYS + RECENT_DAYS);
+    let mut mornings: BTreeMap<NaiveDate, f64> = summaries
+        .range(window_start.date_naive().succ_opt()?..=time.date_naive())
+        .filter_map(|(date, summary)| Some((*date, summary.morning_hrv?)))
+        .collect();
+    for event in store.of_type("health_hrv", window_start, time) {
+        let Some(value) = hrv_ms(event) else { continue };
+        mornings.entry(event.timestamp.date_naive()).or_insert(value);
+    }
+
+    let recent_start = (time - Duration::days(RECENT_DAYS)).date_naive();
+    let recent: Vec<f64> = mornings.range(recent_start.succ_opt()?..).map(|(_, v)| *v).collect();
+    let baseline: Vec<f64> = mornings.range(..=recent_start).map(|(_, v)| *v).collect();
+    if recent.len() < 2 || baseline.len() < 3 {
+        return None;
+    }
+    let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+    let baseline_mean = mean(&baseline);
+    (baseline_mean > 0.0).then(|| mean(&recent) / baseline_mean)
+}
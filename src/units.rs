@@ -0,0 +1,271 @@
+// units.rs - Unit normalization for wearable and lab measurements
+use crate::MeasurementType;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Molar mass of glucose (180.16 g/mol) expressed as mg/dL per mmol/L
+pub const GLUCOSE_MG_DL_PER_MMOL_L: f64 = 18.016;
+
+/// 1 kPa expressed in mmHg
+pub const MMHG_PER_KPA: f64 = 7.50062;
+
+/// Approximate RMSSD/SDNN ratio for short resting recordings.
+/// Both track vagal tone at rest, but SDNN also carries slower sympathetic
+/// variability, so it runs somewhat higher than RMSSD for the same person.
+pub const RMSSD_PER_SDNN: f64 = 0.8;
+
+/// Physical dimension of a unit label, used to tell unknown units apart
+/// from known units that simply do not fit the measurement
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Dimension {
+    HeartRate,
+    Duration,
+    Fraction,
+    Concentration,
+    Temperature,
+    BreathingRate,
+    Count,
+    Conductance,
+    Pressure,
+    OxygenUptake,
+}
+
+/// A recognised unit with its linear conversion to the canonical unit
+/// of its dimension: canonical = value * scale + offset
+#[derive(Debug, Clone, Copy)]
+struct Unit {
+    dimension: Dimension,
+    scale: f64,
+    offset: f64,
+}
+
+impl Unit {
+    fn linear(dimension: Dimension, scale: f64) -> Self {
+        Unit { dimension, scale, offset: 0.0 }
+    }
+
+    fn convert(&self, value: f64) -> f64 {
+        value * self.scale + self.offset
+    }
+
+    /// Convert a difference between two readings (offsets cancel out)
+    fn convert_delta(&self, value: f64) -> f64 {
+        value * self.scale
+    }
+}
+
+fn parse_unit(label: &str) -> Option<Unit> {
+    let normalized = label.trim().to_lowercase().replace(' ', "");
+    let unit = match normalized.as_str() {
+        "bpm" | "beats/min" | "beatsperminute" | "1/min" => Unit::linear(Dimension::HeartRate, 1.0),
+        "ms" | "msec" | "millisecond" | "milliseconds" => Unit::linear(Dimension::Duration, 1.0),
+        "s" | "sec" | "second" | "seconds" => Unit::linear(Dimension::Duration, 1000.0),
+        "min" | "mins" | "minute" | "minutes" => Unit::linear(Dimension::Duration, 60_000.0),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Unit::linear(Dimension::Duration, 3_600_000.0),
+        "%" | "percent" | "pct" => Unit::linear(Dimension::Fraction, 1.0),
+        "fraction" | "ratio" => Unit::linear(Dimension::Fraction, 100.0),
+        "mg/dl" | "mgdl" | "mg_dl" => Unit::linear(Dimension::Concentration, 1.0),
+        "mmol/l" | "mmol" | "mmoll" | "mmol_l" => Unit::linear(Dimension::Concentration, GLUCOSE_MG_DL_PER_MMOL_L),
+        "°c" | "c" | "degc" | "celsius" | "℃" => Unit::linear(Dimension::Temperature, 1.0),
+        "°f" | "f" | "degf" | "fahrenheit" | "℉" => Unit {
+            dimension: Dimension::Temperature,
+            scale: 5.0 / 9.0,
+            offset: -32.0 * 5.0 / 9.0,
+        },
+        "k" | "kelvin" => Unit {
+            dimension: Dimension::Temperature,
+            scale: 1.0,
+            offset: -273.15,
+        },
+        "breaths/min" | "brpm" | "breathsperminute" | "rpm" => Unit::linear(Dimension::BreathingRate, 1.0),
+        "steps" | "step" | "count" => Unit::linear(Dimension::Count, 1.0),
+        "microsiemens" => Unit::linear(Dimension::Conductance, 1.0),
+        "nanosiemens" => Unit::linear(Dimension::Conductance, 0.001),
+        "mmhg" => Unit::linear(Dimension::Pressure, 1.0),
+        "kpa" => Unit::linear(Dimension::Pressure, MMHG_PER_KPA),
+        "ml/kg/min" | "mlkgmin" | "ml/min/kg" => Unit::linear(Dimension::OxygenUptake, 1.0),
+        _ => return None,
+    };
+    Some(unit)
+}
+
+/// Abbreviated conductance units. Once lowercased they read the same as micro-
+/// and nanoseconds, so they are only recognised on EDA readings.
+fn parse_conductance_abbreviation(label: &str) -> Option<Unit> {
+    match label.trim().to_lowercase().replace(' ', "").as_str() {
+        "µs" | "μs" | "us" => Some(Unit::linear(Dimension::Conductance, 1.0)),
+        "ns" => Some(Unit::linear(Dimension::Conductance, 0.001)),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnitError {
+    UnknownUnit(String),
+    IncompatibleUnit { unit: String, expected: &'static str },
+    UnknownMetric(String),
+    UnknownPeriod(String),
+}
+
+impl fmt::Display for UnitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnitError::UnknownUnit(unit) => write!(f, "unknown unit '{}'", unit),
+            UnitError::IncompatibleUnit { unit, expected } => {
+                write!(f, "unit '{}' is incompatible with this measurement (expected {})", unit, expected)
+            }
+            UnitError::UnknownMetric(metric) => write!(f, "unknown HRV metric '{}'", metric),
+            UnitError::UnknownPeriod(period) => write!(f, "unknown aggregation period '{}'", period),
+        }
+    }
+}
+
+/// A measurement that could not be normalized and was left out of validation
+#[derive(Debug, Clone, Serialize)]
+pub struct MeasurementDiagnostic {
+    pub event_id: String,
+    pub event_type: String,
+    pub unit: String,
+    pub message: String,
+}
+
+/// A reading converted to the canonical unit of its measurement type
+#[derive(Debug, Clone)]
+pub struct NormalizedReading {
+    pub value: f64,
+    pub unit: &'static str,
+    pub components: HashMap<String, f64>,
+    // Aggregation period for counts (steps); 24h for daily totals and for
+    // longer totals, which are divided down to a daily figure
+    pub period_hours: Option<f64>,
+}
+
+/// Canonical unit of a measurement type and the dimension it must have
+fn canonical_dimension(measurement_type: &MeasurementType) -> Dimension {
+    match measurement_type {
+        MeasurementType::HeartRate | MeasurementType::RestingHeartRate => Dimension::HeartRate,
+        MeasurementType::HeartRateVariability | MeasurementType::SleepStages => Dimension::Duration,
+        MeasurementType::BloodOxygen => Dimension::Fraction,
+        MeasurementType::BloodGlucose => Dimension::Concentration,
+        MeasurementType::BodyTemperature | MeasurementType::SkinTemperature => Dimension::Temperature,
+        MeasurementType::RespiratoryRate => Dimension::BreathingRate,
+        MeasurementType::Steps => Dimension::Count,
+        MeasurementType::ElectrodermalActivity => Dimension::Conductance,
+        MeasurementType::BloodPressure => Dimension::Pressure,
+        MeasurementType::Vo2Max => Dimension::OxygenUptake,
+    }
+}
+
+/// Scale from the dimension's canonical unit to the measurement's canonical unit
+/// (durations are parsed in ms, but sleep stages are reported in minutes)
+fn dimension_to_measurement_scale(measurement_type: &MeasurementType) -> f64 {
+    match measurement_type {
+        MeasurementType::SleepStages => 1.0 / 60_000.0,
+        _ => 1.0,
+    }
+}
+
+/// Convert a raw reading to the canonical unit of its measurement type.
+///
+/// An empty unit is taken to mean the canonical unit. Unknown units and
+/// units of the wrong dimension are rejected rather than guessed at.
+pub fn normalize_reading(
+    measurement_type: &MeasurementType,
+    value: f64,
+    unit_label: &str,
+    components: &HashMap<String, f64>,
+    properties: &HashMap<String, Value>,
+) -> Result<NormalizedReading, UnitError> {
+    let canonical = measurement_type.default_unit();
+    let label = if unit_label.trim().is_empty() { canonical } else { unit_label };
+
+    // Log-transformed HRV is reported without a meaningful unit
+    let metric = properties.get("metric")
+        .and_then(|v| v.as_str())
+        .map(|m| m.trim().to_lowercase());
+    let is_log_hrv = *measurement_type == MeasurementType::HeartRateVariability
+        && matches!(metric.as_deref(), Some("ln_rmssd") | Some("lnrmssd"));
+
+    let unit = if is_log_hrv {
+        Some(Unit::linear(Dimension::Duration, 1.0))
+    } else if *measurement_type == MeasurementType::ElectrodermalActivity {
+        parse_conductance_abbreviation(label).or_else(|| parse_unit(label))
+    } else {
+        parse_unit(label)
+    };
+    let unit = unit.ok_or_else(|| UnitError::UnknownUnit(label.to_string()))?;
+
+    if unit.dimension != canonical_dimension(measurement_type) {
+        return Err(UnitError::IncompatibleUnit {
+            unit: label.to_string(),
+            expected: canonical,
+        });
+    }
+
+    let measurement_scale = dimension_to_measurement_scale(measurement_type);
+    let mut normalized_value = match measurement_type {
+        // Skin temperature is a deviation from baseline, so only the scale applies
+        MeasurementType::SkinTemperature => unit.convert_delta(value),
+        _ => unit.convert(value) * measurement_scale,
+    };
+
+    if *measurement_type == MeasurementType::HeartRateVariability {
+        normalized_value = match metric.as_deref() {
+            None | Some("rmssd") => normalized_value,
+            Some("sdnn") => normalized_value * RMSSD_PER_SDNN,
+            Some("ln_rmssd") | Some("lnrmssd") => value.exp(),
+            Some(other) => return Err(UnitError::UnknownMetric(other.to_string())),
+        };
+    }
+
+    // Secondary readings share the unit of the headline value
+    let normalized_components = match measurement_type {
+        MeasurementType::BloodPressure | MeasurementType::SleepStages => components.iter()
+            .map(|(key, &v)| {
+                let shares_unit = matches!(key.as_str(), "systolic" | "diastolic") || key.ends_with("_minutes");
+                let converted = if shares_unit { unit.convert(v) * measurement_scale } else { v };
+                (key.clone(), converted)
+            })
+            .collect(),
+        _ => components.clone(),
+    };
+
+    let mut period_hours = None;
+    if *measurement_type == MeasurementType::Steps {
+        let hours = parse_period_hours(properties)?;
+        // Multi-day totals (e.g. a weekly count) become the mean daily count
+        if hours > 24.0 {
+            normalized_value *= 24.0 / hours;
+        }
+        period_hours = Some(hours.min(24.0));
+    }
+
+    Ok(NormalizedReading {
+        value: normalized_value,
+        unit: canonical,
+        components: normalized_components,
+        period_hours,
+    })
+}
+
+/// Aggregation period of a count, from `period` ("day", "hour", ...) or `period_minutes`
+fn parse_period_hours(properties: &HashMap<String, Value>) -> Result<f64, UnitError> {
+    if let Some(minutes) = properties.get("period_minutes").and_then(|v| v.as_f64()) {
+        return Ok(minutes / 60.0);
+    }
+
+    let Some(period) = properties.get("period").and_then(|v| v.as_str()) else {
+        return Ok(24.0);
+    };
+
+    match period.trim().to_lowercase().as_str() {
+        "week" | "weekly" | "7d" => Ok(168.0),
+        "day" | "daily" | "24h" => Ok(24.0),
+        "hour" | "hourly" | "1h" => Ok(1.0),
+        "15min" | "quarter_hour" => Ok(0.25),
+        "minute" | "1min" => Ok(1.0 / 60.0),
+        other => Err(UnitError::UnknownPeriod(other.to_string())),
+    }
+}